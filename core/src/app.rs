@@ -8,13 +8,16 @@ use eframe::egui::{self};
 use glam::Mat4;
 use gpu_picking::GPUPicking;
 use wgpu::{PollStatus, PollType};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::{
     config::Config,
     ecs::{
-        animation::{animation_system, AnimationPlayer, BoneMatrices, AnimatedInstance},
+        animation::{animation_system, AnimationPlayer, BoneMatrices, AnimatedInstance, MorphWeights},
         camera::{Camera, OrbitCamera, camera_control_system, update_camera_transform_system},
+        prefab::spawn_prefab,
+        scene::spawn_scene,
         time::{Time, time_system},
         input::{Input, keyboard_input_system},
         ui::{EguiCtx, LastSize, UiState, ui_system},
@@ -160,11 +163,12 @@ impl Custom3d {
         
   
 
+        // Clips are keyed by the model they were baked with
         let animations = [
-            "Armature|running|baselayer",
-            "Armature|walking_man|baselayer", 
-            "Armature|RunFast|baselayer",
-            "Armature|Axe_Spin_Attack|baselayer",
+            "Animation_Running_withSkin/Armature|running|baselayer",
+            "Animation_Walking_withSkin/Armature|walking_man|baselayer",
+            "Animation_RunFast_withSkin/Armature|RunFast|baselayer",
+            "Animation_Axe_Spin_Attack_withSkin/Armature|Axe_Spin_Attack|baselayer",
         ];
 
         // Debug: Print available animated models
@@ -212,8 +216,9 @@ impl Custom3d {
                     ..Default::default()
                 },
                 BoneMatrices {
-                    matrices: vec![Mat4::IDENTITY; 256],
+                    matrices: Vec::new(),
                 },
+                MorphWeights::default(),
                 transform,
                 GlobalTransform::default(),
             ));
@@ -221,6 +226,35 @@ impl Custom3d {
 
         log::info!("[App] Spawned {} animated test instances.", animations.len());
 
+        // Spawn every static model as its prefab, laid out in a row
+        let static_models = world.resource::<AssetServer>().meshlet_manager.models.clone();
+        let mut prefab_roots = HashMap::new();
+        for model in &static_models {
+            let root = spawn_prefab(&mut world, model, Transform::from_matrix(model.placement));
+            prefab_roots.insert(model.name.clone(), (root, model.placement));
+        }
+        log::info!("[App] Spawned {} static prefabs.", static_models.len());
+
+        // Spawn authored cameras and lights with the prefab of their source, and start the
+        // viewport at the first camera
+        let scenes = world.resource::<AssetServer>().scenes.clone();
+        for scene in &scenes {
+            spawn_scene(&mut world, scene, prefab_roots.get(&scene.name).map(|(root, _)| *root));
+        }
+        if let Some((scene, camera)) = scenes
+            .iter()
+            .find_map(|scene| scene.cameras.first().map(|camera| (scene, camera)))
+        {
+            let placement = prefab_roots.get(&scene.name).map_or(Mat4::IDENTITY, |(_, placement)| *placement);
+            let transform = placement * Mat4::from_rotation_translation(camera.rotation, camera.translation);
+            let (_, rotation, translation) = transform.to_scale_rotation_translation();
+            world.resource_mut::<OrbitCamera>().look_from(translation, rotation);
+            if let Ok(mut viewport_camera) = world.query::<&mut Camera>().single_mut(&mut world) {
+                viewport_camera.apply_projection(&camera.projection);
+            }
+            log::info!("[App] Starting at camera '{}' of '{}'", camera.name, scene.name);
+        }
+
 
         // --- Main Update Schedule ---
         let mut update_schedule = Schedule::new(Update);
@@ -1,9 +1,11 @@
+use std::collections::HashMap;
+
 use bevy_ecs::prelude::*;
 use bevy_transform::components::GlobalTransform;
 use glam::{Mat4, Quat, Vec3};
 use crate::ecs::time::Time;
 use crate::renderer::assets::AssetServer;
-use types::{Animation, PositionKey, RotationKey, ScaleKey};
+use types::{Animation, sample_position, sample_quantized_rotation, sample_rotation, sample_scale, sample_weights};
 use log;
 
 #[derive(Component)]
@@ -45,17 +47,42 @@ pub struct AnimatedInstance {
     pub model_name: String,
 }
 
+/// Current morph target weights of an instance, by the source node whose meshes they drive.
+/// Instances without this component render their morph targets at the baked default weights.
+#[derive(Component, Default)]
+pub struct MorphWeights {
+    pub weights: HashMap<String, Vec<f32>>,
+}
+
 pub fn animation_system(
     time: Res<Time>,
     asset_server: Res<AssetServer>,
-    mut query: Query<(&mut AnimationPlayer, &mut BoneMatrices, &AnimatedInstance, &GlobalTransform)>,
+    mut query: Query<(
+        &mut AnimationPlayer,
+        &mut BoneMatrices,
+        Option<&mut MorphWeights>,
+        &AnimatedInstance,
+        &GlobalTransform,
+    )>,
 ) {
-    for (mut player, mut bone_matrices, instance, transform) in query.iter_mut() {
+    for (mut player, mut bone_matrices, morph_weights, instance, transform) in query.iter_mut() {
         if !player.playing {
             continue;
         }
         log::debug!("[Animation] Processing: {} transform: {:?}", player.animation_name, transform.translation());
 
+        let animations = &asset_server.animated_meshlet_manager;
+
+        // Never blend into a clip authored for a different skeleton
+        if let Some(next_anim_name) = &player.next_animation
+            && !animations.is_compatible(&instance.model_name, next_anim_name)
+        {
+            log::warn!("[Animation] -> '{}' does not fit model '{}', ignoring", next_anim_name, instance.model_name);
+            player.next_animation = None;
+            player.blend_factor = 0.0;
+            player.next_time = 0.0;
+        }
+
         // Handle animation blending
         let next_anim_name = player.next_animation.clone();
         if let Some(next_anim_name) = &next_anim_name {
@@ -78,12 +105,16 @@ pub fn animation_system(
         }
 
         // Get current animation
-        let current_animation = if let Some(anim) = asset_server.animated_meshlet_manager.animations.get(&player.animation_name) {
+        let current_animation = if let Some(anim) = animations.animations.get(&player.animation_name) {
             anim
         } else {
             log::warn!("[Animation] -> WARNING: No animation found for '{}'", player.animation_name);
             continue;
         };
+        if !animations.is_compatible(&instance.model_name, &player.animation_name) {
+            log::warn!("[Animation] -> WARNING: '{}' does not fit model '{}'", player.animation_name, instance.model_name);
+            continue;
+        }
 
         // Update current animation time
         let old_time = player.current_time;
@@ -153,7 +184,7 @@ pub fn animation_system(
             }
 
             // Calculate final skinning matrices with world transform applied
-            bone_matrices.matrices.resize(256, Mat4::IDENTITY);
+            bone_matrices.matrices.resize(skeleton.bones.len(), Mat4::IDENTITY);
             for (i, bone) in skeleton.bones.iter().enumerate() {
                 bone_matrices.matrices[i] = model_matrix * final_global_poses[i] * bone.inverse_bind_pose;
                 
@@ -169,6 +200,34 @@ pub fn animation_system(
         } else {
             log::warn!("[Animation] -> WARNING: No skeleton found for model '{}'", instance.model_name);
         }
+
+        // Morph weights start from the baked defaults, so nodes a clip does not animate rest.
+        if let Some(mut morph_weights) = morph_weights {
+            let defaults = animations.default_morph_weights(&instance.model_name);
+            let mut weights = defaults.clone();
+            apply_morph_channels(current_animation, current_animation_time_in_ticks, &mut weights);
+            if let Some(next_animation) = player.next_animation.as_ref().and_then(|name| animations.animations.get(name)) {
+                let mut next_weights = defaults;
+                apply_morph_channels(next_animation, player.next_time * next_animation.ticks_per_second, &mut next_weights);
+                for (node_name, current) in &mut weights {
+                    if let Some(next) = next_weights.get(node_name) {
+                        for (weight, next) in current.iter_mut().zip(next) {
+                            *weight += (next - *weight) * player.blend_factor;
+                        }
+                    }
+                }
+            }
+            morph_weights.weights = weights;
+        }
+    }
+}
+
+/// Overwrites the weights of every node `animation` has a weights channel for.
+fn apply_morph_channels(animation: &Animation, time_in_ticks: f64, weights: &mut HashMap<String, Vec<f32>>) {
+    for channel in &animation.morph_channels {
+        if let Some(values) = sample_weights(time_in_ticks, &channel.keys) {
+            weights.insert(channel.node_name.clone(), values);
+        }
     }
 }
 
@@ -176,9 +235,18 @@ fn calculate_bone_transform(animation: &Animation, bone_name: &str, time_in_tick
     // Find the channel for the given bone
     if let Some(channel) = animation.channels.iter().find(|c| c.bone_name == bone_name) {
         // Interpolate position, rotation, and scale
-        let position = find_interpolated_position(time_in_ticks, &channel.position_keys).unwrap_or(Vec3::ZERO);
-        let rotation = find_interpolated_rotation(time_in_ticks, &channel.rotation_keys).unwrap_or(Quat::IDENTITY);
-        let scale = find_interpolated_scale(time_in_ticks, &channel.scale_keys).unwrap_or(Vec3::ONE);
+        let position = sample_position(time_in_ticks, &channel.position_keys).unwrap_or(Vec3::ZERO);
+        let rotation = match &channel.quantized_rotation_keys {
+            Some(keys) => sample_quantized_rotation(time_in_ticks, keys),
+            None => sample_rotation(time_in_ticks, &channel.rotation_keys),
+        }
+        .unwrap_or(Quat::IDENTITY);
+        let scale = sample_scale(time_in_ticks, &channel.scale_keys).unwrap_or(Vec3::ONE);
+
+        // **LOGGING**: Check for near-zero scale values, which cause the "crushed into a ball" effect.
+        if scale.length_squared() < 0.01 { // 0.1 * 0.1
+            log::warn!("[Scale Interp] WARNING: Scale is near zero ({:.3}, {:.3}, {:.3}) at t={:.2}. Mesh will be crushed!", scale.x, scale.y, scale.z, time_in_ticks);
+        }
 
         let transform = Mat4::from_scale_rotation_translation(scale, rotation, position);
         
@@ -200,267 +268,6 @@ fn calculate_bone_transform(animation: &Animation, bone_name: &str, time_in_tick
     }
 }
 
-fn find_interpolated_position(time_in_ticks: f64, keys: &[PositionKey]) -> Option<Vec3> {
-    if keys.is_empty() {
-        return None;
-    }
-    if keys.len() == 1 {
-        return Some(keys[0].position);
-    }
-
-    // Handle looping
-    if time_in_ticks >= keys.last().unwrap().time {
-        let last_key = keys.last().unwrap();
-        let first_key = &keys[0];
-        
-        let time_past_end = time_in_ticks - last_key.time;
-        let total_time = first_key.time + (keys.last().unwrap().time - last_key.time);
-        let interpolation_factor = if total_time > 0.0 {
-            time_past_end / total_time
-        } else {
-            0.0
-        };
-        
-        return Some(last_key.position.lerp(first_key.position, interpolation_factor.min(1.0) as f32));
-    }
-
-    let Some(next_key_index) = keys.iter().position(|k| k.time >= time_in_ticks) else {
-        return Some(keys.last().unwrap().position);
-    };
-
-    if next_key_index == 0 {
-        return Some(keys[0].position);
-    }
-
-    let prev_key = &keys[next_key_index - 1];
-    let next_key = &keys[next_key_index];
-
-    let total_time = next_key.time - prev_key.time;
-    let interpolation_factor = if total_time > 0.0 {
-        (time_in_ticks - prev_key.time) / total_time
-    } else {
-        0.0
-    };
-
-    let result = prev_key.position.lerp(next_key.position, interpolation_factor as f32);
-    
-    // Log interpolation details for debugging
-    if keys.len() > 1 && (prev_key.position - next_key.position).length() > 0.1 {
-        log::debug!("[Interpolation] Position: t={:.3}, factor={:.3}, prev=[{:.3}, {:.3}, {:.3}], next=[{:.3}, {:.3}, {:.3}], result=[{:.3}, {:.3}, {:.3}]", 
-            time_in_ticks, interpolation_factor,
-            prev_key.position.x, prev_key.position.y, prev_key.position.z,
-            next_key.position.x, next_key.position.y, next_key.position.z,
-            result.x, result.y, result.z);
-    }
-
-    Some(result)
-}
-
-fn find_interpolated_rotation(time_in_ticks: f64, keys: &[RotationKey]) -> Option<Quat> {
-    if keys.is_empty() {
-        return None;
-    }
-    if keys.len() == 1 {
-        return Some(keys[0].rotation);
-    }
-    
-    // Handle looping: if we're past the last keyframe, interpolate between last and first
-    if time_in_ticks >= keys.last().unwrap().time {
-        let last_key = keys.last().unwrap();
-        let first_key = &keys[0];
-        
-        // Calculate how far past the last keyframe we are
-        let time_past_end = time_in_ticks - last_key.time;
-        
-        // Assume the "virtual" next keyframe is at duration + first_key.time
-        // This creates a smooth loop
-        let total_time = first_key.time + (keys.last().unwrap().time - last_key.time);
-        let interpolation_factor = if total_time > 0.0 {
-            time_past_end / total_time
-        } else {
-            0.0
-        };
-        
-        // IMPORTANT: Check for quaternion flip
-        let mut target_rotation = first_key.rotation;
-        let dot = last_key.rotation.dot(target_rotation);
-        if dot < 0.0 {
-            target_rotation = -target_rotation;
-            log::warn!("[Quat Interp] WARNING: Negative dot product ({:.3}) between loop keyframes at t={:.2} and t={:.2}. Animation will flip!", dot, last_key.time, first_key.time);
-        }
-        
-        return Some(last_key.rotation.slerp(target_rotation, interpolation_factor.min(1.0) as f32));
-    }
-    
-    let Some(next_key_index) = keys.iter().position(|k| k.time >= time_in_ticks) else {
-        return Some(keys.last().unwrap().rotation);
-    };
-
-    if next_key_index == 0 {
-        return Some(keys[0].rotation);
-    }
-
-    let prev_key = &keys[next_key_index - 1];
-    let next_key = &keys[next_key_index];
-
-    let total_time = next_key.time - prev_key.time;
-    let interpolation_factor = if total_time > 0.0 {
-        (time_in_ticks - prev_key.time) / total_time
-    } else {
-        0.0
-    };
-
-    // **FIX QUATERNION FLIPS**: Check the dot product. A negative value means the slerp will take the "long way around", causing a flip.
-    // Instead of negating, use a smoother transition to avoid creating very small rotation values
-    let next_quat_for_interp = next_key.rotation;
-    let dot = prev_key.rotation.dot(next_quat_for_interp);
-    if dot < 0.0 {
-        // Instead of negating, use a smoother interpolation that avoids the flip
-        log::warn!("[Quat Interp] DETECTED: Negative dot product ({:.3}) between keyframes at t={:.2} and t={:.2}. Using smooth transition!", dot, prev_key.time, next_key.time);
-        
-        // Use a step function to avoid the flip entirely
-        if interpolation_factor < 0.5 {
-            return Some(prev_key.rotation);
-        } else {
-            return Some(next_key.rotation);
-        }
-    }
-    
-    // **ADDITIONAL LOGGING**: Check for identity quaternions which can cause "crushing" effects
-    let prev_is_identity = prev_key.rotation.length_squared() < 0.01;
-    let next_is_identity = next_key.rotation.length_squared() < 0.01;
-    if prev_is_identity || next_is_identity {
-        log::warn!("[Quat Interp] WARNING: Identity quaternion detected! prev_is_identity={}, next_is_identity={} at t={:.2} and t={:.2}", 
-            prev_is_identity, next_is_identity, prev_key.time, next_key.time);
-    }
-    
-    // **DEFENSIVE MEASURES**: Handle edge cases that might cause issues
-    let mut prev_quat = prev_key.rotation;
-    let mut next_quat = next_quat_for_interp; // Use the corrected quaternion
-    
-    // Normalize quaternions to prevent issues
-    if (prev_quat.length_squared() - 1.0).abs() > 1e-4 {
-        prev_quat = prev_quat.normalize();
-    }
-    if (next_quat.length_squared() - 1.0).abs() > 1e-4 {
-        next_quat = next_quat.normalize();
-    }
-    
-    // Handle very small quaternions by replacing with identity
-    if prev_quat.length_squared() < 0.01 {
-        prev_quat = Quat::IDENTITY;
-    }
-    if next_quat.length_squared() < 0.01 {
-        next_quat = Quat::IDENTITY;
-    }
-
-    let result = prev_quat.slerp(next_quat, interpolation_factor as f32);
-    
-    // Log interpolation details for debugging
-    if keys.len() > 1 && (prev_key.rotation.xyz() - next_key.rotation.xyz()).length() > 0.1 {
-        log::debug!("[Interpolation] Rotation: t={:.3}, factor={:.3}, prev=[{:.3}, {:.3}, {:.3}, {:.3}], next=[{:.3}, {:.3}, {:.3}, {:.3}], result=[{:.3}, {:.3}, {:.3}, {:.3}]", 
-            time_in_ticks, interpolation_factor,
-            prev_key.rotation.x, prev_key.rotation.y, prev_key.rotation.z, prev_key.rotation.w,
-            next_key.rotation.x, next_key.rotation.y, next_key.rotation.z, next_key.rotation.w,
-            result.x, result.y, result.z, result.w);
-    }
-    
-    // **DEFENSIVE INTERPOLATION**: Handle large rotation differences gracefully
-    let angle_diff = prev_key.rotation.angle_between(next_key.rotation);
-    if angle_diff > 2.0 { // More than ~115 degrees
-        log::warn!("[Quat Interp] WARNING: Large rotation difference ({:.1}°) between keyframes at t={:.2} and t={:.2}. Using defensive interpolation!", 
-            angle_diff.to_degrees(), prev_key.time, next_key.time);
-        
-        // For large rotations, use a step function to avoid sudden flips
-        // This is a common technique in animation systems
-        if interpolation_factor < 0.5 {
-            // Use the previous keyframe for the first half
-            return Some(prev_quat);
-        } else {
-            // Use the next keyframe for the second half
-            return Some(next_quat);
-        }
-    } else if angle_diff > 1.0 { // More than ~57 degrees
-        // For medium rotations, use a smoother transition but still avoid slerp
-        // Use a cubic interpolation on the rotation axis
-        let t = interpolation_factor as f32;
-        let smooth_t = t * t * (3.0 - 2.0 * t); // Smoothstep
-        return Some(prev_quat.slerp(next_quat, smooth_t));
-    }
-
-    Some(result)
-}
-
-fn find_interpolated_scale(time_in_ticks: f64, keys: &[ScaleKey]) -> Option<Vec3> {
-    if keys.is_empty() {
-        return None;
-    }
-    if keys.len() == 1 {
-        return Some(keys[0].scale);
-    }
-
-    // Handle looping
-    if time_in_ticks >= keys.last().unwrap().time {
-        let last_key = keys.last().unwrap();
-        let first_key = &keys[0];
-        
-        let time_past_end = time_in_ticks - last_key.time;
-        let total_time = first_key.time + (keys.last().unwrap().time - last_key.time);
-        let interpolation_factor = if total_time > 0.0 {
-            time_past_end / total_time
-        } else {
-            0.0
-        };
-        
-        return Some(last_key.scale.lerp(first_key.scale, interpolation_factor.min(1.0) as f32));
-    }
-
-    let Some(next_key_index) = keys.iter().position(|k| k.time >= time_in_ticks) else {
-        return Some(keys.last().unwrap().scale);
-    };
-    
-    if next_key_index == 0 {
-        return Some(keys[0].scale);
-    }
-
-    let prev_key = &keys[next_key_index - 1];
-    let next_key = &keys[next_key_index];
-    
-    let total_time = next_key.time - prev_key.time;
-    let interpolation_factor = if total_time > 0.0 {
-        (time_in_ticks - prev_key.time) / total_time
-    } else {
-        0.0
-    };
-
-    let result = prev_key.scale.lerp(next_key.scale, interpolation_factor as f32);
-    
-    // **LOGGING**: Check for near-zero scale values, which cause the "crushed into a ball" effect.
-    if result.length_squared() < 0.01 { // 0.1 * 0.1
-        log::warn!("[Scale Interp] WARNING: Scale is near zero ({:.3}, {:.3}, {:.3}) at t={:.2}. Mesh will be crushed!", result.x, result.y, result.z, time_in_ticks);
-    }
-    
-    // **DEBUGGING**: Log all scale interpolations to see what's happening
-    if keys.len() > 1 {
-        log::debug!("[Scale Interp] t={:.3}, factor={:.3}, prev=[{:.3}, {:.3}, {:.3}], next=[{:.3}, {:.3}, {:.3}], result=[{:.3}, {:.3}, {:.3}]", 
-            time_in_ticks, interpolation_factor,
-            prev_key.scale.x, prev_key.scale.y, prev_key.scale.z,
-            next_key.scale.x, next_key.scale.y, next_key.scale.z,
-            result.x, result.y, result.z);
-    }
-    
-    // Log interpolation details for debugging
-    if keys.len() > 1 && (prev_key.scale - next_key.scale).length() > 0.1 {
-        log::debug!("[Interpolation] Scale: t={:.3}, factor={:.3}, prev=[{:.3}, {:.3}, {:.3}], next=[{:.3}, {:.3}, {:.3}], result=[{:.3}, {:.3}, {:.3}]", 
-            time_in_ticks, interpolation_factor,
-            prev_key.scale.x, prev_key.scale.y, prev_key.scale.z,
-            next_key.scale.x, next_key.scale.y, next_key.scale.z,
-            result.x, result.y, result.z);
-    }
-
-    Some(result)
-}
-
 /// Blend between two bone poses using linear interpolation for position/scale and spherical linear interpolation for rotation
 fn blend_poses(pose1: Mat4, pose2: Mat4, factor: f32) -> Mat4 {
     // Decompose matrices into translation, rotation, and scale
@@ -497,4 +304,4 @@ fn decompose_matrix(matrix: Mat4) -> (Vec3, Quat, Vec3) {
     let rotation = Quat::from_mat4(&rotation_matrix);
     
     (translation, rotation, scale)
-}
\ No newline at end of file
+}
@@ -6,6 +6,7 @@ use bevy_ecs::prelude::*;
 use bevy_transform::components::Transform;
 use glam::{Mat4, Quat, Vec3};
 use eframe::egui::Key;
+use types::Projection;
 
 #[derive(Component)]
 pub struct Camera {
@@ -37,6 +38,22 @@ impl Camera {
         // Apply the correction to the projection matrix.
         y_flip * projection
     }
+
+    /// Takes the field of view and clip planes of an authored camera. The aspect ratio keeps
+    /// following the viewport, and orthographic cameras only contribute their clip planes.
+    pub fn apply_projection(&mut self, projection: &Projection) {
+        match *projection {
+            Projection::Perspective { yfov, znear, zfar, .. } => {
+                self.fovy = yfov;
+                self.znear = znear;
+                self.zfar = zfar.unwrap_or(Camera::default().zfar);
+            }
+            Projection::Orthographic { znear, zfar, .. } => {
+                self.znear = znear;
+                self.zfar = zfar;
+            }
+        }
+    }
 }
 
 #[derive(Resource)]
@@ -60,6 +77,21 @@ impl Default for OrbitCamera {
     }
 }
 
+impl OrbitCamera {
+    /// Orbits so the view matches a camera at `position` looking down its local -Z axis,
+    /// keeping the current distance. Roll is dropped.
+    pub fn look_from(&mut self, position: Vec3, rotation: Quat) {
+        let back = rotation * Vec3::Z;
+        self.yaw = back.x.atan2(back.z);
+        self.pitch = (-back.y)
+            .asin()
+            .clamp(-std::f32::consts::FRAC_PI_2 + 0.01, std::f32::consts::FRAC_PI_2 - 0.01);
+        let rotation = Quat::from_rotation_y(self.yaw) * Quat::from_rotation_x(self.pitch);
+        self.target = position - rotation * (Vec3::Z * self.distance);
+        self.pan = Vec3::ZERO;
+    }
+}
+
 pub fn update_camera_transform_system(
     mut query: Query<&mut Transform, With<Camera>>,
     orbit_camera: Res<OrbitCamera>,
@@ -3,4 +3,6 @@ pub mod camera;
 pub mod time;
 pub mod input;
 pub mod animation;
+pub mod prefab;
+pub mod scene;
 pub mod ui;
@@ -0,0 +1,49 @@
+use bevy_ecs::prelude::*;
+use bevy_transform::components::{GlobalTransform, Transform};
+
+use crate::renderer::assets::static_meshlet::StaticModel;
+
+/// Static meshes drawn at the entity's `GlobalTransform`, by id in the `MeshletManager`.
+#[derive(Component)]
+pub struct StaticInstance {
+    pub meshes: Vec<u32>,
+}
+
+/// Spawns the prefab of `model` as an entity tree, one entity per node, parented under a root
+/// named after the model and placed by `transform`. `propagate_parent_transforms` places the
+/// nodes from there, so each can be moved on its own. Returns the root.
+pub fn spawn_prefab(world: &mut World, model: &StaticModel, transform: Transform) -> Entity {
+    let root = world
+        .spawn((
+            Name::new(model.name.clone()),
+            transform,
+            GlobalTransform::default(),
+        ))
+        .id();
+
+    // Parents come before their children, so each parent entity already exists.
+    let mut entities: Vec<Entity> = Vec::with_capacity(model.prefab.nodes.len());
+    for node in &model.prefab.nodes {
+        let parent = node.parent_index.map_or(root, |index| entities[index]);
+        let mut entity = world.spawn((
+            Name::new(node.name.clone()),
+            Transform {
+                translation: node.translation,
+                rotation: node.rotation,
+                scale: node.scale,
+            },
+            GlobalTransform::default(),
+            ChildOf(parent),
+        ));
+        let meshes: Vec<u32> = node
+            .meshes
+            .iter()
+            .filter_map(|&index| model.mesh_ids.get(index).copied().flatten())
+            .collect();
+        if !meshes.is_empty() {
+            entity.insert(StaticInstance { meshes });
+        }
+        entities.push(entity.id());
+    }
+    root
+}
@@ -0,0 +1,55 @@
+use bevy_ecs::prelude::*;
+use bevy_transform::components::{GlobalTransform, Transform};
+use glam::Vec3;
+use types::{LightKind, Projection, Scene};
+
+/// A camera authored in a model source. The viewport can jump to it; it does not render on
+/// its own.
+#[derive(Component)]
+pub struct AuthoredCamera {
+    pub projection: Projection,
+}
+
+/// A punctual light authored in a model source.
+#[derive(Component)]
+pub struct Light {
+    pub color: Vec3,
+    pub intensity: f32,
+    pub range: Option<f32>,
+    pub kind: LightKind,
+}
+
+/// Spawns the cameras and lights of `scene`, one entity each. Their transforms are in the
+/// source's space, so they go under `parent` when the source's prefab was spawned, and follow
+/// it from there.
+pub fn spawn_scene(world: &mut World, scene: &Scene, parent: Option<Entity>) {
+    for camera in &scene.cameras {
+        let mut entity = world.spawn((
+            Name::new(camera.name.clone()),
+            AuthoredCamera {
+                projection: camera.projection,
+            },
+            Transform::from_translation(camera.translation).with_rotation(camera.rotation),
+            GlobalTransform::default(),
+        ));
+        if let Some(parent) = parent {
+            entity.insert(ChildOf(parent));
+        }
+    }
+    for light in &scene.lights {
+        let mut entity = world.spawn((
+            Name::new(light.name.clone()),
+            Light {
+                color: light.color,
+                intensity: light.intensity,
+                range: light.range,
+                kind: light.kind,
+            },
+            Transform::from_translation(light.translation).with_rotation(light.rotation),
+            GlobalTransform::default(),
+        ));
+        if let Some(parent) = parent {
+            entity.insert(ChildOf(parent));
+        }
+    }
+}
@@ -1,14 +1,16 @@
 use bevy_derive::Deref;
 use bevy_ecs::prelude::*;
 use bevy_ecs::system::SystemParam;
+use bevy_transform::components::GlobalTransform;
 use eframe::egui;
 use log;
 
 use crate::{
     config::Config,
     ecs::{
-        animation::AnimationPlayer,
+        animation::{AnimatedInstance, AnimationPlayer},
         camera::{Camera, OrbitCamera},
+        scene::{AuthoredCamera, Light},
         // commands::{DespawnInstance, SpawnInstance},
         time::Time,
         // model::SpawnedEntities,
@@ -55,10 +57,13 @@ pub struct UiSystemParams<'w, 's> {
     config: ResMut<'w, Config>,
     time: Res<'w, Time>,
     events: EventWriter<'w, ResizeEvent>,
-    orbit_camera: Res<'w, OrbitCamera>,
-    camera_query: Query<'w, 's, &'static Camera>,
+    orbit_camera: ResMut<'w, OrbitCamera>,
+    camera_query: Query<'w, 's, &'static mut Camera>,
+    // --- For Authored Cameras and Lights ---
+    authored_camera_query: Query<'w, 's, (&'static Name, &'static AuthoredCamera, &'static GlobalTransform)>,
+    light_query: Query<'w, 's, (&'static Name, &'static Light)>,
     // --- For Animation Control ---
-    animation_player_query: Query<'w, 's, &'static mut AnimationPlayer>,
+    animation_player_query: Query<'w, 's, (&'static mut AnimationPlayer, &'static AnimatedInstance)>,
     // --- For Spawner ---
     commands: Commands<'w, 's>,
     asset_server: Res<'w, AssetServer>,
@@ -97,23 +102,24 @@ pub fn ui_system(mut p: UiSystemParams) {
     // Add Animation Control Window
     egui::Window::new("Animation Control").show(ctx, |ui| {
         let mut animation_players: Vec<_> = p.animation_player_query.iter_mut().collect();
-        let available_animations: Vec<String> = p.asset_server.animated_meshlet_manager.animations.keys().cloned().collect();
         
         if animation_players.is_empty() {
             ui.label("No animated entities found.");
         } else {
             ui.label(format!("Found {} animated entities:", animation_players.len()));
             
-            for (i, player) in animation_players.iter_mut().enumerate() {
+            for (i, (player, instance)) in animation_players.iter_mut().enumerate() {
+                // Only offer clips authored for a skeleton this model shares
+                let available_animations = p.asset_server.animated_meshlet_manager.clips_for(&instance.model_name);
                 ui.separator();
-                ui.label(format!("Entity {}: {}", i + 1, player.animation_name));
+                ui.label(format!("Entity {}: {} ({})", i + 1, instance.model_name, player.animation_name));
                 
                 // Animation selection dropdown
                 let current_anim_name = player.animation_name.clone();
                 egui::ComboBox::from_label(format!("Entity {} Animation", i + 1))
                     .selected_text(&current_anim_name)
                     .show_ui(ui, |ui| {
-                        for anim_name in &available_animations {
+                        for anim_name in available_animations {
                             if ui.selectable_value(&mut player.animation_name, anim_name.clone(), anim_name).changed() {
                                 player.current_time = 0.0; // Reset time on animation change
                                 player.next_animation = None; // Cancel any ongoing blend
@@ -223,6 +229,31 @@ pub fn ui_system(mut p: UiSystemParams) {
         }
     });
 
+    // Cameras and lights authored in the loaded models
+    egui::Window::new("Scene").show(ctx, |ui| {
+        ui.label(format!("Cameras: {}", p.authored_camera_query.iter().count()));
+        for (name, authored, transform) in p.authored_camera_query.iter() {
+            ui.horizontal(|ui| {
+                ui.label(name.as_str());
+                if ui.button("View").clicked() {
+                    p.orbit_camera.look_from(transform.translation(), transform.rotation());
+                    if let Ok(mut camera) = p.camera_query.single_mut() {
+                        camera.apply_projection(&authored.projection);
+                    }
+                    log::info!("[UI] Jumped to camera '{name}'");
+                }
+            });
+        }
+        ui.separator();
+        ui.label(format!("Lights: {}", p.light_query.iter().count()));
+        for (name, light) in p.light_query.iter() {
+            ui.label(format!(
+                "{name}: {:?}, color {:.2?} x {:.1}, range {:?}",
+                light.kind, light.color, light.intensity, light.range
+            ));
+        }
+    });
+
     // GPU Picking Window
     egui::Window::new("GPU Picking").show(ctx, |ui| {
         // Display current pick coordinates
@@ -1,28 +1,51 @@
 use glam::Mat4;
-use redb::{ReadOnlyTable, ReadableTable};
+use database::AssetReader;
 use std::collections::HashMap;
-use types::{AnimatedModel, SkinnedVertex, AABB, Skeleton, Animation};
+use types::{AnimatedMesh, AnimatedModel, VertexFormat, AABB, Skeleton, Animation};
 use wgpu::util::DeviceExt;
 use bevy_ecs::prelude::Resource;
 use bytemuck::{Pod, Zeroable};
 use log;
 
-use crate::renderer::assets::static_meshlet::MeshletDescription;
+use crate::renderer::assets::base_color_uv_set;
+use crate::renderer::assets::lod::{append_lods, LodMeshlets};
+use crate::renderer::assets::static_meshlet::{MeshletDescription, VertexPlacement};
 
 // Define the new, specific struct for animated draws
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod, Zeroable)]
 pub struct AnimatedDrawCommand {
     pub meshlet_id: u32,
-    pub bone_set_id: u32,
+    pub first_bone: u32, // Index of the draw's first matrix in this frame's bone matrices
+    pub bone_count: u32,
     pub transform_id: u32,  // Still needed for positioning
     pub entity_id: u32,     // Entity ID for picking
     pub texture_id: u32,
+    pub morph_target_count: u32, // 0 when the mesh has no morph targets
+    pub morph_first_weight: u32, // Index of the mesh's first weight in this frame's weights
+    pub morph_first_delta: u32,  // See `MorphPlacement`
+    pub first_vertex_word: u32,
+    pub vertex_stride_words: u32,
+}
+
+/// Where a mesh's morph target deltas sit in `morph_deltas` and which node's weights drive
+/// them. Each vertex has two entries per target, the position then the normal delta, for all
+/// targets in turn.
+pub struct MorphPlacement {
+    pub first_delta: u32,
+    pub target_count: u32,
+    pub node_name: String,
+    pub default_weights: Vec<f32>,
 }
 
 pub struct ModelMeshlets {
-    pub meshlet_indices: Vec<u32>, // Indices into the global meshlets array
+    pub lods: Vec<LodMeshlets>, // Meshlet ranges in the global meshlets array, full detail first
+    pub aabb: AABB,
     pub texture_id: u32,
+    pub placement: VertexPlacement,
+    pub morph: Option<MorphPlacement>,
+    /// Skeleton bone of each joint the vertices name; empty when they name skeleton bones.
+    pub bone_palette: Vec<u32>,
 }
 
 #[derive(Resource)]
@@ -30,16 +53,19 @@ pub struct AnimatedMeshletManager {
     // CPU data
     pub skeletons: HashMap<String, Skeleton>,
     pub animations: HashMap<String, Animation>,
-    pub vertices: Vec<SkinnedVertex>,
+    pub vertex_words: Vec<u32>, // Full or quantized skinned vertices, see `VertexPlacement`
+    pub morph_deltas: Vec<[f32; 4]>, // See `MorphPlacement`
     pub meshlet_vertex_indices: Vec<u32>,
     pub meshlet_triangle_indices: Vec<u8>,
     pub meshlets: Vec<MeshletDescription>,
     pub transforms: Vec<Mat4>,
     pub draw_commands: Vec<AnimatedDrawCommand>,
     pub model_meshlets: HashMap<String, Vec<ModelMeshlets>>, // Maps model name to its meshlets
+    pub compatible_clips: HashMap<String, Vec<String>>, // Maps model name to the clip keys that fit its skeleton
 
     // GPU resources
     pub vertex_buffer: Option<wgpu::Buffer>,
+    pub morph_delta_buffer: Option<wgpu::Buffer>,
     pub meshlet_vertex_index_buffer: Option<wgpu::Buffer>,
     pub meshlet_triangle_index_buffer: Option<wgpu::Buffer>,
     pub meshlet_description_buffer: Option<wgpu::Buffer>,
@@ -55,11 +81,11 @@ pub struct AnimatedMeshletManager {
 impl AnimatedMeshletManager {
     pub fn new(
         device: &wgpu::Device,
-        model_table: &ReadOnlyTable<&str, &[u8]>,
-        animation_table: &ReadOnlyTable<&str, &[u8]>,
+        reader: &AssetReader,
         texture_map: &HashMap<String, u32>,
     ) -> Self {
-        let mut all_vertices = Vec::new();
+        let mut all_vertex_words = Vec::new();
+        let mut all_morph_deltas = Vec::new();
         let mut all_meshlet_vertex_indices = Vec::<u32>::new();
         let mut all_meshlet_triangle_indices = Vec::new();
         let mut all_meshlets = Vec::new();
@@ -67,15 +93,15 @@ impl AnimatedMeshletManager {
         let mut model_meshlets = HashMap::new();
 
         let mut skeletons = HashMap::new();
-        let animations: HashMap<String, Animation> = animation_table
-            .iter()
+        let animations: HashMap<String, Animation> = reader
+            .iter::<Animation>()
             .unwrap()
-            .filter_map(|result| {
-                result.ok().and_then(|(name, anim_data)| {
-                    bincode::deserialize::<Animation>(anim_data.value())
-                        .ok()
-                        .map(|anim| (name.value().to_string(), anim))
-                })
+            .filter_map(|result| match result {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    log::warn!("[Asset Loading] Skipping animation: {e}");
+                    None
+                }
             })
             .collect();
 
@@ -91,7 +117,7 @@ impl AnimatedMeshletManager {
             for (i, channel) in animation.channels.iter().enumerate() {
                 log::info!("      Channel {}: bone='{}', pos_keys={}, rot_keys={}, scale_keys={}", 
                     i, channel.bone_name, channel.position_keys.len(), 
-                    channel.rotation_keys.len(), channel.scale_keys.len());
+                    channel.rotation_key_count(), channel.scale_keys.len());
                 
                 // Log keyframe ranges for debugging
                 if !channel.position_keys.is_empty() {
@@ -102,9 +128,10 @@ impl AnimatedMeshletManager {
                         last_pos.position.x, last_pos.position.y, last_pos.position.z);
                 }
                 
-                if !channel.rotation_keys.is_empty() {
-                    let first_rot = &channel.rotation_keys[0];
-                    let last_rot = &channel.rotation_keys[channel.rotation_keys.len() - 1];
+                let rotation_keys = channel.decoded_rotation_keys();
+                if !rotation_keys.is_empty() {
+                    let first_rot = &rotation_keys[0];
+                    let last_rot = &rotation_keys[rotation_keys.len() - 1];
                     log::info!("        Rotation range: [{:.3}, {:.3}, {:.3}, {:.3}] to [{:.3}, {:.3}, {:.3}, {:.3}]", 
                         first_rot.rotation.x, first_rot.rotation.y, first_rot.rotation.z, first_rot.rotation.w,
                         last_rot.rotation.x, last_rot.rotation.y, last_rot.rotation.z, last_rot.rotation.w);
@@ -112,13 +139,15 @@ impl AnimatedMeshletManager {
             }
         }
 
-        let models: Vec<AnimatedModel> = model_table
-            .iter()
+        let models: Vec<AnimatedModel> = reader
+            .iter::<AnimatedModel>()
             .unwrap()
-            .filter_map(|result| {
-                result.ok().and_then(|(_, model_data)| {
-                    bincode::deserialize::<AnimatedModel>(model_data.value()).ok()
-                })
+            .filter_map(|result| match result {
+                Ok((_, model)) => Some(model),
+                Err(e) => {
+                    log::warn!("[Asset Loading] Skipping animated model: {e}");
+                    None
+                }
             })
             .collect();
 
@@ -150,19 +179,20 @@ impl AnimatedMeshletManager {
             log::info!("  Processing {} meshes...", model.meshes.len());
             for (mesh_idx, mesh) in model.meshes.iter().enumerate() {
                 log::info!("    Mesh {}: '{}'", mesh_idx, mesh.name);
-                log::info!("      Vertices: {}", mesh.vertices.len());
+                log::info!("      Vertices: {} ({:?})", mesh.vertex_count(), mesh.vertex_format());
                 log::info!("      Indices: {}", mesh.indices.len());
                 log::info!("      Texture: {:?}", mesh.texture_name);
                 
                 // Log vertex bone data statistics
-                let vertices_with_bones = mesh.vertices.iter()
+                let vertices = mesh.decoded_vertices();
+                let vertices_with_bones = vertices.iter()
                     .filter(|v| v.bone_indices.iter().any(|&idx| idx != 0))
                     .count();
-                log::info!("      Vertices with bone influences: {}/{}", vertices_with_bones, mesh.vertices.len());
+                log::info!("      Vertices with bone influences: {}/{}", vertices_with_bones, vertices.len());
                 
                 // Log bone weight distribution
                 let mut bone_usage = std::collections::HashMap::new();
-                for vertex in &mesh.vertices {
+                for vertex in vertices.iter() {
                     for (i, &bone_idx) in vertex.bone_indices.iter().enumerate() {
                         if bone_idx != 0 && vertex.bone_weights[i] > 0.0 {
                             *bone_usage.entry(bone_idx).or_insert(0) += 1;
@@ -172,19 +202,25 @@ impl AnimatedMeshletManager {
                 log::info!("      Bone usage: {} unique bones used", bone_usage.len());
                 
                 if let Some(mesh_meshlets) = &mesh.meshlets {
-                    let vertex_base = all_vertices.len() as u32;
-                    let meshlet_vertex_index_base = all_meshlet_vertex_indices.len() as u32;
-
-                    all_vertices.extend_from_slice(&mesh.vertices);
-                    let remapped_vertex_indices: Vec<u32> = mesh_meshlets
-                        .vertices
-                        .iter()
-                        .map(|&i| vertex_base + i)
-                        .collect();
-                    all_meshlet_vertex_indices.extend(remapped_vertex_indices);
-
-                    let triangle_base = all_meshlet_triangle_indices.len() as u32;
-                    all_meshlet_triangle_indices.extend(&mesh_meshlets.triangles);
+                    let placement = match &mesh.quantized_vertices {
+                        Some(quantized) => VertexPlacement::append(&mut all_vertex_words, quantized, VertexFormat::Quantized, mesh.aabb),
+                        None => VertexPlacement::append(&mut all_vertex_words, &mesh.vertices, VertexFormat::Full, mesh.aabb),
+                    };
+                    let morph = (!mesh.morph_targets.is_empty()).then(|| append_morph_targets(&mut all_morph_deltas, mesh));
+
+                    let levels = std::iter::once((mesh_meshlets, 0.0)).chain(
+                        mesh.lods
+                            .iter()
+                            .filter_map(|lod| lod.meshlets.as_ref().map(|m| (m, lod.error))),
+                    );
+                    let lods = append_lods(
+                        levels,
+                        &placement,
+                        base_color_uv_set(reader, mesh.material.as_deref()),
+                        &mut all_meshlet_vertex_indices,
+                        &mut all_meshlet_triangle_indices,
+                        &mut all_meshlets,
+                    );
 
                     let texture_id = mesh
                         .texture_name
@@ -193,45 +229,41 @@ impl AnimatedMeshletManager {
                         .unwrap_or(0);
 
                     log::info!(
-                        "      Generated {} meshlets (vertex_base={}, triangle_base={})",
-                        mesh_meshlets.meshlets.len(),
-                        vertex_base,
-                        triangle_base
+                        "      Generated {} meshlets over {} LODs (first vertex word={})",
+                        lods.iter().map(|lod| lod.meshlet_count).sum::<u32>(),
+                        lods.len(),
+                        placement.first_word
                     );
 
-                    let mut meshlet_indices = Vec::new();
-
-                    for (meshlet_idx, m) in mesh_meshlets.meshlets.iter().enumerate() {
-                        let desc = MeshletDescription {
-                            vertex_list_offset: meshlet_vertex_index_base + m.vertex_offset,
-                            triangle_list_offset: triangle_base + m.triangle_offset,
-                            triangle_count: m.triangle_count,
-                            vertex_count: m.vertex_count,
-                        };
-                        all_meshlets.push(desc);
-
-                        let meshlet_id = (all_meshlets.len() - 1) as u32;
-                        meshlet_indices.push(meshlet_id);
-
-                        // Log first few meshlets for debugging
-                        if meshlet_idx < 3 {
-                            log::debug!("        Meshlet {}: id={}, vertices={}, triangles={}", 
-                                meshlet_idx, meshlet_id, m.vertex_count, m.triangle_count);
-                        }
-
-                        let draw_command = AnimatedDrawCommand {
+                    // The initial commands draw full detail; the render system picks a level
+                    // per instance every frame.
+                    let full_detail = lods[0];
+                    for meshlet_id in full_detail.first_meshlet..full_detail.first_meshlet + full_detail.meshlet_count {
+                        draw_commands.push(AnimatedDrawCommand {
                             meshlet_id,
-                            bone_set_id: 0, // Placeholder, will be updated later
+                            first_bone: 0, // Placeholder, will be updated later
+                            bone_count: 0,
                             transform_id: transform_id as u32, // Use transform_id as transform_id
                             entity_id: transform_id as u32, // Use transform_id as entity_id
                             texture_id,
-                        };
-                        draw_commands.push(draw_command);
+                            morph_target_count: 0, // No weights until the first frame
+                            morph_first_weight: 0,
+                            morph_first_delta: 0,
+                            first_vertex_word: placement.first_word,
+                            vertex_stride_words: placement.stride_words,
+                        });
                     }
 
+                    if let Some(morph) = &morph {
+                        log::info!("      Morph targets: {} driven by '{}'", morph.target_count, morph.node_name);
+                    }
                     model_meshlets_list.push(ModelMeshlets {
-                        meshlet_indices,
+                        lods,
+                        aabb: mesh.aabb,
                         texture_id,
+                        placement,
+                        morph,
+                        bone_palette: mesh.bone_palette.clone(),
                     });
 
                 } else {
@@ -244,8 +276,8 @@ impl AnimatedMeshletManager {
         }
 
         log::info!(
-            "[Asset Loading] AnimatedMeshletManager created. Total vertices: {}, Total meshlets: {}",
-            all_vertices.len(),
+            "[Asset Loading] AnimatedMeshletManager created. Total vertex words: {}, Total meshlets: {}",
+            all_vertex_words.len(),
             all_meshlets.len()
         );
 
@@ -261,7 +293,14 @@ impl AnimatedMeshletManager {
         let vertex_buffer =
             Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("Skinned Vertex Buffer"),
-                contents: bytemuck::cast_slice(&all_vertices),
+                contents: bytemuck::cast_slice(&all_vertex_words),
+                usage: wgpu::BufferUsages::STORAGE,
+            }));
+        // Storage bindings cannot be empty, so models without morph targets get one unused delta.
+        let morph_delta_buffer =
+            Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Animated Morph Delta Buffer"),
+                contents: bytemuck::cast_slice(if all_morph_deltas.is_empty() { &[[0.0f32; 4]] } else { &all_morph_deltas[..] }),
                 usage: wgpu::BufferUsages::STORAGE,
             }));
         let meshlet_vertex_index_buffer =
@@ -340,6 +379,16 @@ impl AnimatedMeshletManager {
                         },
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry { // morph_deltas
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
                 ],
             });
 
@@ -372,6 +421,10 @@ impl AnimatedMeshletManager {
                         .unwrap()
                         .as_entire_binding(),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: morph_delta_buffer.as_ref().unwrap().as_entire_binding(),
+                },
             ],
         });
 
@@ -417,18 +470,23 @@ impl AnimatedMeshletManager {
             ],
         });
 
+        let compatible_clips = build_compatible_clips(&skeletons, &animations);
+
         Self {
             skeletons,
             animations,
-            vertices: all_vertices,
+            vertex_words: all_vertex_words,
+            morph_deltas: all_morph_deltas,
             meshlet_vertex_indices: all_meshlet_vertex_indices,
             meshlet_triangle_indices: all_meshlet_triangle_indices,
             meshlets: all_meshlets,
             transforms,
             draw_commands,
             model_meshlets, // Initialize the new field
+            compatible_clips,
 
             vertex_buffer,
+            morph_delta_buffer,
             meshlet_vertex_index_buffer,
             meshlet_triangle_index_buffer,
             meshlet_description_buffer,
@@ -441,4 +499,70 @@ impl AnimatedMeshletManager {
             instance_bind_group: Some(instance_bind_group),
         }
     }
-} 
\ No newline at end of file
+
+    /// Clip keys whose skeleton matches `model_name`'s, sorted by key.
+    pub fn clips_for(&self, model_name: &str) -> &[String] {
+        self.compatible_clips.get(model_name).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn is_compatible(&self, model_name: &str, clip: &str) -> bool {
+        self.clips_for(model_name).iter().any(|c| c == clip)
+    }
+
+    /// The weights `model_name`'s morph targets rest at, by the node that drives them.
+    pub fn default_morph_weights(&self, model_name: &str) -> HashMap<String, Vec<f32>> {
+        self.model_meshlets
+            .get(model_name)
+            .into_iter()
+            .flatten()
+            .filter_map(|mesh| mesh.morph.as_ref())
+            .map(|morph| (morph.node_name.clone(), morph.default_weights.clone()))
+            .collect()
+    }
+}
+
+/// Appends the morph target deltas of `mesh` in the order `MorphPlacement` describes.
+fn append_morph_targets(deltas: &mut Vec<[f32; 4]>, mesh: &AnimatedMesh) -> MorphPlacement {
+    let first_delta = deltas.len() as u32;
+    for vertex in 0..mesh.vertex_count() {
+        for target in &mesh.morph_targets {
+            let position = target.position_deltas.get(vertex).copied().unwrap_or_default();
+            let normal = target.normal_deltas.get(vertex).copied().unwrap_or_default();
+            deltas.push(position.extend(0.0).to_array());
+            deltas.push(normal.extend(0.0).to_array());
+        }
+    }
+    MorphPlacement {
+        first_delta,
+        target_count: mesh.morph_targets.len() as u32,
+        node_name: mesh.node_name.clone().unwrap_or_default(),
+        default_weights: mesh.morph_weights.clone(),
+    }
+}
+
+fn build_compatible_clips(
+    skeletons: &HashMap<String, Skeleton>,
+    animations: &HashMap<String, Animation>,
+) -> HashMap<String, Vec<String>> {
+    let mut compatible_clips = HashMap::new();
+    for (model_name, skeleton) in skeletons {
+        let mut clips: Vec<String> = animations
+            .iter()
+            .filter(|(_, animation)| {
+                skeletons
+                    .get(&animation.skeleton)
+                    .is_some_and(|authored| skeleton.is_compatible_with(authored))
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        clips.sort();
+        log::info!("[Asset Loading] Model '{model_name}' can play {} clips", clips.len());
+        compatible_clips.insert(model_name.clone(), clips);
+    }
+    for (key, animation) in animations {
+        if !skeletons.contains_key(&animation.skeleton) {
+            log::warn!("[Asset Loading] Clip '{key}' was authored for missing model '{}'", animation.skeleton);
+        }
+    }
+    compatible_clips
+}
@@ -0,0 +1,92 @@
+use glam::{Mat4, Vec3};
+use types::{Meshlets, AABB};
+
+use crate::renderer::assets::static_meshlet::{MeshletDescription, VertexPlacement};
+
+/// The meshlets drawing one level of detail of a mesh, which are contiguous in the global
+/// meshlet list, and the baked error of that level in model units.
+#[derive(Debug, Clone, Copy)]
+pub struct LodMeshlets {
+    pub first_meshlet: u32,
+    pub meshlet_count: u32,
+    pub error: f32,
+}
+
+/// Largest projected error, as a fraction of the viewport height, a level may show; about one
+/// pixel at 1080p.
+pub const LOD_ERROR_THRESHOLD: f32 = 1.0 / 1080.0;
+
+/// Picks the coarsest level whose error stays under `LOD_ERROR_THRESHOLD` when `aabb`, placed
+/// by `transform`, is seen from `eye` with vertical field of view `fovy`. `levels` runs from
+/// full detail to coarsest and must not be empty.
+pub fn select_lod<'a>(
+    levels: &'a [LodMeshlets],
+    transform: &Mat4,
+    aabb: &AABB,
+    eye: Vec3,
+    fovy: f32,
+) -> &'a LodMeshlets {
+    let (scale, _, _) = transform.to_scale_rotation_translation();
+    let scale = scale.max_element();
+    let center = transform.transform_point3(((aabb.min + aabb.max) * 0.5).truncate());
+    let radius = ((aabb.max - aabb.min) * 0.5).truncate().length() * scale;
+
+    // Distance to the bounding sphere, so nothing inside it is ever simplified.
+    let distance = (center.distance(eye) - radius).max(0.0);
+    let view_height = 2.0 * distance * (fovy * 0.5).tan();
+
+    levels
+        .iter()
+        .rev()
+        .find(|level| level.error * scale <= view_height * LOD_ERROR_THRESHOLD)
+        .unwrap_or(&levels[0])
+}
+
+/// Appends the meshlets of every level of one mesh to the global lists, in order from full
+/// detail to coarsest. The mesh's vertices must already sit where `placement` says; meshlet
+/// vertex indices become word offsets into the vertex buffer.
+pub fn append_lods<'a>(
+    levels: impl Iterator<Item = (&'a Meshlets, f32)>,
+    placement: &VertexPlacement,
+    base_color_uv_set: u32,
+    meshlet_vertex_indices: &mut Vec<u32>,
+    meshlet_triangle_indices: &mut Vec<u8>,
+    meshlets: &mut Vec<MeshletDescription>,
+) -> Vec<LodMeshlets> {
+    let position_min = placement.aabb.min.truncate().extend(0.0).to_array();
+    let position_extent = (placement.aabb.max - placement.aabb.min).truncate().extend(0.0).to_array();
+
+    let mut lods = Vec::new();
+    for (level, error) in levels {
+        let vertex_index_base = meshlet_vertex_indices.len() as u32;
+        meshlet_vertex_indices.extend(
+            level
+                .vertices
+                .iter()
+                .map(|&i| placement.first_word + i * placement.stride_words),
+        );
+        let triangle_base = meshlet_triangle_indices.len() as u32;
+        meshlet_triangle_indices.extend(&level.triangles);
+
+        lods.push(LodMeshlets {
+            first_meshlet: meshlets.len() as u32,
+            meshlet_count: level.meshlets.len() as u32,
+            error,
+        });
+        meshlets.extend(level.meshlets.iter().map(|m| MeshletDescription {
+            vertex_list_offset: vertex_index_base + m.vertex_offset,
+            triangle_list_offset: triangle_base + m.triangle_offset,
+            triangle_count: m.triangle_count,
+            vertex_count: m.vertex_count,
+            vertex_format: placement.format as u32,
+            base_color_uv_set,
+            _padding: [0; 2],
+            bounding_sphere: m.bounds.center.extend(m.bounds.radius).to_array(),
+            cone_apex: m.bounds.cone_apex.extend(0.0).to_array(),
+            cone_axis_cutoff: m.bounds.cone_axis.extend(m.bounds.cone_cutoff).to_array(),
+            position_min,
+            position_extent,
+        }));
+    }
+    lods
+}
@@ -7,8 +7,8 @@ use bevy_ecs::{
     world::{FromWorld, World},
 };
 use glam::{Mat4, Vec3};
-use redb::ReadOnlyTable;
-use types::{AABB, MODEL_TABLE, TEXTURE_TABLE, ANIMATED_MODEL_TABLE, ANIMATION_TABLE};
+use database::{AssetReader, AssetStore};
+use types::{Material, Scene, AABB};
 
 use crate::{
     renderer::core::{WgpuDevice, WgpuQueue},
@@ -20,6 +20,7 @@ use self::{
 };
 
 pub mod animated_meshlet;
+pub mod lod;
 pub mod static_meshlet;
 pub mod texture;
 
@@ -28,6 +29,8 @@ pub struct AssetServer {
     pub meshlet_manager: MeshletManager,
     pub animated_meshlet_manager: AnimatedMeshletManager,
     pub textures: TextureManager,
+    /// Authored cameras and lights, one scene per model source that has any.
+    pub scenes: Vec<Scene>,
     pub texture_bind_group_layout: Option<wgpu::BindGroupLayout>,
     pub texture_bind_group: Option<wgpu::BindGroup>,
 }
@@ -39,22 +42,16 @@ impl FromWorld for AssetServer {
 }
 
 pub fn new(world: &mut World) -> AssetServer {
-    let db = redb::Database::open("assets/models.redb").unwrap();
-    let read_txn = db.begin_read().unwrap();
-    let model_table: ReadOnlyTable<&str, &[u8]> =
-        read_txn.open_table(MODEL_TABLE).unwrap();
-    let animated_model_table: ReadOnlyTable<&str, &[u8]> =
-        read_txn.open_table(ANIMATED_MODEL_TABLE).unwrap();
-    let animation_table: ReadOnlyTable<&str, &[u8]> =
-        read_txn.open_table(ANIMATION_TABLE).unwrap();
-    let texture_table = read_txn.open_table(TEXTURE_TABLE).unwrap();
-
-    let (texture_cpu_data, texture_map) = texture::load_textures_from_db(&texture_table);
+    let store = AssetStore::open_current("assets/models.redb")
+        .unwrap_or_else(|e| panic!("[Asset Loading] assets/models.redb: {e}; run `database migrate` or rebake"));
+    let reader = store.read().unwrap();
+
+    let (texture_cpu_data, texture_map) = texture::load_textures_from_db(&reader);
     
     let device = world.resource::<WgpuDevice>();
-    let meshlet_manager = MeshletManager::new(device, &model_table, &texture_map);
-    let animated_meshlet_manager =
-        AnimatedMeshletManager::new(device, &animated_model_table, &animation_table, &texture_map);
+    let meshlet_manager = MeshletManager::new(device, &reader, &texture_map);
+    let animated_meshlet_manager = AnimatedMeshletManager::new(device, &reader, &texture_map);
+    let scenes = load_scenes(&reader);
 
     let mut asset_server = AssetServer {
         meshlet_manager,
@@ -64,6 +61,7 @@ pub fn new(world: &mut World) -> AssetServer {
             texture_array: None,
             texture_sampler: None,
         },
+        scenes,
         texture_bind_group_layout: None,
         texture_bind_group: None,
     };
@@ -72,6 +70,27 @@ pub fn new(world: &mut World) -> AssetServer {
     asset_server
 }
 
+fn load_scenes(reader: &AssetReader) -> Vec<Scene> {
+    reader
+        .iter::<Scene>()
+        .unwrap()
+        .filter_map(|result| match result {
+            Ok((_, scene)) => Some(scene),
+            Err(e) => {
+                log::warn!("[Asset Loading] Skipping scene: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// The UV set a mesh's base color texture is sampled with, as its material asks; 0 without one.
+pub fn base_color_uv_set(reader: &AssetReader, material: Option<&str>) -> u32 {
+    material
+        .and_then(|key| reader.get::<Material>(key).ok().flatten())
+        .map_or(0, |material| material.base_color_tex_coord.min(1))
+}
+
 fn create_texture_gpu_resources(
     asset_server: &mut AssetServer,
     device: &wgpu::Device,
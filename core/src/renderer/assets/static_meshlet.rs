@@ -1,10 +1,15 @@
 use bytemuck::{Pod, Zeroable};
-use glam::Mat4;
-use redb::{ReadOnlyTable, ReadableTable};
+use glam::{Mat4, Quat, Vec3, Vec4};
+use database::AssetReader;
 use std::collections::HashMap;
-use types::{Model, Vertex};
+use types::{Frustum, MeshletBounds, Model, Prefab, PrefabNode, VertexFormat, AABB};
 use wgpu::util::DeviceExt;
 
+use crate::renderer::assets::{
+    base_color_uv_set,
+    lod::{append_lods, select_lod, LodMeshlets},
+};
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod, Zeroable)]
 pub struct MeshletDescription {
@@ -12,6 +17,57 @@ pub struct MeshletDescription {
     pub triangle_list_offset: u32,
     pub triangle_count: u32,
     pub vertex_count: u32,
+    pub vertex_format: u32,
+    pub base_color_uv_set: u32, // 0 samples the base color with `uv`, 1 with `uv1`
+    pub _padding: [u32; 2],
+    pub bounding_sphere: [f32; 4],
+    pub cone_apex: [f32; 4],
+    pub cone_axis_cutoff: [f32; 4],
+    // The mesh AABB quantized positions are relative to.
+    pub position_min: [f32; 4],
+    pub position_extent: [f32; 4],
+}
+
+impl MeshletDescription {
+    pub fn bounds(&self) -> MeshletBounds {
+        let [x, y, z, radius] = self.bounding_sphere;
+        let [axis_x, axis_y, axis_z, cone_cutoff] = self.cone_axis_cutoff;
+        MeshletBounds {
+            center: Vec3::new(x, y, z),
+            radius,
+            cone_apex: Vec4::from(self.cone_apex).truncate(),
+            cone_axis: Vec3::new(axis_x, axis_y, axis_z),
+            cone_cutoff,
+        }
+    }
+}
+
+/// Where a mesh's vertices start in the vertex word buffer, how many words each one takes
+/// and how the shaders decode them.
+#[derive(Debug, Clone, Copy)]
+pub struct VertexPlacement {
+    pub first_word: u32,
+    pub stride_words: u32,
+    pub format: VertexFormat,
+    pub aabb: AABB,
+}
+
+impl VertexPlacement {
+    /// Appends `vertices` to `words` and describes where they went.
+    pub fn append<V: Pod>(words: &mut Vec<u32>, vertices: &[V], format: VertexFormat, aabb: AABB) -> Self {
+        let first_word = words.len() as u32;
+        words.extend(
+            bytemuck::cast_slice::<V, u8>(vertices)
+                .chunks_exact(4)
+                .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]])),
+        );
+        Self {
+            first_word,
+            stride_words: (std::mem::size_of::<V>() / 4) as u32,
+            format,
+            aabb,
+        }
+    }
 }
 
 #[repr(C)]
@@ -23,14 +79,39 @@ pub struct DrawCommand {
     pub texture_id: u32,
 }
 
+/// One uploaded mesh with the meshlet range of each of its levels of detail.
+pub struct StaticMesh {
+    pub texture_id: u32,
+    pub aabb: AABB,
+    pub lods: Vec<LodMeshlets>,
+}
+
+/// A loaded static model: the prefab placing its meshes, the `MeshletManager::meshes` id of
+/// each of them (`None` for meshes without meshlets) and where the row layout puts it.
+#[derive(Debug, Clone)]
+pub struct StaticModel {
+    pub name: String,
+    pub prefab: Prefab,
+    pub mesh_ids: Vec<Option<u32>>,
+    pub placement: Mat4,
+}
+
+/// An entity drawing static meshes this frame. Its transform goes to the transform buffer at
+/// the instance's position in the list passed to `MeshletManager::draw_commands`.
+pub struct InstanceDraw<'a> {
+    pub entity_id: u32,
+    pub transform: Mat4,
+    pub meshes: &'a [u32],
+}
+
 pub struct MeshletManager {
     // CPU data
-    pub vertices: Vec<Vertex>,
+    pub vertex_words: Vec<u32>, // Full or quantized vertices, see `VertexPlacement`
     pub meshlet_vertex_indices: Vec<u32>,
     pub meshlet_triangle_indices: Vec<u8>,
     pub meshlets: Vec<MeshletDescription>,
-    pub transforms: Vec<Mat4>,
-    pub draw_commands: Vec<DrawCommand>,
+    pub meshes: Vec<StaticMesh>,
+    pub models: Vec<StaticModel>,
 
     // GPU resources
     pub vertex_buffer: Option<wgpu::Buffer>,
@@ -49,44 +130,54 @@ pub struct MeshletManager {
 impl MeshletManager {
     pub fn new(
         device: &wgpu::Device,
-        model_table: &ReadOnlyTable<&str, &[u8]>,
+        reader: &AssetReader,
         texture_map: &HashMap<String, u32>,
     ) -> Self {
-        let mut all_vertices = Vec::new();
+        let mut all_vertex_words = Vec::new();
         let mut all_meshlet_vertex_indices = Vec::<u32>::new();
         let mut all_meshlet_triangle_indices = Vec::new();
         let mut all_meshlets = Vec::new();
-        let mut draw_commands: Vec<DrawCommand> = Vec::new();
+        let mut meshes = Vec::new();
 
-        let models: Vec<Model> = model_table
-            .iter()
+        let models: Vec<Model> = reader
+            .iter::<Model>()
             .unwrap()
-            .filter_map(|result| {
-                result.ok().and_then(|(_, model_data)| {
-                    bincode::deserialize::<Model>(model_data.value()).ok()
-                })
+            .filter_map(|result| match result {
+                Ok((_, model)) => Some(model),
+                Err(e) => {
+                    log::warn!("[Asset Loading] Skipping model: {e}");
+                    None
+                }
             })
             .collect();
 
         let aabbs: Vec<types::AABB> = models.iter().map(|model| model.aabb).collect();
-        let transforms = crate::renderer::assets::layout_models_in_a_row(&aabbs);
+        let placements = crate::renderer::assets::layout_models_in_a_row(&aabbs);
 
-        for (transform_id, model) in models.iter().enumerate() {
+        let mut static_models = Vec::new();
+        for (model, placement) in models.iter().zip(placements) {
+            let mut mesh_ids = Vec::new();
             for mesh in &model.meshes {
+                mesh_ids.push(None);
                 if let Some(mesh_meshlets) = &mesh.meshlets {
-                    let vertex_base = all_vertices.len() as u32;
-                    let meshlet_vertex_index_base = all_meshlet_vertex_indices.len() as u32;
+                    let placement = match &mesh.quantized_vertices {
+                        Some(quantized) => VertexPlacement::append(&mut all_vertex_words, quantized, VertexFormat::Quantized, mesh.aabb),
+                        None => VertexPlacement::append(&mut all_vertex_words, &mesh.vertices, VertexFormat::Full, mesh.aabb),
+                    };
 
-                    all_vertices.extend_from_slice(&mesh.vertices);
-                    let remapped_vertex_indices: Vec<u32> = mesh_meshlets
-                        .vertices
-                        .iter()
-                        .map(|&i| vertex_base + i)
-                        .collect();
-                    all_meshlet_vertex_indices.extend(remapped_vertex_indices);
-
-                    let triangle_base = all_meshlet_triangle_indices.len() as u32;
-                    all_meshlet_triangle_indices.extend(&mesh_meshlets.triangles);
+                    let levels = std::iter::once((mesh_meshlets, 0.0)).chain(
+                        mesh.lods
+                            .iter()
+                            .filter_map(|lod| lod.meshlets.as_ref().map(|m| (m, lod.error))),
+                    );
+                    let lods = append_lods(
+                        levels,
+                        &placement,
+                        base_color_uv_set(reader, mesh.material.as_deref()),
+                        &mut all_meshlet_vertex_indices,
+                        &mut all_meshlet_triangle_indices,
+                        &mut all_meshlets,
+                    );
 
                     let texture_id = mesh
                         .texture_name
@@ -94,31 +185,35 @@ impl MeshletManager {
                         .and_then(|name| texture_map.get(name).copied())
                         .unwrap_or(0);
 
-                    for m in &mesh_meshlets.meshlets {
-                        let desc = MeshletDescription {
-                            vertex_list_offset: meshlet_vertex_index_base + m.vertex_offset,
-                            triangle_list_offset: triangle_base + m.triangle_offset,
-                            triangle_count: m.triangle_count,
-                            vertex_count: m.vertex_count,
-                        };
-                        all_meshlets.push(desc);
-
-                        let draw_command = DrawCommand {
-                            meshlet_id: (all_meshlets.len() - 1) as u32,
-                            transform_id: transform_id as u32,
-                            entity_id: transform_id as u32, // Use transform_id as entity_id for static meshlets
-                            texture_id,
-                        };
-                        draw_commands.push(draw_command);
-                    }
+                    *mesh_ids.last_mut().unwrap() = Some(meshes.len() as u32);
+                    meshes.push(StaticMesh {
+                        texture_id,
+                        aabb: mesh.aabb,
+                        lods,
+                    });
                 }
             }
+
+            let prefab = match reader.get::<Prefab>(&model.name) {
+                Ok(Some(prefab)) => prefab,
+                Ok(None) => single_node_prefab(model),
+                Err(e) => {
+                    log::warn!("[Asset Loading] Placing '{}' without its prefab: {e}", model.name);
+                    single_node_prefab(model)
+                }
+            };
+            static_models.push(StaticModel {
+                name: model.name.clone(),
+                prefab,
+                mesh_ids,
+                placement,
+            });
         }
 
         let vertex_buffer =
             Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("Vertex Buffer"),
-                contents: bytemuck::cast_slice(&all_vertices),
+                contents: bytemuck::cast_slice(&all_vertex_words),
                 usage: wgpu::BufferUsages::STORAGE,
             }));
         let meshlet_vertex_index_buffer =
@@ -139,18 +234,9 @@ impl MeshletManager {
                 contents: bytemuck::cast_slice(&all_meshlets),
                 usage: wgpu::BufferUsages::STORAGE,
             }));
-        let transform_buffer =
-            Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Transform Buffer"),
-                contents: bytemuck::cast_slice(&transforms),
-                usage: wgpu::BufferUsages::STORAGE,
-            }));
-        let indirection_buffer =
-            Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Indirection Buffer"),
-                contents: bytemuck::cast_slice(&draw_commands),
-                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT,
-            }));
+        // Both filled every frame by `upload_instances`, and grown when they fall short.
+        let transform_buffer = create_transform_buffer(device, 1);
+        let indirection_buffer = create_indirection_buffer(device, 1);
 
         let mesh_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -262,35 +348,23 @@ impl MeshletManager {
                 ],
             });
 
-        let instance_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Instance Bind Group"),
-            layout: &instance_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: indirection_buffer.as_ref().unwrap().as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: transform_buffer.as_ref().unwrap().as_entire_binding(),
-                },
-            ],
-        });
+        let instance_bind_group =
+            create_instance_bind_group(device, &instance_bind_group_layout, &indirection_buffer, &transform_buffer);
 
         Self {
-            vertices: all_vertices,
+            vertex_words: all_vertex_words,
             meshlet_vertex_indices: all_meshlet_vertex_indices,
             meshlet_triangle_indices: all_meshlet_triangle_indices,
             meshlets: all_meshlets,
-            transforms,
-            draw_commands,
+            meshes,
+            models: static_models,
 
             vertex_buffer,
             meshlet_vertex_index_buffer,
             meshlet_triangle_index_buffer,
             meshlet_description_buffer,
-            transform_buffer,
-            indirection_buffer,
+            transform_buffer: Some(transform_buffer),
+            indirection_buffer: Some(indirection_buffer),
 
             mesh_bind_group_layout: Some(mesh_bind_group_layout),
             mesh_bind_group: Some(mesh_bind_group),
@@ -298,4 +372,118 @@ impl MeshletManager {
             instance_bind_group: Some(instance_bind_group),
         }
     }
-} 
\ No newline at end of file
+
+    /// This frame's draw commands: the meshlets of the level each instance's meshes select
+    /// when seen from `eye`, leaving out those outside `frustum` or facing away from `eye`.
+    pub fn draw_commands(&self, instances: &[InstanceDraw], eye: Vec3, fovy: f32, frustum: &Frustum) -> Vec<DrawCommand> {
+        let mut draw_commands = Vec::new();
+        for (transform_id, instance) in instances.iter().enumerate() {
+            let transform = &instance.transform;
+            for mesh in instance.meshes.iter().filter_map(|&id| self.meshes.get(id as usize)) {
+                let lod = select_lod(&mesh.lods, transform, &mesh.aabb, eye, fovy);
+                for meshlet_id in lod.first_meshlet..lod.first_meshlet + lod.meshlet_count {
+                    let bounds = self.meshlets[meshlet_id as usize].bounds();
+                    if !bounds.is_in_frustum(transform, frustum) || bounds.is_backfacing(transform, eye) {
+                        continue;
+                    }
+                    draw_commands.push(DrawCommand {
+                        meshlet_id,
+                        transform_id: transform_id as u32,
+                        entity_id: instance.entity_id,
+                        texture_id: mesh.texture_id,
+                    });
+                }
+            }
+        }
+        draw_commands
+    }
+
+    /// Writes this frame's instance transforms and draw commands, recreating the buffers and
+    /// the instance bind group when either no longer fits.
+    pub fn upload_instances(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        instances: &[InstanceDraw],
+        draw_commands: &[DrawCommand],
+    ) {
+        let transforms: Vec<Mat4> = instances.iter().map(|instance| instance.transform).collect();
+        let fits = |buffer: &Option<wgpu::Buffer>, size: usize| buffer.as_ref().is_some_and(|b| b.size() as usize >= size);
+
+        let transform_size = std::mem::size_of_val(transforms.as_slice());
+        let draw_command_size = std::mem::size_of_val(draw_commands);
+        if !fits(&self.transform_buffer, transform_size) || !fits(&self.indirection_buffer, draw_command_size) {
+            let transform_buffer = create_transform_buffer(device, transforms.len().next_power_of_two());
+            let indirection_buffer = create_indirection_buffer(device, draw_commands.len().next_power_of_two());
+            self.instance_bind_group = Some(create_instance_bind_group(
+                device,
+                self.instance_bind_group_layout.as_ref().unwrap(),
+                &indirection_buffer,
+                &transform_buffer,
+            ));
+            self.transform_buffer = Some(transform_buffer);
+            self.indirection_buffer = Some(indirection_buffer);
+        }
+
+        queue.write_buffer(self.transform_buffer.as_ref().unwrap(), 0, bytemuck::cast_slice(&transforms));
+        queue.write_buffer(self.indirection_buffer.as_ref().unwrap(), 0, bytemuck::cast_slice(draw_commands));
+    }
+}
+
+/// A prefab for models baked without one: a single node at the origin placing every mesh.
+fn single_node_prefab(model: &Model) -> Prefab {
+    Prefab {
+        name: model.name.clone(),
+        model: model.name.clone(),
+        nodes: vec![PrefabNode {
+            name: model.name.clone(),
+            parent_index: None,
+            translation: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+            scale: Vec3::ONE,
+            meshes: (0..model.meshes.len()).collect(),
+        }],
+    }
+}
+
+fn create_transform_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Transform Buffer"),
+        size: (capacity.max(1) * std::mem::size_of::<Mat4>()) as u64,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_indirection_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Indirection Buffer"),
+        size: (capacity.max(1) * std::mem::size_of::<DrawCommand>()) as u64,
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::INDIRECT
+            | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_instance_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    indirection_buffer: &wgpu::Buffer,
+    transform_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Instance Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: indirection_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: transform_buffer.as_entire_binding(),
+            },
+        ],
+    })
+}
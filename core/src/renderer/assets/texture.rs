@@ -1,50 +1,75 @@
-use redb::{ReadOnlyTable, ReadableTable};
+use database::{AssetReader, TextureData};
+use types::TextureRef;
 use std::collections::HashMap;
 use log;
 
 pub struct TextureManager {
-    pub texture_cpu_data: Vec<image::DynamicImage>,
+    pub texture_cpu_data: Vec<TextureData>,
     pub texture_array: Option<wgpu::Texture>,
     pub texture_sampler: Option<wgpu::Sampler>,
 }
 
 pub fn load_textures_from_db(
-    texture_table: &ReadOnlyTable<&str, &[u8]>,
-) -> (Vec<image::DynamicImage>, HashMap<String, u32>) {
+    reader: &AssetReader,
+) -> (Vec<TextureData>, HashMap<String, u32>) {
     let mut texture_map = HashMap::new();
     let mut texture_cpu_data = Vec::new();
 
     // Create a fallback texture
-    let fallback_texture = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
-        1,
-        1,
-        image::Rgba([255, 0, 255, 255]),
-    ));
+    let fallback_texture = TextureData {
+        width: 1,
+        height: 1,
+        srgb: true,
+        levels: vec![vec![255, 0, 255, 255]],
+    };
     texture_cpu_data.push(fallback_texture);
 
-    for result in texture_table.iter().unwrap() {
-        let (name_bytes, texture_data) = result.unwrap();
-        let name = name_bytes.value();
+    // One array layer per unique image; names shared between models resolve to the same layer.
+    let mut blob_layers = HashMap::new();
+    for result in reader.texture_blobs().unwrap() {
+        let (blob, texture_data) = result.unwrap();
+        match TextureData::from_ktx2(&blob, &texture_data) {
+            Ok(texture) => {
+                blob_layers.insert(blob, texture_cpu_data.len() as u32);
+                texture_cpu_data.push(texture);
+            }
+            Err(e) => log::error!("[Asset Loading] {e}"),
+        }
+    }
+
+    for result in reader.iter::<TextureRef>().unwrap() {
+        let (name, texture) = result.unwrap();
         log::info!("[Asset Loading] Loading texture: {name}");
-        if let Ok(image) = image::load_from_memory(texture_data.value()) {
-            texture_map.insert(name.to_string(), texture_cpu_data.len() as u32);
-            texture_cpu_data.push(image);
+        match blob_layers.get(&texture.blob) {
+            Some(&layer) => {
+                texture_map.insert(name, layer);
+            }
+            None => log::error!("[Asset Loading] Texture {name} refers to a missing blob"),
         }
     }
+    log::info!(
+        "[Asset Loading] {} textures share {} array layers",
+        texture_map.len(),
+        blob_layers.len()
+    );
 
     (texture_cpu_data, texture_map)
 }
 
+/// Uploads every texture with all of its mip levels into one sRGB array. Layers whose chain is
+/// shorter than the array's, because the image is smaller than the largest one, repeat their
+/// 1x1 level in the remaining mips.
 pub fn create_texture_gpu_resources(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
-    texture_cpu_data: &[image::DynamicImage],
+    texture_cpu_data: &[TextureData],
 ) -> (wgpu::Texture, wgpu::Sampler) {
     let (max_width, max_height) = texture_cpu_data
         .iter()
-        .fold((0, 0), |(max_w, max_h), img| {
-            (max_w.max(img.width()), max_h.max(img.height()))
+        .fold((0, 0), |(max_w, max_h), texture| {
+            (max_w.max(texture.width), max_h.max(texture.height))
         });
+    let mip_level_count = u32::BITS - max_width.max(max_height).leading_zeros();
 
     let texture_array = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("Texture Array"),
@@ -53,7 +78,7 @@ pub fn create_texture_gpu_resources(
             height: max_height,
             depth_or_array_layers: texture_cpu_data.len() as u32,
         },
-        mip_level_count: 1,
+        mip_level_count,
         sample_count: 1,
         dimension: wgpu::TextureDimension::D2,
         format: wgpu::TextureFormat::Rgba8UnormSrgb,
@@ -61,31 +86,34 @@ pub fn create_texture_gpu_resources(
         view_formats: &[],
     });
 
-    for (i, image) in texture_cpu_data.iter().enumerate() {
-        let rgba_image = image.to_rgba8();
-        queue.write_texture(
-            wgpu::TexelCopyTextureInfo {
-                texture: &texture_array,
-                mip_level: 0,
-                origin: wgpu::Origin3d {
-                    x: 0,
-                    y: 0,
-                    z: i as u32,
+    for (i, texture) in texture_cpu_data.iter().enumerate() {
+        for mip_level in 0..mip_level_count {
+            let level = (mip_level as usize).min(texture.levels.len() - 1);
+            let (width, height) = texture.level_size(level);
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &texture_array,
+                    mip_level,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: i as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &texture.levels[level],
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * width),
+                    rows_per_image: Some(height),
                 },
-                aspect: wgpu::TextureAspect::All,
-            },
-            &rgba_image,
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * rgba_image.width()),
-                rows_per_image: Some(rgba_image.height()),
-            },
-            wgpu::Extent3d {
-                width: rgba_image.width(),
-                height: rgba_image.height(),
-                depth_or_array_layers: 1,
-            },
-        );
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
     }
 
     let texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
@@ -94,10 +122,11 @@ pub fn create_texture_gpu_resources(
         address_mode_v: wgpu::AddressMode::ClampToEdge,
         address_mode_w: wgpu::AddressMode::ClampToEdge,
         mag_filter: wgpu::FilterMode::Linear,
-        min_filter: wgpu::FilterMode::Nearest,
-        mipmap_filter: wgpu::FilterMode::Nearest,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Linear,
+        anisotropy_clamp: 16,
         ..Default::default()
     });
 
     (texture_array, texture_sampler)
-} 
\ No newline at end of file
+}
@@ -8,12 +8,13 @@ use log;
 
 use crate::{
     ecs::{
-        animation::{AnimatedInstance, BoneMatrices},
+        animation::{AnimatedInstance, BoneMatrices, MorphWeights},
         camera::Camera,
     },
     renderer::{
         assets::{
             animated_meshlet::AnimatedDrawCommand,
+            lod::select_lod,
             AssetServer,
         },
         core::{WgpuDevice, WgpuQueue},
@@ -76,6 +77,16 @@ impl D3AnimatedPipeline {
                         },
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2, // Morph target weights
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
                 ],
             });
 
@@ -164,7 +175,7 @@ pub fn render_d3_animated_pipeline_system(
     // Add ID texture to the system parameters
     id_texture: Res<IdTexture>,
     camera_query: Query<(&Camera, &GlobalTransform), With<Camera>>,
-    animated_instance_query: Query<(Entity, &AnimatedInstance, &BoneMatrices, &GlobalTransform)>,
+    animated_instance_query: Query<(Entity, &AnimatedInstance, &BoneMatrices, Option<&MorphWeights>, &GlobalTransform)>,
 ) {
     let animated_meshlet_manager = &asset_server.animated_meshlet_manager;
 
@@ -178,23 +189,17 @@ pub fn render_d3_animated_pipeline_system(
     };
 
     // --- 1. Prepare Per-Frame Data (Dynamically) ---
+    let eye = camera_transform.translation();
     let mut draw_commands = Vec::new();
     let mut all_bone_matrices = Vec::new();
+    let mut all_morph_weights: Vec<f32> = Vec::new();
 
-    // Track bone matrix offsets for each instance
-    let mut bone_matrix_offsets = Vec::new();
-    
     // Iterate over the entities that are actually in the scene right now.
-    for (instance_index, (entity, instance, bone_matrices, _transform)) in
+    for (instance_index, (entity, instance, bone_matrices, morph_weights, transform)) in
         animated_instance_query.iter().enumerate()
     {
         // Note: We no longer need to track transforms separately since bone matrices include world transform
 
-        // Track where this instance's bone matrices start
-        let bone_matrix_offset = all_bone_matrices.len() as u32;
-        log::debug!("[Animated Render] Instance[{}] '{}': bone_matrix_offset = {} (adding {} matrices)", 
-            instance_index, instance.model_name, bone_matrix_offset, bone_matrices.matrices.len());
-        
         // Log bone matrix details for first few bones
         if instance_index < 2 {
             for (i, matrix) in bone_matrices.matrices.iter().take(3).enumerate() {
@@ -203,34 +208,67 @@ pub fn render_d3_animated_pipeline_system(
                     i, pos.x, pos.y, pos.z);
             }
         }
-        
-        bone_matrix_offsets.push(bone_matrix_offset);
-        
-        // Add this instance's bone matrices
-        all_bone_matrices.extend_from_slice(&bone_matrices.matrices);
+
+        // Meshes that name skeleton bones share one copy of the whole skeleton, uploaded when
+        // the first of them is drawn; split meshes get just the bones of their palette.
+        let bone_count = animated_meshlet_manager
+            .skeletons
+            .get(&instance.model_name)
+            .map_or(0, |skeleton| skeleton.bones.len());
+        let bone = |i: usize| bone_matrices.matrices.get(i).copied().unwrap_or(Mat4::IDENTITY);
+        let mut skeleton_bones: Option<u32> = None;
 
         // Find the meshlets associated with this instance's model name.
         if let Some(model_meshlets_list) = animated_meshlet_manager.model_meshlets.get(&instance.model_name) {
             log::debug!("[Animated Render] Creating draw commands for model '{}' (instance {}):", instance.model_name, instance_index);
+            let instance_matrix = transform.compute_matrix();
             for (meshlet_group_idx, model_meshlets) in model_meshlets_list.iter().enumerate() {
-                log::debug!("[Animated Render]   Meshlet group {}: {} meshlets, texture_id={}", 
-                    meshlet_group_idx, model_meshlets.meshlet_indices.len(), model_meshlets.texture_id);
-                for (meshlet_idx, &meshlet_id) in model_meshlets.meshlet_indices.iter().enumerate() {
-                    if meshlet_idx < 3 { // Only print first 3 for brevity
-                        log::debug!("[Animated Render]     Meshlet[{meshlet_idx}]: id={meshlet_id}, bone_set_id={instance_index}");
+                let lod = select_lod(&model_meshlets.lods, &instance_matrix, &model_meshlets.aabb, eye, camera.fovy);
+                log::debug!("[Animated Render]   Meshlet group {}: {} meshlets (error {:.4}), texture_id={}",
+                    meshlet_group_idx, lod.meshlet_count, lod.error, model_meshlets.texture_id);
+
+                // Each morphed mesh of each instance gets its own run of weights.
+                let morph_first_weight = all_morph_weights.len() as u32;
+                let (morph_target_count, morph_first_delta) = match &model_meshlets.morph {
+                    Some(morph) => {
+                        let weights = morph_weights
+                            .and_then(|w| w.weights.get(&morph.node_name))
+                            .unwrap_or(&morph.default_weights);
+                        let start = all_morph_weights.len();
+                        all_morph_weights.extend(weights.iter().take(morph.target_count as usize));
+                        all_morph_weights.resize(start + morph.target_count as usize, 0.0);
+                        (morph.target_count, morph.first_delta)
                     }
+                    None => (0, 0),
+                };
+                let (first_bone, draw_bone_count) = if model_meshlets.bone_palette.is_empty() {
+                    let first_bone = *skeleton_bones.get_or_insert_with(|| {
+                        let first_bone = all_bone_matrices.len() as u32;
+                        all_bone_matrices.extend((0..bone_count).map(bone));
+                        first_bone
+                    });
+                    (first_bone, bone_count as u32)
+                } else {
+                    let first_bone = all_bone_matrices.len() as u32;
+                    all_bone_matrices.extend(model_meshlets.bone_palette.iter().map(|&b| bone(b as usize)));
+                    (first_bone, model_meshlets.bone_palette.len() as u32)
+                };
+                for meshlet_id in lod.first_meshlet..lod.first_meshlet + lod.meshlet_count {
                     // Create a new draw command with the correct, frame-specific IDs.
                     draw_commands.push(AnimatedDrawCommand {
                         meshlet_id,
-                        bone_set_id: instance_index as u32, // Use instance index as bone_set_id for proper indexing
+                        first_bone,
+                        bone_count: draw_bone_count,
                         transform_id: instance_index as u32, // Use instance index as transform_id for positioning
                         entity_id: entity.index(),  // Direct Entity ID
                         texture_id: model_meshlets.texture_id,
+                        morph_target_count,
+                        morph_first_weight,
+                        morph_first_delta,
+                        first_vertex_word: model_meshlets.placement.first_word,
+                        vertex_stride_words: model_meshlets.placement.stride_words,
                     });
                 }
-                if model_meshlets.meshlet_indices.len() > 3 {
-                    log::debug!("[Animated Render]     ... and {} more meshlets", model_meshlets.meshlet_indices.len() - 3);
-                }
             }
         } else {
             log::warn!("[Animated Render] WARNING: No meshlets found for model '{}'", instance.model_name);
@@ -243,32 +281,24 @@ pub fn render_d3_animated_pipeline_system(
 
     log::info!("[Animated Render] Drawing {} commands", draw_commands.len());
     if !draw_commands.is_empty() {
-        log::debug!("[Animated Render] First draw command: meshlet_id={}, bone_set_id={}, transform_id={}, entity_id={}, texture_id={}", 
-            draw_commands[0].meshlet_id, draw_commands[0].bone_set_id, 
+        log::debug!("[Animated Render] First draw command: meshlet_id={}, first_bone={}, transform_id={}, entity_id={}, texture_id={}", 
+            draw_commands[0].meshlet_id, draw_commands[0].first_bone, 
             draw_commands[0].transform_id, draw_commands[0].entity_id, draw_commands[0].texture_id);
-        log::debug!("[Animated Render]   Last draw command: meshlet_id={}, bone_set_id={}, transform_id={}, entity_id={}, texture_id={}", 
-            draw_commands[draw_commands.len()-1].meshlet_id, draw_commands[draw_commands.len()-1].bone_set_id, 
+        log::debug!("[Animated Render]   Last draw command: meshlet_id={}, first_bone={}, transform_id={}, entity_id={}, texture_id={}", 
+            draw_commands[draw_commands.len()-1].meshlet_id, draw_commands[draw_commands.len()-1].first_bone, 
             draw_commands[draw_commands.len()-1].transform_id, draw_commands[draw_commands.len()-1].entity_id, draw_commands[draw_commands.len()-1].texture_id);
     }
     
     log::debug!("[Animated Render] Total bone matrices: {}", all_bone_matrices.len());
     
-    log::debug!("[Animated Render] Bone matrix layout:");
-    let mut offset = 0;
-    for (i, (_entity, instance, bone_matrices, _)) in animated_instance_query.iter().enumerate() {
-        log::debug!("[Animated Render]   Instance[{}] '{}': offset={}, count={}", 
-            i, instance.model_name, offset, bone_matrices.matrices.len());
-        offset += bone_matrices.matrices.len();
-    }
-    
     log::debug!("[Animated Render] Draw command summary:");
     log::debug!("[Animated Render]   Total draw commands: {}", draw_commands.len());
     if !draw_commands.is_empty() {
-        log::debug!("[Animated Render]   First draw command: meshlet_id={}, bone_set_id={}, transform_id={}, entity_id={}, texture_id={}", 
-            draw_commands[0].meshlet_id, draw_commands[0].bone_set_id, 
+        log::debug!("[Animated Render]   First draw command: meshlet_id={}, first_bone={}, transform_id={}, entity_id={}, texture_id={}", 
+            draw_commands[0].meshlet_id, draw_commands[0].first_bone, 
             draw_commands[0].transform_id, draw_commands[0].entity_id, draw_commands[0].texture_id);
-        log::debug!("[Animated Render]   Last draw command: meshlet_id={}, bone_set_id={}, transform_id={}, entity_id={}, texture_id={}", 
-            draw_commands[draw_commands.len()-1].meshlet_id, draw_commands[draw_commands.len()-1].bone_set_id, 
+        log::debug!("[Animated Render]   Last draw command: meshlet_id={}, first_bone={}, transform_id={}, entity_id={}, texture_id={}", 
+            draw_commands[draw_commands.len()-1].meshlet_id, draw_commands[draw_commands.len()-1].first_bone, 
             draw_commands[draw_commands.len()-1].transform_id, draw_commands[draw_commands.len()-1].entity_id, draw_commands[draw_commands.len()-1].texture_id);
     }
     
@@ -288,6 +318,10 @@ pub fn render_d3_animated_pipeline_system(
     );
 
     // Create new buffers for this frame's data. This is necessary because the data changes every frame.
+    // A frame drawing only skeletons without bones still needs one matrix to bind.
+    if all_bone_matrices.is_empty() {
+        all_bone_matrices.push(Mat4::IDENTITY);
+    }
     let bone_matrix_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("per_frame_bone_matrix_buffer"),
         contents: bytemuck::cast_slice(&all_bone_matrices),
@@ -296,6 +330,16 @@ pub fn render_d3_animated_pipeline_system(
 
     // Note: We no longer need a separate transform buffer since bone matrices include world transform
 
+    // Storage bindings cannot be empty, so a frame without morph targets uploads one unused weight.
+    if all_morph_weights.is_empty() {
+        all_morph_weights.push(0.0);
+    }
+    let morph_weight_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("per_frame_morph_weight_buffer"),
+        contents: bytemuck::cast_slice(&all_morph_weights),
+        usage: wgpu::BufferUsages::STORAGE,
+    });
+
     let indirection_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("per_frame_animated_indirection_buffer"),
         contents: bytemuck::cast_slice(&draw_commands),
@@ -324,7 +368,10 @@ pub fn render_d3_animated_pipeline_system(
                 binding: 1,
                 resource: bone_matrix_buffer.as_entire_binding(),
             },
-            // Note: Binding 2 (transform_buffer) is no longer used since bone matrices include world transform
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: morph_weight_buffer.as_entire_binding(),
+            },
         ],
     });
 
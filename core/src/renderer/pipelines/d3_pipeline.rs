@@ -1,13 +1,14 @@
 use bevy_ecs::{
-    prelude::{Res,Query},
+    prelude::{Entity, Res, ResMut, Query},
 };
 use bevy_transform::components::GlobalTransform;
+use types::Frustum;
 use wgpu::{include_wgsl, util::DeviceExt, PipelineCompilationOptions};
 
 use crate::{
-    ecs::camera::Camera,
+    ecs::{camera::Camera, prefab::StaticInstance},
     renderer::{
-        assets::AssetServer,
+        assets::{static_meshlet::InstanceDraw, AssetServer},
         core::{WgpuDevice,  WgpuQueue},
         pipelines::tonemapping::{DepthTexture, HdrTexture, IdTexture},
     },
@@ -118,11 +119,12 @@ impl D3Pipeline {
 pub fn render_d3_pipeline_system(
     device: Res<WgpuDevice>,
     queue: Res<WgpuQueue>,
-    asset_server: Res<AssetServer>,
+    mut asset_server: ResMut<AssetServer>,
     depth_texture: Res<DepthTexture>,
     hdr_texture: Res<HdrTexture>,
     id_texture: Res<IdTexture>,
     camera_query: Query<(&Camera, &GlobalTransform)>,
+    instance_query: Query<(Entity, &StaticInstance, &GlobalTransform)>,
 ) {
     // If the mesh bind group doesn't exist on the asset server, it's because
     // no renderable meshlets were loaded. In this case, there is nothing
@@ -136,16 +138,34 @@ pub fn render_d3_pipeline_system(
         return;
     };
 
+    let view = transform.compute_matrix().inverse();
+    let proj = camera.projection_matrix();
+    let view_proj = proj * view;
+
+    // Every prefab node placing static meshes is drawn where the hierarchy puts it.
+    let instances: Vec<InstanceDraw> = instance_query
+        .iter()
+        .map(|(entity, instance, global_transform)| InstanceDraw {
+            entity_id: entity.index(),
+            transform: global_transform.compute_matrix(),
+            meshes: &instance.meshes,
+        })
+        .collect();
+
+    let meshlet_manager = &mut asset_server.meshlet_manager;
+    let frustum = Frustum::from_view_proj(&view_proj);
+    let draw_commands = meshlet_manager.draw_commands(&instances, transform.translation(), camera.fovy, &frustum);
+    if draw_commands.is_empty() {
+        return;
+    }
+    meshlet_manager.upload_instances(&device, &queue, &instances, &draw_commands);
+
     let pipeline = D3Pipeline::new(
         &device,
         &asset_server,
         wgpu::TextureFormat::Rgba16Float, // HDR format
     );
 
-    let view = transform.compute_matrix().inverse();
-    let proj = camera.projection_matrix();
-    let view_proj = proj * view;
-
     let camera_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("camera_uniform_buffer"),
         contents: bytemuck::cast_slice(view_proj.as_ref()),
@@ -207,10 +227,10 @@ pub fn render_d3_pipeline_system(
         );
         render_pass.set_bind_group(3, asset_server.texture_bind_group.as_ref().unwrap(), &[]);
         
-        // Draw all the meshlets.
+        // Draw the meshlets of each mesh's selected LOD.
         render_pass.draw(
             0..(128 * 3), // Max triangles * 3
-            0..asset_server.meshlet_manager.draw_commands.len() as u32,
+            0..draw_commands.len() as u32,
         );
     }
 
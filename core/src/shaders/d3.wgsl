@@ -1,8 +1,11 @@
-// The structure of a single vertex, matching the Rust `Vertex` struct.
+// A vertex decoded from either layout of `types::Vertex` or `types::QuantizedVertex`.
 struct Vertex {
     position: vec3<f32>,
     normal: vec3<f32>,
     uv: vec2<f32>,
+    uv1: vec2<f32>,
+    tangent: vec4<f32>, // w is the bitangent sign
+    color: vec4<f32>,
 };
 
 // Static asset data describing a slice of the geometry buffers.
@@ -12,6 +15,14 @@ struct MeshletDescription {
     triangle_list_offset: u32,
     triangle_count: u32,
     vertex_count: u32,
+    vertex_format: u32,         // VERTEX_FORMAT_*
+    base_color_uv_set: u32,     // 0 samples the base color with `uv`, 1 with `uv1`
+    _padding: vec2<u32>,
+    bounding_sphere: vec4<f32>, // center, radius
+    cone_apex: vec4<f32>,       // w unused
+    cone_axis_cutoff: vec4<f32>,
+    position_min: vec4<f32>,    // The mesh AABB quantized positions are relative to
+    position_extent: vec4<f32>,
 };
 
 // Dynamic per-frame command telling the GPU what to draw.
@@ -30,6 +41,8 @@ struct VSOutput {
     @location(1) uv: vec2<f32>,
     @location(2) @interpolate(flat) texture_id: u32,
     @location(3) @interpolate(flat) entity_id: u32,
+    @location(4) world_tangent: vec4<f32>,
+    @location(5) color: vec4<f32>,
 };
 
 // Fragment output for MRT
@@ -44,7 +57,7 @@ struct FragmentOutput {
 @group(0) @binding(0) var<uniform> camera: mat4x4<f32>;
 
 // This group contains all the static mesh data.
-@group(1) @binding(0) var<storage, read> vertices: array<Vertex>;
+@group(1) @binding(0) var<storage, read> vertex_words: array<u32>; // Vertices in the meshlet's format
 @group(1) @binding(1) var<storage, read> meshlet_vertex_indices: array<u32>;
 @group(1) @binding(2) var<storage, read> meshlet_triangle_indices: array<u32>; // u8s packed into u32s
 @group(1) @binding(3) var<storage, read> meshlet_descriptions: array<MeshletDescription>;
@@ -58,6 +71,54 @@ struct FragmentOutput {
 @group(3) @binding(1) var texture_sampler: sampler;
 
 
+//-- Vertex Decoding -----------------------------------------------------------
+
+// Matches `types::VertexFormat`.
+const VERTEX_FORMAT_FULL: u32 = 0u;
+const VERTEX_FORMAT_QUANTIZED: u32 = 1u;
+
+fn decode_octahedral(e: vec2<f32>) -> vec3<f32> {
+    var n = vec3<f32>(e.x, e.y, 1.0 - abs(e.x) - abs(e.y));
+    let t = max(-n.z, 0.0);
+    n.x += select(t, -t, n.x >= 0.0);
+    n.y += select(t, -t, n.y >= 0.0);
+    return normalize(n);
+}
+
+// `offset` is the word offset of the vertex in `vertex_words`.
+fn load_vertex(offset: u32, meshlet: MeshletDescription) -> Vertex {
+    var vertex: Vertex;
+    if (meshlet.vertex_format == VERTEX_FORMAT_QUANTIZED) {
+        // unorm16 x, y, z, bitangent sign | snorm16 octahedral normal | f16 u, v
+        // | snorm16 octahedral tangent | f16 u1, v1 | unorm8 color
+        let position_zw = unpack2x16unorm(vertex_words[offset + 1u]);
+        let position = vec3<f32>(unpack2x16unorm(vertex_words[offset]), position_zw.x);
+        vertex.position = meshlet.position_min.xyz + position * meshlet.position_extent.xyz;
+        vertex.normal = decode_octahedral(unpack2x16snorm(vertex_words[offset + 2u]));
+        vertex.uv = unpack2x16float(vertex_words[offset + 3u]);
+        vertex.tangent = vec4<f32>(
+            decode_octahedral(unpack2x16snorm(vertex_words[offset + 4u])),
+            1.0 - 2.0 * position_zw.y,
+        );
+        vertex.uv1 = unpack2x16float(vertex_words[offset + 5u]);
+        vertex.color = unpack4x8unorm(vertex_words[offset + 6u]);
+    } else {
+        // vec4 position | vec4 normal | vec2 uv, vec2 uv1 | vec4 tangent | vec4 color
+        vertex.position = bitcast<vec3<f32>>(vec3<u32>(
+            vertex_words[offset], vertex_words[offset + 1u], vertex_words[offset + 2u]));
+        vertex.normal = bitcast<vec3<f32>>(vec3<u32>(
+            vertex_words[offset + 4u], vertex_words[offset + 5u], vertex_words[offset + 6u]));
+        vertex.uv = bitcast<vec2<f32>>(vec2<u32>(vertex_words[offset + 8u], vertex_words[offset + 9u]));
+        vertex.uv1 = bitcast<vec2<f32>>(vec2<u32>(vertex_words[offset + 10u], vertex_words[offset + 11u]));
+        vertex.tangent = bitcast<vec4<f32>>(vec4<u32>(
+            vertex_words[offset + 12u], vertex_words[offset + 13u], vertex_words[offset + 14u], vertex_words[offset + 15u]));
+        vertex.color = bitcast<vec4<f32>>(vec4<u32>(
+            vertex_words[offset + 16u], vertex_words[offset + 17u], vertex_words[offset + 18u], vertex_words[offset + 19u]));
+    }
+    return vertex;
+}
+
+
 //-- Vertex Shader -------------------------------------------------------------
 
 @vertex
@@ -72,6 +133,8 @@ fn vs_main(
     output.clip_position = vec4<f32>(2.0, 2.0, 2.0, 1.0); // Outside clip space
     output.world_normal = vec3<f32>(0.0, 0.0, 0.0);
     output.uv = vec2<f32>(0.0, 0.0);
+    output.world_tangent = vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    output.color = vec4<f32>(1.0, 1.0, 1.0, 1.0);
     output.texture_id = 0u;
     output.entity_id = 0u;
 
@@ -99,13 +162,13 @@ fn vs_main(
     let packed_indices = meshlet_triangle_indices[u32_index];
     let local_vertex_index_in_meshlet = (packed_indices >> (byte_in_u32 * 8u)) & 0xFFu;
 
-    // Second, use that local index to look up the global vertex index. This points
-    // into the main `vertices` buffer.
+    // Second, use that local index to look up the vertex's word offset in the
+    // `vertex_words` buffer.
     let vertex_index_in_list = meshlet.vertex_list_offset + local_vertex_index_in_meshlet;
     let final_vertex_index = meshlet_vertex_indices[vertex_index_in_list];
 
     // 5. Fetch the final vertex data using the resolved index.
-    let vertex = vertices[final_vertex_index];
+    let vertex = load_vertex(final_vertex_index, meshlet);
 
     // 6. Apply transformations using the transform_id from the draw command.
     let model_transform = transform_buffer[command.transform_id];
@@ -113,7 +176,12 @@ fn vs_main(
 
     output.clip_position = camera * world_pos;
     output.world_normal = normalize((model_transform * vec4<f32>(vertex.normal, 0.0)).xyz);
-    output.uv = vertex.uv;
+    output.uv = select(vertex.uv, vertex.uv1, meshlet.base_color_uv_set == 1u);
+    // A mirroring transform flips which way the bitangent points.
+    let linear = mat3x3<f32>(model_transform[0].xyz, model_transform[1].xyz, model_transform[2].xyz);
+    let handedness = select(1.0, -1.0, determinant(linear) < 0.0);
+    output.world_tangent = vec4<f32>(normalize((model_transform * vec4<f32>(vertex.tangent.xyz, 0.0)).xyz), vertex.tangent.w * handedness);
+    output.color = vertex.color;
     output.texture_id = command.texture_id;
     output.entity_id = command.entity_id;
 
@@ -127,7 +195,7 @@ fn vs_main(
 fn fs_main(in: VSOutput) -> FragmentOutput {
     // Sample the texture
     let base_color = textureSample(texture_array, texture_sampler, in.uv, in.texture_id);
-    let final_color = base_color.rgb;
+    let final_color = base_color.rgb * in.color.rgb;
     
     var output: FragmentOutput;
     output.color = vec4<f32>(final_color, 1.0);
@@ -1,11 +1,15 @@
 //-- Per-Vertex Data -----------------------------------------------------------
+// A vertex decoded from either layout of `types::SkinnedVertex` or
+// `types::QuantizedSkinnedVertex`.
 struct SkinnedVertex {
     position: vec4<f32>,
     normal: vec4<f32>,
     uv: vec2<f32>,
-    _padding: vec2<f32>, // Padding to match Rust struct
+    uv1: vec2<f32>,
     bone_indices: vec4<u32>,
     bone_weights: vec4<f32>,
+    tangent: vec4<f32>, // w is the bitangent sign
+    color: vec4<f32>,
 };
 
 //-- Static Asset Data ---------------------------------------------------------
@@ -14,15 +18,29 @@ struct MeshletDescription {
     triangle_list_offset: u32,
     triangle_count: u32,
     vertex_count: u32,
+    vertex_format: u32,         // VERTEX_FORMAT_*
+    base_color_uv_set: u32,     // 0 samples the base color with `uv`, 1 with `uv1`
+    _padding: vec2<u32>,
+    bounding_sphere: vec4<f32>, // center, radius
+    cone_apex: vec4<f32>,       // w unused
+    cone_axis_cutoff: vec4<f32>,
+    position_min: vec4<f32>,    // The mesh AABB quantized positions are relative to
+    position_extent: vec4<f32>,
 };
 
 //-- Per-Frame/Per-Draw Data ----------------------------------------------------
 struct AnimatedDrawCommand {
     meshlet_id: u32,
-    bone_set_id: u32, // An index pointing to the start of a block of 256 matrices
+    first_bone: u32, // Index of the draw's first matrix in bone_matrices
+    bone_count: u32, // How many matrices the draw's joint indices can name
     transform_id: u32, // Transform ID for positioning
     entity_id: u32,    // Entity ID for picking
     texture_id: u32,
+    morph_target_count: u32, // 0 when the mesh has no morph targets
+    morph_first_weight: u32, // Index into morph_weights
+    morph_first_delta: u32,  // Index into morph_deltas of the mesh's first vertex
+    first_vertex_word: u32,  // Where the mesh's vertices start in vertex_words
+    vertex_stride_words: u32,
 };
 
 //-- Vertex to Fragment Data ---------------------------------------------------
@@ -32,6 +50,8 @@ struct VSOutput {
     @location(1) uv: vec2<f32>,
     @location(2) @interpolate(flat) texture_id: u32,
     @location(3) @interpolate(flat) entity_id: u32,
+    @location(4) world_tangent: vec4<f32>,
+    @location(5) color: vec4<f32>,
 };
 
 //-- Fragment Output for MRT ---------------------------------------------------
@@ -46,19 +66,85 @@ struct FragmentOutput {
 @group(0) @binding(0) var<uniform> camera: mat4x4<f32>;
 
 // @group(1): Static Mesh Data (provided by AssetServer)
-@group(1) @binding(0) var<storage, read> vertices: array<SkinnedVertex>;
+@group(1) @binding(0) var<storage, read> vertex_words: array<u32>; // Vertices in the meshlet's format
 @group(1) @binding(1) var<storage, read> meshlet_vertex_indices: array<u32>;
 @group(1) @binding(2) var<storage, read> meshlet_triangle_indices: array<u32>; // u8s packed into u32s
 @group(1) @binding(3) var<storage, read> meshlet_descriptions: array<MeshletDescription>;
+// Per vertex, a position then a normal delta for each of the mesh's morph targets.
+@group(1) @binding(4) var<storage, read> morph_deltas: array<vec4<f32>>;
 
 // @group(2): Per-Draw Data
 @group(2) @binding(0) var<storage, read> indirection_buffer: array<AnimatedDrawCommand>;
 @group(2) @binding(1) var<storage, read> bone_matrices: array<mat4x4<f32>>; // Bone matrices now include world transform
+@group(2) @binding(2) var<storage, read> morph_weights: array<f32>;
 
 // @group(3): Texture Data (provided by AssetServer)
 @group(3) @binding(0) var texture_array: texture_2d_array<f32>;
 @group(3) @binding(1) var texture_sampler: sampler;
 
+//-- Vertex Decoding -----------------------------------------------------------
+
+// Matches `types::VertexFormat`.
+const VERTEX_FORMAT_FULL: u32 = 0u;
+const VERTEX_FORMAT_QUANTIZED: u32 = 1u;
+
+fn decode_octahedral(e: vec2<f32>) -> vec3<f32> {
+    var n = vec3<f32>(e.x, e.y, 1.0 - abs(e.x) - abs(e.y));
+    let t = max(-n.z, 0.0);
+    n.x += select(t, -t, n.x >= 0.0);
+    n.y += select(t, -t, n.y >= 0.0);
+    return normalize(n);
+}
+
+fn load_vec4(offset: u32) -> vec4<f32> {
+    return bitcast<vec4<f32>>(vec4<u32>(
+        vertex_words[offset], vertex_words[offset + 1u], vertex_words[offset + 2u], vertex_words[offset + 3u]));
+}
+
+// `offset` is the word offset of the vertex in `vertex_words`.
+fn load_vertex(offset: u32, meshlet: MeshletDescription) -> SkinnedVertex {
+    var vertex: SkinnedVertex;
+    if (meshlet.vertex_format == VERTEX_FORMAT_QUANTIZED) {
+        // unorm16 x, y, z, bitangent sign | snorm16 octahedral normal | f16 u, v
+        // | snorm16 octahedral tangent | f16 u1, v1 | unorm8 color | u8 bones | unorm16 weights
+        let position_zw = unpack2x16unorm(vertex_words[offset + 1u]);
+        let position = vec3<f32>(unpack2x16unorm(vertex_words[offset]), position_zw.x);
+        vertex.position = vec4<f32>(meshlet.position_min.xyz + position * meshlet.position_extent.xyz, 1.0);
+        vertex.normal = vec4<f32>(decode_octahedral(unpack2x16snorm(vertex_words[offset + 2u])), 0.0);
+        vertex.uv = unpack2x16float(vertex_words[offset + 3u]);
+        vertex.tangent = vec4<f32>(
+            decode_octahedral(unpack2x16snorm(vertex_words[offset + 4u])),
+            1.0 - 2.0 * position_zw.y,
+        );
+        vertex.uv1 = unpack2x16float(vertex_words[offset + 5u]);
+        vertex.color = unpack4x8unorm(vertex_words[offset + 6u]);
+        let bones = vertex_words[offset + 7u];
+        vertex.bone_indices = vec4<u32>(
+            extractBits(bones, 0u, 8u),
+            extractBits(bones, 8u, 8u),
+            extractBits(bones, 16u, 8u),
+            extractBits(bones, 24u, 8u),
+        );
+        vertex.bone_weights = vec4<f32>(
+            unpack2x16unorm(vertex_words[offset + 8u]),
+            unpack2x16unorm(vertex_words[offset + 9u]),
+        );
+    } else {
+        // vec4 position | vec4 normal | vec2 uv, vec2 uv1 | uvec4 bones | vec4 weights
+        // | vec4 tangent | vec4 color
+        vertex.position = load_vec4(offset);
+        vertex.normal = load_vec4(offset + 4u);
+        vertex.uv = bitcast<vec2<f32>>(vec2<u32>(vertex_words[offset + 8u], vertex_words[offset + 9u]));
+        vertex.uv1 = bitcast<vec2<f32>>(vec2<u32>(vertex_words[offset + 10u], vertex_words[offset + 11u]));
+        vertex.bone_indices = vec4<u32>(
+            vertex_words[offset + 12u], vertex_words[offset + 13u], vertex_words[offset + 14u], vertex_words[offset + 15u]);
+        vertex.bone_weights = load_vec4(offset + 16u);
+        vertex.tangent = load_vec4(offset + 20u);
+        vertex.color = load_vec4(offset + 24u);
+    }
+    return vertex;
+}
+
 //-- Vertex Shader -------------------------------------------------------------
 
 @vertex
@@ -90,9 +176,22 @@ fn vs_main(
     let vertex_index_in_list = meshlet.vertex_list_offset + local_vertex_index_in_meshlet;
     let final_vertex_index = meshlet_vertex_indices[vertex_index_in_list];
 
-    let vertex = vertices[final_vertex_index];
+    var vertex = load_vertex(final_vertex_index, meshlet);
+
+    // 5. Add the weighted morph target deltas in bind space, before skinning.
+    if (command.morph_target_count > 0u) {
+        let vertex_in_mesh = (final_vertex_index - command.first_vertex_word) / command.vertex_stride_words;
+        let first_delta = command.morph_first_delta + vertex_in_mesh * command.morph_target_count * 2u;
+        for (var t = 0u; t < command.morph_target_count; t = t + 1u) {
+            let weight = morph_weights[command.morph_first_weight + t];
+            if (weight != 0.0) {
+                vertex.position += vec4<f32>(morph_deltas[first_delta + t * 2u].xyz * weight, 0.0);
+                vertex.normal += vec4<f32>(morph_deltas[first_delta + t * 2u + 1u].xyz * weight, 0.0);
+            }
+        }
+    }
 
-    // 5. Calculate the skinning transform.
+    // 6. Calculate the skinning transform.
     var skin_transform: mat4x4<f32> = mat4x4<f32>(
         0.0, 0.0, 0.0, 0.0,
         0.0, 0.0, 0.0, 0.0,
@@ -108,15 +207,15 @@ fn vs_main(
     
     // Only apply skinning if we have valid weights
     if (total_weight > 0.001) {
-        // Use the bone_set_id as a block offset into the bone matrices array
-        // Each instance has 256 bone matrices, so multiply by 256 to get the start of this instance's bone matrices
-        let bone_matrix_offset = command.bone_set_id * 256u;
+        // Joint indices count from the draw's first matrix: skeleton bones for whole meshes,
+        // palette slots for meshes that were split at bake time
+        let bone_matrix_offset = command.first_bone;
         
         // Apply bone transformations with proper weight normalization
         for (var i = 0; i < 4; i = i + 1) {
             let bone_index = vertex.bone_indices[i];
             let bone_weight = vertex.bone_weights[i];
-            if (bone_weight > 0.001 && bone_index < 256u) {
+            if (bone_weight > 0.001 && bone_index < command.bone_count) {
                 // Normalize the weight and blend the bone matrices
                 let normalized_weight = bone_weight / total_weight;
                 let bone_matrix_index = bone_matrix_offset + bone_index;
@@ -133,7 +232,7 @@ fn vs_main(
         );
     }
 
-    // 6. Apply transformations.
+    // 7. Apply transformations.
     // The bone matrices already include the world transform, so we apply them directly to the vertex
     let skinned_pos = skin_transform * vertex.position;
     let world_pos = skinned_pos; // Bone matrices already include world transform
@@ -151,8 +250,17 @@ fn vs_main(
         // Provide a default normal if the calculated one is zero
         output.world_normal = vec3<f32>(0.0, 0.0, 1.0);
     }
+
+    // The tangent is skinned like the normal.
+    let skinned_tangent = (skin_transform * vec4<f32>(vertex.tangent.xyz, 0.0)).xyz;
+    if (length(skinned_tangent) > 0.0001) {
+        output.world_tangent = vec4<f32>(normalize(skinned_tangent), vertex.tangent.w);
+    } else {
+        output.world_tangent = vec4<f32>(1.0, 0.0, 0.0, vertex.tangent.w);
+    }
     
-    output.uv = vertex.uv;
+    output.uv = select(vertex.uv, vertex.uv1, meshlet.base_color_uv_set == 1u);
+    output.color = vertex.color;
     output.texture_id = command.texture_id;
     output.entity_id = command.entity_id;
 
@@ -167,7 +275,7 @@ fn fs_main(in: VSOutput) -> FragmentOutput {
     // Basic lighting
     let light_dir = normalize(vec3<f32>(0.5, 1.0, 0.5));
     let diffuse_light = max(dot(in.world_normal, light_dir), 0.1) + 0.1; // Adding ambient term
-    let final_color = base_color.rgb * diffuse_light;
+    let final_color = base_color.rgb * in.color.rgb * diffuse_light;
     
     var output: FragmentOutput;
     output.color = vec4<f32>(final_color, 1.0);
@@ -0,0 +1,691 @@
+// database/src/bake.rs - Imports source assets into the database
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rayon::prelude::*;
+
+use types::{
+    AABB, AnimatedModel, Animation, Composite, DatabaseMeta, Material, Model, Prefab, Scene, SourceManifestEntry,
+    VertexFormat, animation_key,
+};
+
+use crate::keyframes::{CompressionStats, KeyframeSettings, compress_animation};
+use crate::lod::{LodSettings, build_lods, build_skinned_lods};
+use crate::mipmap::{TextureUsage, build_mip_chain, texture_usages, usage_of};
+use crate::optimize::{OptimizeSettings, optimize_mesh, optimize_skinned_mesh};
+use crate::palette::split_for_palette;
+use crate::russimp_loader::RUSSIMP_EXTENSIONS;
+use crate::store::Table;
+use crate::tangents::{generate_skinned_tangents, generate_tangents};
+use crate::validate::{Finding, Severity, ValidationReport, validate_animated_model, validate_model};
+use crate::{AssetStore, AssetWriter, DatabaseError, Record};
+
+/// Called as each source finishes importing, with the number finished so far, the number
+/// being imported, the source key and whether it failed.
+pub type BakeProgress = Box<dyn Fn(usize, usize, &str, bool) + Send + Sync>;
+
+pub struct ModelDatabase {
+    store: AssetStore,
+    /// Import .gltf/.glb files with the glTF importer instead of russimp.
+    use_gltf: bool,
+    settings: BakeSettings,
+    /// Import threads; zero uses one per core.
+    jobs: usize,
+    /// Fail sources whose validation finds errors instead of baking them anyway.
+    strict: bool,
+    progress: Option<BakeProgress>,
+}
+
+/// Options that change what the importers produce. They are recorded per source, so a bake
+/// with different settings re-imports everything they affect.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BakeSettings {
+    pub lod: LodSettings,
+    pub optimize: OptimizeSettings,
+    pub keyframes: KeyframeSettings,
+    /// Prefixes of the source keys whose meshes are stored as `VertexFormat::Quantized`. An
+    /// empty prefix selects every source.
+    pub quantize: Vec<String>,
+}
+
+impl BakeSettings {
+    pub fn vertex_format_for(&self, source_key: &str) -> VertexFormat {
+        if self.quantize.iter().any(|prefix| source_key.starts_with(prefix.as_str())) {
+            VertexFormat::Quantized
+        } else {
+            VertexFormat::Full
+        }
+    }
+
+    /// Everything the output for `source_key` depends on, so that only the sources a change
+    /// affects are re-baked.
+    fn fingerprint(&self, source_key: &str) -> String {
+        format!(
+            "{:?} {:?} {:?} {:?}",
+            self.lod,
+            self.optimize,
+            self.keyframes,
+            self.vertex_format_for(source_key)
+        )
+    }
+}
+
+/// What a call to `populate_from_assets` changed, by source path relative to the assets folder.
+#[derive(Debug, Default)]
+pub struct BakeReport {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub unchanged: Vec<String>,
+    pub removed: Vec<String>,
+    /// Sources that could not be imported, with the error. Whatever they produced before
+    /// stays in the database until they bake again.
+    pub failed: Vec<(String, String)>,
+    /// Totals over the animation clips baked by this call.
+    pub keyframes: CompressionStats,
+    /// What validation found in the sources this call imported.
+    pub validation: ValidationReport,
+}
+
+impl BakeReport {
+    pub fn log_summary(&self) {
+        log::info!(
+            "[DB] Bake finished: {} added, {} updated, {} unchanged, {} removed, {} failed",
+            self.added.len(),
+            self.updated.len(),
+            self.unchanged.len(),
+            self.removed.len(),
+            self.failed.len()
+        );
+        if self.keyframes.keys_before > 0 {
+            self.keyframes.log("all clips");
+        }
+        for source in &self.added {
+            log::info!("    + {source}");
+        }
+        for source in &self.updated {
+            log::info!("    ~ {source}");
+        }
+        for source in &self.removed {
+            log::info!("    - {source}");
+        }
+        for (source, error) in &self.failed {
+            log::info!("    ! {source}: {error}");
+        }
+        log::info!(
+            "[DB] Validation: {} errors, {} warnings",
+            self.validation.count(Severity::Error),
+            self.validation.count(Severity::Warning)
+        );
+    }
+}
+
+impl ModelDatabase {
+    pub fn new<P: AsRef<Path>>(path: P, use_gltf: bool) -> Result<Self, DatabaseError> {
+        let store = AssetStore::create(path)?;
+        Ok(Self {
+            store,
+            use_gltf,
+            settings: BakeSettings::default(),
+            jobs: 0,
+            strict: false,
+            progress: None,
+        })
+    }
+
+    pub fn with_settings(mut self, settings: BakeSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn with_progress(mut self, progress: BakeProgress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn store(&self) -> &AssetStore {
+        &self.store
+    }
+
+    /// Bakes every supported file under `assets_dir`, skipping sources whose content hash
+    /// matches the manifest and dropping table entries whose source file has disappeared.
+    /// Changed sources are imported in parallel and written in path order; one that fails is
+    /// reported in `BakeReport::failed` without stopping the others. Only the imported sources
+    /// are validated, so a full report needs a bake into a fresh database.
+    pub fn populate_from_assets<P: AsRef<Path>>(
+        &self,
+        assets_dir: P,
+    ) -> Result<BakeReport, Box<dyn std::error::Error>> {
+        let assets_dir = assets_dir.as_ref();
+        let mut sources = Vec::new();
+        collect_sources(assets_dir, &mut sources)?;
+        sources.sort();
+
+        // Unchanged sources are reused as stored, so the stored data has to be current first.
+        crate::migrate(&self.store)?;
+
+        let mut report = BakeReport::default();
+        let writer = self.store.write()?;
+
+        let mut old_manifest: HashMap<String, SourceManifestEntry> = HashMap::new();
+        for result in writer.iter::<SourceManifestEntry>()? {
+            match result {
+                Ok((source_key, entry)) => {
+                    old_manifest.insert(source_key, entry);
+                }
+                Err(e) => log::warn!("[DB] Ignoring unreadable manifest entry: {e}"),
+            }
+        }
+
+        let mut new_manifest: HashMap<String, SourceManifestEntry> = HashMap::new();
+        // Entries whose outputs may now be stale, either replaced by a re-bake or removed.
+        let mut superseded: Vec<SourceManifestEntry> = Vec::new();
+
+        let mut pending = Vec::new();
+        for path in &sources {
+            let source_key = source_key(assets_dir, path);
+            let settings = self.settings.fingerprint(&source_key);
+            let previous = old_manifest.remove(&source_key);
+            let source_hash = match hash_source(path) {
+                Ok(hash) => hash,
+                Err(e) => {
+                    fail(&mut report, &mut new_manifest, source_key, previous, e.to_string());
+                    continue;
+                }
+            };
+            // Files only the importer can name are taken from the last import, which read the
+            // same source.
+            let dependencies = previous
+                .as_ref()
+                .filter(|previous| previous.source_hash == source_hash)
+                .map_or(&[][..], |previous| &previous.dependencies);
+            let content_hash = content_hash(&source_hash, path, dependencies);
+
+            if let Some(previous) = &previous
+                && previous.content_hash == content_hash
+                && previous.importer == importer_for(path, self.use_gltf)
+                && previous.settings == settings
+            {
+                log::debug!("[DB] Unchanged: {source_key}");
+                new_manifest.insert(source_key.clone(), previous.clone());
+                report.unchanged.push(source_key);
+                continue;
+            }
+            pending.push(PendingSource { path, source_key, source_hash, settings, previous });
+        }
+
+        let baked = self.import_all(&pending)?;
+        for (source, baked) in pending.into_iter().zip(baked) {
+            let mut baked = match baked {
+                Ok(baked) => baked,
+                Err(error) => {
+                    fail(&mut report, &mut new_manifest, source.source_key, source.previous, error);
+                    continue;
+                }
+            };
+            let findings = std::mem::take(&mut baked.findings);
+            if let Some(error) = report.validation.add(&source.source_key, findings, self.strict) {
+                fail(&mut report, &mut new_manifest, source.source_key, source.previous, error);
+                continue;
+            }
+            report.keyframes.add(&baked.keyframes);
+            let mut entry = baked.write(&writer)?;
+            entry.content_hash = content_hash(&source.source_hash, source.path, &entry.dependencies);
+            entry.source_hash = source.source_hash;
+            entry.importer = importer_for(source.path, self.use_gltf).to_string();
+            entry.settings = source.settings;
+
+            writer.put(&source.source_key, &entry)?;
+            new_manifest.insert(source.source_key.clone(), entry);
+
+            match source.previous {
+                Some(previous) => {
+                    superseded.push(previous);
+                    report.updated.push(source.source_key);
+                }
+                None => report.added.push(source.source_key),
+            }
+        }
+
+        // Whatever is left in the old manifest no longer exists on disk.
+        for (source_key, entry) in old_manifest {
+            writer.remove::<SourceManifestEntry>(&source_key)?;
+            superseded.push(entry);
+            report.removed.push(source_key);
+        }
+        report.removed.sort();
+
+        // A key is only stale if no current source still produces it.
+        let claimed = |select: fn(&SourceManifestEntry) -> &Vec<String>| -> HashSet<String> {
+            new_manifest.values().flat_map(|e| select(e).iter().cloned()).collect()
+        };
+        let claimed_models = claimed(|e| &e.models);
+        let claimed_animated_models = claimed(|e| &e.animated_models);
+        let claimed_animations = claimed(|e| &e.animations);
+        let claimed_textures = claimed(|e| &e.textures);
+        let claimed_materials = claimed(|e| &e.materials);
+        let claimed_composites = claimed(|e| &e.composites);
+        let claimed_prefabs = claimed(|e| &e.prefabs);
+        let claimed_scenes = claimed(|e| &e.scenes);
+
+        for entry in &superseded {
+            for key in entry.models.iter().filter(|k| !claimed_models.contains(*k)) {
+                log::info!("[DB] Removing stale model: {key}");
+                writer.remove::<Model>(key)?;
+            }
+            for key in entry.animated_models.iter().filter(|k| !claimed_animated_models.contains(*k)) {
+                log::info!("[DB] Removing stale animated model: {key}");
+                writer.remove::<AnimatedModel>(key)?;
+            }
+            for key in entry.animations.iter().filter(|k| !claimed_animations.contains(*k)) {
+                log::info!("[DB] Removing stale animation: {key}");
+                writer.remove::<Animation>(key)?;
+            }
+            for key in entry.textures.iter().filter(|k| !claimed_textures.contains(*k)) {
+                log::info!("[DB] Removing stale texture: {key}");
+                writer.remove_texture(key)?;
+            }
+            for key in entry.materials.iter().filter(|k| !claimed_materials.contains(*k)) {
+                log::info!("[DB] Removing stale material: {key}");
+                writer.remove::<Material>(key)?;
+            }
+            for key in entry.composites.iter().filter(|k| !claimed_composites.contains(*k)) {
+                log::info!("[DB] Removing stale composite: {key}");
+                writer.remove::<Composite>(key)?;
+            }
+            for key in entry.prefabs.iter().filter(|k| !claimed_prefabs.contains(*k)) {
+                log::info!("[DB] Removing stale prefab: {key}");
+                writer.remove::<Prefab>(key)?;
+            }
+            for key in entry.scenes.iter().filter(|k| !claimed_scenes.contains(*k)) {
+                log::info!("[DB] Removing stale scene: {key}");
+                writer.remove::<Scene>(key)?;
+            }
+        }
+
+        writer.put(DatabaseMeta::KEY, &crate::current_meta())?;
+        writer.commit()?;
+        report.log_summary();
+        Ok(report)
+    }
+
+    /// Imports `pending` on the worker pool, keeping the results in the same order.
+    fn import_all(
+        &self,
+        pending: &[PendingSource],
+    ) -> Result<Vec<Result<BakedSource, String>>, Box<dyn std::error::Error>> {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(self.jobs).build()?;
+        let finished = AtomicUsize::new(0);
+        Ok(pool.install(|| {
+            pending
+                .par_iter()
+                .map(|source| {
+                    let vertex_format = self.settings.vertex_format_for(&source.source_key);
+                    let result = process_file(source.path, self.use_gltf, &self.settings, vertex_format)
+                        .map_err(|e| e.to_string());
+                    let finished = finished.fetch_add(1, Ordering::Relaxed) + 1;
+                    match &result {
+                        Ok(_) => log::info!("[DB] [{finished}/{}] Imported {}", pending.len(), source.source_key),
+                        Err(e) => log::warn!("[DB] [{finished}/{}] Failed {}: {e}", pending.len(), source.source_key),
+                    }
+                    if let Some(progress) = &self.progress {
+                        progress(finished, pending.len(), &source.source_key, result.is_err());
+                    }
+                    result
+                })
+                .collect()
+        }))
+    }
+}
+
+/// A source whose hash, importer or settings changed since its last bake.
+struct PendingSource<'a> {
+    path: &'a Path,
+    source_key: String,
+    source_hash: [u8; 32],
+    settings: String,
+    previous: Option<SourceManifestEntry>,
+}
+
+/// Records `source` as failed. Its previous entry is carried over unchanged, so its old
+/// outputs are not removed as stale and the next bake tries it again.
+fn fail(
+    report: &mut BakeReport,
+    new_manifest: &mut HashMap<String, SourceManifestEntry>,
+    source_key: String,
+    previous: Option<SourceManifestEntry>,
+    error: String,
+) {
+    if let Some(previous) = previous {
+        new_manifest.insert(source_key.clone(), previous);
+    }
+    report.failed.push((source_key, error));
+}
+
+/// Everything one source produced, already encoded by the worker that imported it, so the
+/// thread holding the write transaction only inserts it.
+#[derive(Default)]
+struct BakedSource {
+    entry: SourceManifestEntry,
+    records: Vec<(Table, String, Vec<u8>)>,
+    textures: Vec<(String, Vec<u8>)>,
+    keyframes: CompressionStats,
+    findings: Vec<Finding>,
+}
+
+impl BakedSource {
+    fn put<T: Record>(&mut self, key: &str, value: &T) -> Result<(), DatabaseError> {
+        self.records.push((T::TABLE, key.to_string(), value.encode(key)?));
+        Ok(())
+    }
+
+    fn put_texture(&mut self, name: &str, bytes: Vec<u8>) {
+        self.textures.push((name.to_string(), bytes));
+    }
+
+    /// Inserts the outputs and returns the manifest entry listing them.
+    fn write(self, writer: &AssetWriter) -> Result<SourceManifestEntry, DatabaseError> {
+        for (name, bytes) in &self.textures {
+            writer.put_texture(name, bytes)?;
+        }
+        for (table, key, bytes) in &self.records {
+            writer.put_raw(*table, key, bytes)?;
+        }
+        Ok(self.entry)
+    }
+}
+
+fn is_model_extension(extension: &str) -> bool {
+    RUSSIMP_EXTENSIONS.contains(&extension)
+}
+
+fn is_source_file(path: &Path) -> bool {
+    match path.extension().and_then(|s| s.to_str()) {
+        Some(extension) => is_model_extension(extension) || extension == "png",
+        None => false,
+    }
+}
+
+/// `--gltf` only switches glTF files to the glTF importer; everything else needs assimp.
+fn importer_for(path: &Path, use_gltf: bool) -> &'static str {
+    match path.extension().and_then(|s| s.to_str()) {
+        Some("gltf") | Some("glb") if use_gltf => "gltf",
+        _ => "russimp",
+    }
+}
+
+fn collect_sources(dir: &Path, sources: &mut Vec<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    if dir.is_dir() {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.is_dir() {
+                collect_sources(&path, sources)?;
+            } else if is_source_file(&path) {
+                sources.push(path);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Manifest key for a source: its path relative to the assets folder, always with `/` separators.
+fn source_key(assets_dir: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(assets_dir).unwrap_or(path);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Hashes a source file together with any external buffers, materials or images it names in a
+/// way the baker can read, so that editing `PalmTree.bin` re-bakes `PalmTree.gltf`. Files only
+/// the importer can name are added by `content_hash`.
+fn hash_source(path: &Path) -> Result<[u8; 32], Box<dyn std::error::Error>> {
+    let bytes = fs::read(path)?;
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&bytes);
+
+    if path.extension().and_then(|s| s.to_str()) == Some("gltf") {
+        let base = path.parent().unwrap_or(Path::new("."));
+        let gltf = gltf::Gltf::from_slice(&bytes)?;
+        let buffer_uris = gltf.buffers().filter_map(|b| match b.source() {
+            gltf::buffer::Source::Uri(uri) => Some(uri),
+            gltf::buffer::Source::Bin => None,
+        });
+        let image_uris = gltf.images().filter_map(|i| match i.source() {
+            gltf::image::Source::Uri { uri, .. } => Some(uri),
+            gltf::image::Source::View { .. } => None,
+        });
+        for uri in buffer_uris.chain(image_uris) {
+            if uri.starts_with("data:") {
+                continue; // Embedded data is already covered by the .gltf bytes.
+            }
+            hasher.update(uri.as_bytes());
+            match fs::read(base.join(uri)) {
+                Ok(external) => {
+                    hasher.update(&external);
+                }
+                Err(e) => log::warn!("[DB] Could not read '{uri}' referenced by {path:?}: {e}"),
+            }
+        }
+    }
+
+    if path.extension().and_then(|s| s.to_str()) == Some("obj") {
+        let base = path.parent().unwrap_or(Path::new("."));
+        for mtl in referenced_files(&bytes, "mtllib") {
+            hasher.update(mtl.as_bytes());
+            match fs::read(base.join(&mtl)) {
+                Ok(mtl_bytes) => {
+                    hasher.update(&mtl_bytes);
+                }
+                Err(e) => log::warn!("[DB] Could not read '{mtl}' referenced by {path:?}: {e}"),
+            }
+        }
+    }
+
+    Ok(*hasher.finalize().as_bytes())
+}
+
+/// Adds the files `path` depends on, named relative to its folder as
+/// `SourceManifestEntry::dependencies` records them, to its `source_hash`. Editing a texture
+/// next to an FBX re-bakes the FBX without assimp parsing it again to find the texture.
+fn content_hash(source_hash: &[u8; 32], path: &Path, dependencies: &[String]) -> [u8; 32] {
+    if dependencies.is_empty() {
+        return *source_hash;
+    }
+    let base = path.parent().unwrap_or(Path::new("."));
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(source_hash);
+    for dependency in dependencies {
+        hasher.update(dependency.as_bytes());
+        match fs::read(base.join(dependency)) {
+            Ok(bytes) => {
+                hasher.update(&bytes);
+            }
+            Err(e) => log::warn!("[DB] Could not read '{dependency}' referenced by {path:?}: {e}"),
+        }
+    }
+    *hasher.finalize().as_bytes()
+}
+
+/// File names following `keyword` in an OBJ or MTL file. Options before the name are skipped
+/// by taking the last token.
+fn referenced_files(bytes: &[u8], keyword: &str) -> Vec<String> {
+    String::from_utf8_lossy(bytes)
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim().strip_prefix(keyword)?;
+            if !rest.starts_with(char::is_whitespace) {
+                return None;
+            }
+            rest.split_whitespace().last().map(|name| name.replace('\\', "/"))
+        })
+        .collect()
+}
+
+/// Imports one source file into the records to store for it.
+fn process_file(
+    path: &Path,
+    use_gltf: bool,
+    settings: &BakeSettings,
+    vertex_format: VertexFormat,
+) -> Result<BakedSource, Box<dyn std::error::Error>> {
+    let extension = path.extension().and_then(|s| s.to_str());
+    let file_name = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    let mut baked = BakedSource::default();
+    let mut entry = SourceManifestEntry::default();
+
+    match extension {
+        Some(ext) if is_model_extension(ext) => {
+            let model_name = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("unknown_model");
+
+            let importer = importer_for(path, use_gltf);
+            log::info!("[DB] Processing model: {model_name} (using {importer})");
+
+            let mut imported = if importer == "gltf" {
+                crate::gltf_loader::load_gltf_model(path, model_name, false)?
+            } else {
+                crate::russimp_loader::load_russimp_model(path, model_name)?
+            };
+
+            // Relative, so moving the whole assets folder does not invalidate every hash.
+            let base = path.parent().unwrap_or(Path::new("."));
+            entry.dependencies = imported.dependencies.iter().map(|file| source_key(base, file)).collect();
+
+            // Checked as imported, before anything is generated or welded.
+            baked.findings.extend(imported.model.iter().flat_map(validate_model));
+            baked.findings.extend(imported.animated_models.iter().flat_map(validate_animated_model));
+            for finding in &baked.findings {
+                match finding.severity {
+                    Severity::Error => log::error!("[DB]    - {}: {}", finding.asset, finding.issue),
+                    Severity::Warning => log::warn!("[DB]    - {}: {}", finding.asset, finding.issue),
+                }
+            }
+
+            for animated_model in &mut imported.animated_models {
+                split_for_palette(animated_model)?;
+            }
+
+            // Tangents take part in welding, and LODs are simplified from the welded, optimized
+            // mesh.
+            for mesh in imported.model.iter_mut().flat_map(|m| &mut m.meshes) {
+                generate_tangents(mesh);
+                optimize_mesh(mesh, &settings.optimize)?;
+                build_lods(mesh, &settings.lod)?;
+            }
+            for animated_model in &mut imported.animated_models {
+                for mesh in &mut animated_model.meshes {
+                    generate_skinned_tangents(mesh);
+                    optimize_skinned_mesh(mesh, &settings.optimize)?;
+                    build_skinned_lods(mesh, &settings.lod)?;
+                    mesh.grow_bounds_for_morph_targets();
+                    animated_model.aabb.min = animated_model.aabb.min.min(mesh.aabb.min);
+                    animated_model.aabb.max = animated_model.aabb.max.max(mesh.aabb.max);
+                }
+            }
+
+            if vertex_format == VertexFormat::Quantized {
+                for mesh in imported.model.iter_mut().flat_map(|m| &mut m.meshes) {
+                    mesh.quantize();
+                }
+                for mesh in imported.animated_models.iter_mut().flat_map(|m| &mut m.meshes) {
+                    if !mesh.quantize() {
+                        log::warn!("[DB]    - '{}' has joints past a byte; keeping full vertices", mesh.name);
+                    }
+                }
+            }
+
+            // Save textures with their mip chains, filtered according to how materials use them
+            let usages = texture_usages(imported.materials.iter().map(|(_, m)| m));
+            for (texture_name, image) in imported.textures {
+                let texture = build_mip_chain(&image, usage_of(&usages, &texture_name));
+                baked.put_texture(&texture_name, texture.to_ktx2());
+                entry.textures.push(texture_name);
+            }
+
+            // Save materials
+            for (key, material) in imported.materials {
+                baked.put(&key, &material)?;
+                entry.materials.push(key);
+            }
+
+            // Save the static model with its prefab, the authored cameras and lights, one animated
+            // model per skin, and the composite listing them
+            let mut composite = Composite {
+                name: model_name.to_string(),
+                model: None,
+                animated_models: Vec::new(),
+                aabb: AABB::default(),
+            };
+            let mut aabbs = Vec::new();
+            if let Some(model) = imported.model {
+                baked.put(model_name, &model)?;
+                entry.models.push(model_name.to_string());
+                composite.model = Some(model_name.to_string());
+                aabbs.push(model.aabb);
+            }
+            if let Some(prefab) = imported.prefab {
+                baked.put(model_name, &prefab)?;
+                entry.prefabs.push(model_name.to_string());
+            }
+            if let Some(scene) = imported.scene {
+                baked.put(model_name, &scene)?;
+                entry.scenes.push(model_name.to_string());
+            }
+            for animated_model in imported.animated_models {
+                baked.put(&animated_model.name, &animated_model)?;
+                entry.animated_models.push(animated_model.name.clone());
+                composite.animated_models.push(animated_model.name);
+                aabbs.push(animated_model.aabb);
+            }
+            if !aabbs.is_empty() {
+                composite.aabb = aabbs
+                    .into_iter()
+                    .reduce(|a, b| AABB { min: a.min.min(b.min), max: a.max.max(b.max) })
+                    .unwrap_or_default();
+                baked.put(model_name, &composite)?;
+                entry.composites.push(model_name.to_string());
+            }
+
+            // Save animations, namespaced by the animated model they drive
+            for mut animation in imported.animations {
+                baked.keyframes.add(&compress_animation(&mut animation, &settings.keyframes));
+                let key = animation_key(&animation.skeleton, &animation.name);
+                baked.put(&key, &animation)?;
+                entry.animations.push(key);
+            }
+        }
+        Some("png") => {
+            log::info!("[DB] Processing texture: {file_name}");
+            let image = image::open(path)?.to_rgba8();
+            let texture = build_mip_chain(&image, TextureUsage::Color);
+            baked.put_texture(file_name, texture.to_ktx2());
+            entry.textures.push(file_name.to_string());
+        }
+        _ => {
+            // Skip other file types
+        }
+    }
+    baked.entry = entry;
+    Ok(baked)
+}
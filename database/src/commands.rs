@@ -0,0 +1,404 @@
+// database/src/commands.rs - Read-only and maintenance subcommands of the baker CLI
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use database::{AssetKind, AssetReader, AssetStore, Record, TextureData};
+use glam::Mat4;
+use types::{
+    AnimatedModel, Animation, Composite, Material, Meshlets, Model, Prefab, Scene, TEXTURE_BLOB_TABLE, TextureRef,
+    VertexAttributes,
+};
+
+type Located = (AssetKind, Vec<u8>);
+
+fn selected(kind: Option<AssetKind>) -> Vec<AssetKind> {
+    kind.map_or_else(|| AssetKind::ALL.to_vec(), |k| vec![k])
+}
+
+/// Finds which tables contain `key`, restricted to `kind` when given.
+fn locate(reader: &AssetReader, key: &str, kind: Option<AssetKind>) -> Result<Vec<Located>, Box<dyn std::error::Error>> {
+    let mut found = Vec::new();
+    for kind in selected(kind) {
+        let bytes = match kind {
+            AssetKind::Texture => reader.get_texture(key)?,
+            _ => reader.get_raw(kind.table(), key)?,
+        };
+        if let Some(bytes) = bytes {
+            found.push((kind, bytes));
+        }
+    }
+    Ok(found)
+}
+
+fn locate_one(reader: &AssetReader, key: &str, kind: Option<AssetKind>) -> Result<Located, Box<dyn std::error::Error>> {
+    let mut found = locate(reader, key, kind)?;
+    match found.len() {
+        0 => Err(format!("'{key}' was not found in the database").into()),
+        1 => Ok(found.remove(0)),
+        _ => {
+            let kinds: Vec<_> = found.iter().map(|(k, _)| k.label()).collect();
+            Err(format!("'{key}' exists in several tables ({}); pass --kind", kinds.join(", ")).into())
+        }
+    }
+}
+
+pub fn list(store: &AssetStore, kind: Option<AssetKind>) -> Result<(), Box<dyn std::error::Error>> {
+    match store.meta()? {
+        Some(meta) => println!(
+            "Format version {} (baked by {}), current is {}",
+            meta.format_version,
+            meta.baker_version,
+            types::FORMAT_VERSION
+        ),
+        None => println!("Format version 0 (unversioned), current is {}", types::FORMAT_VERSION),
+    }
+    let reader = store.read()?;
+    for kind in selected(kind) {
+        if kind == AssetKind::Texture {
+            list_textures(&reader)?;
+            continue;
+        }
+        let mut entries = reader.entries(kind.table())?;
+        entries.sort();
+        let total: usize = entries.iter().map(|(_, size)| size).sum();
+        println!("{} ({}, {}):", kind.label(), entries.len(), format_size(total));
+        for (key, size) in entries {
+            println!("  {key:<60} {:>10}", format_size(size));
+        }
+    }
+    Ok(())
+}
+
+/// Texture names with the size and key of the blob each resolves to.
+fn list_textures(reader: &AssetReader) -> Result<(), Box<dyn std::error::Error>> {
+    let blobs: HashMap<String, usize> = reader.entries(TEXTURE_BLOB_TABLE)?.into_iter().collect();
+    let mut textures = reader.iter::<TextureRef>()?.collect::<Result<Vec<_>, _>>()?;
+    textures.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let stored: usize = blobs.values().sum();
+    println!(
+        "{} ({}, {} in {} unique blobs):",
+        AssetKind::Texture.label(),
+        textures.len(),
+        format_size(stored),
+        blobs.len()
+    );
+    for (key, texture) in &textures {
+        let size = blobs.get(&texture.blob).copied().unwrap_or_default();
+        println!("  {key:<60} {:>10}  blob {}", format_size(size), &texture.blob[..12]);
+    }
+    Ok(())
+}
+
+pub fn inspect(store: &AssetStore, key: &str, kind: Option<AssetKind>) -> Result<(), Box<dyn std::error::Error>> {
+    let found = locate(&store.read()?, key, kind)?;
+    if found.is_empty() {
+        return Err(format!("'{key}' was not found in the database").into());
+    }
+    for (kind, bytes) in found {
+        println!("{} '{key}' ({}):", kind.label(), format_size(bytes.len()));
+        match kind {
+            AssetKind::Model => {
+                let model = Model::decode(key, &bytes)?;
+                println!("  AABB: {:?} .. {:?}", model.aabb.min.truncate(), model.aabb.max.truncate());
+                println!("  Meshes: {}", model.meshes.len());
+                for mesh in &model.meshes {
+                    println!(
+                        "    - {}: {} {:?} vertices{}, {} triangles, {}, texture {:?}, material {:?}",
+                        mesh.name,
+                        mesh.vertex_count(),
+                        mesh.vertex_format(),
+                        describe_attributes(&mesh.attributes),
+                        mesh.indices.len() / 3,
+                        describe_meshlets(mesh.meshlets.as_ref()),
+                        mesh.texture_name,
+                        mesh.material
+                    );
+                }
+            }
+            AssetKind::AnimatedModel => {
+                let model = AnimatedModel::decode(key, &bytes)?;
+                println!("  AABB: {:?} .. {:?}", model.aabb.min.truncate(), model.aabb.max.truncate());
+                println!("  Meshes: {}", model.meshes.len());
+                for mesh in &model.meshes {
+                    println!(
+                        "    - {}: {} {:?} vertices{}, {} triangles, {}, texture {:?}, material {:?}",
+                        mesh.name,
+                        mesh.vertex_count(),
+                        mesh.vertex_format(),
+                        describe_attributes(&mesh.attributes),
+                        mesh.indices.len() / 3,
+                        describe_meshlets(mesh.meshlets.as_ref()),
+                        mesh.texture_name,
+                        mesh.material
+                    );
+                    if !mesh.bone_palette.is_empty() {
+                        println!("        {} bones through a palette", mesh.bone_palette.len());
+                    }
+                    if !mesh.morph_targets.is_empty() {
+                        println!(
+                            "        {} morph targets driven by {:?}, weights {:?}",
+                            mesh.morph_targets.len(),
+                            mesh.node_name,
+                            mesh.morph_weights
+                        );
+                    }
+                }
+                let bones = &model.skeleton.bones;
+                let roots = bones.iter().filter(|b| b.parent_index.is_none()).count();
+                println!("  Skeleton: {} bones, {} roots", bones.len(), roots);
+                for (i, bone) in bones.iter().enumerate() {
+                    let parent = bone.parent_index.map_or_else(|| "-".to_string(), |p| p.to_string());
+                    println!("    {i:>3} {:<40} parent {parent}", bone.name);
+                }
+            }
+            AssetKind::Animation => {
+                let animation = Animation::decode(key, &bytes)?;
+                println!("  Clip: '{}', authored for '{}'", animation.name, animation.skeleton);
+                println!(
+                    "  Duration: {:.3}s ({} ticks at {} ticks/s)",
+                    animation.duration_in_ticks / animation.ticks_per_second,
+                    animation.duration_in_ticks,
+                    animation.ticks_per_second
+                );
+                println!("  Channels: {}", animation.channels.len());
+                for channel in &animation.channels {
+                    println!(
+                        "    - {:<40} pos {:>4}  rot {:>4}{}  scale {:>4}",
+                        channel.bone_name,
+                        channel.position_keys.len(),
+                        channel.rotation_key_count(),
+                        if channel.quantized_rotation_keys.is_some() { " (packed)" } else { "" },
+                        channel.scale_keys.len()
+                    );
+                }
+                if !animation.morph_channels.is_empty() {
+                    println!("  Weights channels: {}", animation.morph_channels.len());
+                    for channel in &animation.morph_channels {
+                        println!("    - {:<40} keys {:>4}", channel.node_name, channel.keys.len());
+                    }
+                }
+            }
+            AssetKind::Material => {
+                let material = Material::decode(key, &bytes)?;
+                println!("  Name: {}", material.name);
+                println!("  Base color: {:?} x {:?}", material.base_color_factor, material.base_color_texture);
+                println!(
+                    "  Metallic/roughness: {} / {} x {:?}",
+                    material.metallic_factor, material.roughness_factor, material.metallic_roughness_texture
+                );
+                println!("  Normal: {:?} (scale {})", material.normal_texture, material.normal_scale);
+                println!("  Occlusion: {:?} (strength {})", material.occlusion_texture, material.occlusion_strength);
+                println!("  Emissive: {:?} x {:?}", material.emissive_factor, material.emissive_texture);
+                println!(
+                    "  Alpha: {:?} (cutoff {}), double-sided: {}",
+                    material.alpha_mode, material.alpha_cutoff, material.double_sided
+                );
+            }
+            AssetKind::Composite => {
+                let composite = Composite::decode(key, &bytes)?;
+                println!("  AABB: {:?} .. {:?}", composite.aabb.min.truncate(), composite.aabb.max.truncate());
+                println!("  Static model: {:?}", composite.model);
+                println!("  Animated models: {}", composite.animated_models.len());
+                for animated_model in &composite.animated_models {
+                    println!("    - {animated_model}");
+                }
+            }
+            AssetKind::Prefab => {
+                let prefab = Prefab::decode(key, &bytes)?;
+                println!("  Model: {}", prefab.model);
+                println!("  Nodes: {}", prefab.nodes.len());
+                for (i, node) in prefab.nodes.iter().enumerate() {
+                    let parent = node.parent_index.map_or_else(|| "-".to_string(), |p| p.to_string());
+                    println!("    {i:>3} {:<40} parent {parent:<4} meshes {:?}", node.name, node.meshes);
+                }
+            }
+            AssetKind::Scene => {
+                let scene = Scene::decode(key, &bytes)?;
+                println!("  Cameras: {}", scene.cameras.len());
+                for camera in &scene.cameras {
+                    println!(
+                        "    - {} at node '{}', position {:?}: {:?}",
+                        camera.name, camera.node_name, camera.translation, camera.projection
+                    );
+                }
+                println!("  Lights: {}", scene.lights.len());
+                for light in &scene.lights {
+                    println!(
+                        "    - {} at node '{}', position {:?}: {:?}, color {:?} x {}, range {:?}",
+                        light.name, light.node_name, light.translation, light.kind, light.color, light.intensity, light.range
+                    );
+                }
+            }
+            AssetKind::Texture => match TextureData::from_ktx2(key, &bytes) {
+                Ok(texture) => println!(
+                    "  {}x{} {}, {} mip levels",
+                    texture.width,
+                    texture.height,
+                    if texture.srgb { "sRGB" } else { "linear" },
+                    texture.levels.len()
+                ),
+                Err(e) => println!("  {e}"),
+            },
+        }
+    }
+    Ok(())
+}
+
+pub fn extract(store: &AssetStore, key: &str, kind: Option<AssetKind>, out: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let reader = store.read()?;
+    let (kind, bytes) = locate_one(&reader, key, kind)?;
+    let contents = match kind {
+        // The full chain as stored, or the top level when a PNG is asked for.
+        AssetKind::Texture if out.extension().is_some_and(|e| e.eq_ignore_ascii_case("png")) => {
+            let texture = TextureData::from_ktx2(key, &bytes)?;
+            let image = image::RgbaImage::from_raw(texture.width, texture.height, texture.levels[0].clone())
+                .ok_or("mip level 0 does not match the texture size")?;
+            let mut png = std::io::Cursor::new(Vec::new());
+            image.write_to(&mut png, image::ImageFormat::Png)?;
+            png.into_inner()
+        }
+        AssetKind::Texture => bytes,
+        AssetKind::Model => {
+            // Meshes are written once per node of the model's prefab that places them.
+            let model = Model::decode(key, &bytes)?;
+            let placements: Vec<(usize, Mat4)> = match reader.get::<Prefab>(key)? {
+                Some(prefab) => prefab
+                    .nodes
+                    .iter()
+                    .zip(prefab.world_transforms())
+                    .flat_map(|(node, transform)| node.meshes.iter().map(move |&mesh| (mesh, transform)))
+                    .filter(|(mesh, _)| *mesh < model.meshes.len())
+                    .collect(),
+                None => (0..model.meshes.len()).map(|mesh| (mesh, Mat4::IDENTITY)).collect(),
+            };
+            let meshes = placements.iter().map(|(mesh, transform)| {
+                let m = &model.meshes[*mesh];
+                let vertices = m.decoded_vertices();
+                ObjMesh {
+                    name: &m.name,
+                    positions: vertices.iter().map(|v| transform.transform_point3(v.position.truncate()).to_array()).collect(),
+                    normals: vertices
+                        .iter()
+                        .map(|v| transform.transform_vector3(v.normal.truncate()).normalize_or_zero().to_array())
+                        .collect(),
+                    uvs: vertices.iter().map(|v| v.uv.to_array()).collect(),
+                    indices: &m.indices,
+                    texture_name: m.texture_name.as_deref(),
+                }
+            });
+            write_obj(meshes).into_bytes()
+        }
+        AssetKind::AnimatedModel => {
+            // Written in bind pose; skinning data has no OBJ equivalent.
+            let model = AnimatedModel::decode(key, &bytes)?;
+            let meshes = model.meshes.iter().map(|m| {
+                let vertices = m.decoded_vertices();
+                ObjMesh {
+                    name: &m.name,
+                    positions: vertices.iter().map(|v| v.position.truncate().to_array()).collect(),
+                    normals: vertices.iter().map(|v| v.normal.truncate().to_array()).collect(),
+                    uvs: vertices.iter().map(|v| v.uv.to_array()).collect(),
+                    indices: &m.indices,
+                    texture_name: m.texture_name.as_deref(),
+                }
+            });
+            write_obj(meshes).into_bytes()
+        }
+        AssetKind::Animation | AssetKind::Material | AssetKind::Composite | AssetKind::Prefab | AssetKind::Scene => {
+            return Err(format!("{} cannot be extracted; use `inspect` to view them", kind.label().to_lowercase()).into());
+        }
+    };
+    fs::write(out, &contents)?;
+    println!("Wrote {} '{key}' to {} ({})", kind.label().to_lowercase(), out.display(), format_size(contents.len()));
+    Ok(())
+}
+
+pub fn delete(store: &AssetStore, key: &str, kind: Option<AssetKind>) -> Result<(), Box<dyn std::error::Error>> {
+    let (kind, _) = locate_one(&store.read()?, key, kind)?;
+    store.remove(kind, key)?;
+    println!("Deleted {} '{key}'", kind.label().to_lowercase());
+    Ok(())
+}
+
+/// The optional attributes a mesh carries, as a suffix to its vertex count.
+fn describe_attributes(attributes: &VertexAttributes) -> String {
+    let present: Vec<&str> = [(attributes.uv1, "uv1"), (attributes.color, "color")]
+        .into_iter()
+        .filter_map(|(present, name)| present.then_some(name))
+        .collect();
+    if present.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", present.join(", "))
+    }
+}
+
+fn describe_meshlets(meshlets: Option<&Meshlets>) -> String {
+    match meshlets {
+        Some(meshlets) => {
+            let triangles: u32 = meshlets.meshlets.iter().map(|m| m.triangle_count).sum();
+            let average = if meshlets.meshlets.is_empty() {
+                0.0
+            } else {
+                triangles as f32 / meshlets.meshlets.len() as f32
+            };
+            format!("{} meshlets ({average:.1} tris avg)", meshlets.meshlets.len())
+        }
+        None => "no meshlets".to_string(),
+    }
+}
+
+fn format_size(bytes: usize) -> String {
+    const KIB: f64 = 1024.0;
+    let bytes_f = bytes as f64;
+    if bytes_f >= KIB * KIB {
+        format!("{:.1} MiB", bytes_f / (KIB * KIB))
+    } else if bytes_f >= KIB {
+        format!("{:.1} KiB", bytes_f / KIB)
+    } else {
+        format!("{bytes} B")
+    }
+}
+
+struct ObjMesh<'a> {
+    name: &'a str,
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    indices: &'a [u32],
+    texture_name: Option<&'a str>,
+}
+
+/// Serializes meshes as a single Wavefront OBJ, one object per mesh. The texture name is kept
+/// as the material name so it can be matched against extracted textures.
+fn write_obj<'a>(meshes: impl Iterator<Item = ObjMesh<'a>>) -> String {
+    let mut obj = String::new();
+    // OBJ indices are global and 1-based.
+    let mut base = 1;
+    for mesh in meshes {
+        let _ = writeln!(obj, "o {}", mesh.name);
+        if let Some(texture_name) = mesh.texture_name {
+            let _ = writeln!(obj, "usemtl {texture_name}");
+        }
+        for p in &mesh.positions {
+            let _ = writeln!(obj, "v {} {} {}", p[0], p[1], p[2]);
+        }
+        for n in &mesh.normals {
+            let _ = writeln!(obj, "vn {} {} {}", n[0], n[1], n[2]);
+        }
+        for uv in &mesh.uvs {
+            // OBJ texture space has V pointing up, glTF has it pointing down.
+            let _ = writeln!(obj, "vt {} {}", uv[0], 1.0 - uv[1]);
+        }
+        for tri in mesh.indices.chunks_exact(3) {
+            let (a, b, c) = (tri[0] + base, tri[1] + base, tri[2] + base);
+            let _ = writeln!(obj, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}");
+        }
+        base += mesh.positions.len() as u32;
+    }
+    obj
+}
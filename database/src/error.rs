@@ -0,0 +1,88 @@
+use std::fmt;
+
+/// Errors returned by the typed asset database API.
+#[derive(Debug)]
+pub enum DatabaseError {
+    /// The underlying redb file could not be opened, read or written.
+    Storage(Box<redb::Error>),
+    /// A stored value could not be decoded as the type its table holds.
+    Decode {
+        table: String,
+        key: String,
+        source: bincode::Error,
+    },
+    /// A value could not be encoded before being stored.
+    Encode {
+        table: String,
+        key: String,
+        source: bincode::Error,
+    },
+    /// A lookup that must succeed did not find the key.
+    NotFound { table: String, key: String },
+    /// The database was written with a different `types::FORMAT_VERSION`.
+    VersionMismatch { found: u32, expected: u32 },
+    /// No registered migration upgrades a database of this version; it has to be rebaked.
+    NoMigration { from: u32 },
+    /// A stored texture is not a KTX2 container this crate can read.
+    Texture { key: String, reason: String },
+}
+
+impl fmt::Display for DatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatabaseError::Storage(e) => write!(f, "asset database error: {e}"),
+            DatabaseError::Decode { table, key, source } => {
+                write!(f, "could not decode '{key}' from table '{table}': {source}")
+            }
+            DatabaseError::Encode { table, key, source } => {
+                write!(f, "could not encode '{key}' for table '{table}': {source}")
+            }
+            DatabaseError::NotFound { table, key } => {
+                write!(f, "'{key}' was not found in table '{table}'")
+            }
+            DatabaseError::VersionMismatch { found, expected } => {
+                write!(f, "database is version {found}, expected {expected}")
+            }
+            DatabaseError::NoMigration { from } => {
+                write!(f, "no migration from database version {from}; rebake it from source")
+            }
+            DatabaseError::Texture { key, reason } => {
+                write!(f, "texture '{key}' could not be read: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DatabaseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DatabaseError::Storage(e) => Some(e.as_ref()),
+            DatabaseError::Decode { source, .. } | DatabaseError::Encode { source, .. } => Some(source),
+            DatabaseError::NotFound { .. }
+            | DatabaseError::VersionMismatch { .. }
+            | DatabaseError::NoMigration { .. }
+            | DatabaseError::Texture { .. } => None,
+        }
+    }
+}
+
+macro_rules! impl_from_redb {
+    ($($error:ty),* $(,)?) => {
+        $(
+            impl From<$error> for DatabaseError {
+                fn from(e: $error) -> Self {
+                    DatabaseError::Storage(Box::new(e.into()))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_redb!(
+    redb::Error,
+    redb::DatabaseError,
+    redb::TransactionError,
+    redb::TableError,
+    redb::StorageError,
+    redb::CommitError,
+);
@@ -1,268 +1,471 @@
-use glam::{Mat4, Quat, Vec2, Vec3};
-use image::ImageEncoder;
-use std::collections::HashMap;
-use std::path::Path;
+use glam::{Mat4, Quat, Vec2, Vec3, Vec4};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use types::{
-    AnimatedMesh, AnimatedModel, Animation, AnimationChannel, Bone, Mesh, Meshlet, Meshlets,
-    Model, PositionKey, RotationKey, ScaleKey, Skeleton, SkinnedVertex, Vertex, AABB,
+    material_key, skin_key, AlphaMode, AnimatedMesh, AnimatedModel, Animation, AnimationChannel, Bone,
+    Interpolation, LightKind, Material, Mesh, Meshlet, MeshletBounds, Meshlets, Model, MorphChannel, MorphKey,
+    MorphTarget, PositionKey, Prefab, PrefabNode, Projection, RotationKey, ScaleKey, Scene, SceneCamera, SceneLight,
+    Skeleton, SkinnedVertex, Vertex, VertexAttributes, AABB,
 };
 
+/// What an importer produces for one source file: the unskinned meshes as a static model, one
+/// animated model per skin with the animations of each, and the textures and materials they
+/// reference, by key. Unskinned meshes with morph targets are animated models as well, since
+/// only those play weights channels.
+#[derive(Default)]
+pub struct ImportedModel {
+    pub model: Option<Model>,
+    /// The node hierarchy placing `model`'s meshes, when the source has one.
+    pub prefab: Option<Prefab>,
+    /// Authored cameras and lights, when the source has any.
+    pub scene: Option<Scene>,
+    /// Each named by its `ANIMATED_MODEL_TABLE` key.
+    pub animated_models: Vec<AnimatedModel>,
+    pub animations: Vec<Animation>,
+    pub textures: Vec<(String, image::RgbaImage)>,
+    pub materials: Vec<(String, Material)>,
+    /// Files besides the source that the import read and only the importer can name.
+    pub dependencies: Vec<PathBuf>,
+}
+
 pub fn load_gltf_model<P: AsRef<Path>>(
     path: P,
     model_name: &str,
     skip_validation: bool,
-) -> Result<(Option<Model>, Option<AnimatedModel>, Vec<Animation>, Vec<(String, Vec<u8>)>), Box<dyn std::error::Error>> {
+) -> Result<ImportedModel, Box<dyn std::error::Error>> {
     log::info!("[GLTF] Loading model: {} from {:?}", model_name, path.as_ref());
 
     let (document, buffers, images) = gltf::import(path)?;
 
-    // Debug information
-    log::info!("[GLTF] Document info:");
-    log::info!("  - Scenes: {}", document.scenes().count());
-    log::info!("  - Nodes: {}", document.nodes().count());
-    log::info!("  - Meshes: {}", document.meshes().count());
-    log::info!("  - Animations: {}", document.animations().count());
-    log::info!("  - Skins: {}", document.skins().count());
-    log::info!("  - Images: {}", images.len());
-
-    // Check if this is an animated model
-    let has_animations = !document.animations().collect::<Vec<_>>().is_empty();
-    let has_skins = !document.skins().collect::<Vec<_>>().is_empty();
-
     let mut textures_to_add = Vec::new();
+    let referenced = referenced_images(&document);
 
-    // Process textures from the imported images
+    // Process textures from the imported images. Every image is stored under its source index,
+    // which is what materials and primitives refer to.
     for (idx, image) in images.iter().enumerate() {
-        let texture_name = format!("{model_name}_texture_{idx}.png");
+        let texture_name = image_texture_name(model_name, idx);
         log::info!("[GLTF] Processing texture {}: {}x{}, format: {:?}",
                  idx, image.width, image.height, image.format);
 
-        // Convert to PNG using the image crate
-        let png_data = match image.format {
-            gltf::image::Format::R8G8B8A8 => {
-                // Already in RGBA format, encode as PNG
-                let mut png_data = Vec::new();
-                {
-                    let encoder = image::codecs::png::PngEncoder::new(&mut png_data);
-                    let img = image::RgbaImage::from_raw(
-                        image.width,
-                        image.height,
-                        image.pixels.clone(),
-                    ).ok_or("Failed to create image from pixels")?;
-                    encoder.write_image(
-                        &img,
-                        image.width,
-                        image.height,
-                        image::ColorType::Rgba8.into(),
-                    )?;
-                }
-                png_data
-            },
-            gltf::image::Format::R8G8B8 => {
-                // Convert RGB to RGBA
-                let mut rgba_pixels = Vec::with_capacity((image.width * image.height * 4) as usize);
-                for chunk in image.pixels.chunks(3) {
-                    rgba_pixels.extend_from_slice(chunk);
-                    rgba_pixels.push(255); // Alpha = 1.0
-                }
-
-                let mut png_data = Vec::new();
-                {
-                    let encoder = image::codecs::png::PngEncoder::new(&mut png_data);
-                    let img = image::RgbaImage::from_raw(
-                        image.width,
-                        image.height,
-                        rgba_pixels,
-                    ).ok_or("Failed to create image from RGBA pixels")?;
-                    encoder.write_image(
-                        &img,
-                        image.width,
-                        image.height,
-                        image::ColorType::Rgba8.into(),
-                    )?;
-                }
-                png_data
-            },
-            _ => {
-                log::warn!("[GLTF] Skipping unsupported image format: {:?}", image.format);
+        let rgba = match gltf_image_to_rgba8(image) {
+            Ok(rgba) => rgba,
+            Err(e) if referenced.contains(&idx) => {
+                return Err(format!("texture {texture_name} cannot be converted: {e}").into());
+            }
+            Err(e) => {
+                log::warn!("[GLTF] Skipping unreferenced texture {texture_name}: {e}");
                 continue;
             }
         };
-
-        textures_to_add.push((texture_name, png_data));
+        textures_to_add.push((texture_name, rgba));
     }
 
-    if has_animations || has_skins {
-        log::info!("[GLTF] Processing as animated model");
-        // Process as animated model
-        let (animated_model, animations) = process_animated_gltf(
+    let materials = document
+        .materials()
+        .filter_map(|material| {
+            let key = gltf_material_key(model_name, &material)?;
+            Some((key, convert_material(&material, model_name)))
+        })
+        .collect();
+
+    // Unskinned nodes become one static model and the prefab placing its meshes, cameras and
+    // lights become a scene, and every skin becomes an animated model of its own. Unskinned
+    // meshes with morph targets are posed by an animated model of their nodes instead.
+    let roots = root_nodes(&document);
+    let (model, prefab) = process_static_gltf(&document, &roots, &buffers, model_name)?.unzip();
+    let scene = process_scene(&roots, model_name);
+    let keys = skin_keys(&document, model_name);
+    let mut animated_models = Vec::new();
+    let mut animations = Vec::new();
+    for (skin, key) in document.skins().zip(&keys) {
+        log::info!("[GLTF] Processing skin {:?} as animated model '{key}'", skin.name());
+        let (animated_model, skin_animations) = process_animated_gltf(
             &document,
+            &skin,
             &buffers,
-            &images,
-            model_name,
-            &mut textures_to_add,
+            key,
             skip_validation,
         )?;
-        Ok((None, Some(animated_model), animations, textures_to_add))
-    } else {
-        log::info!("[GLTF] Processing as static model");
-        // Process as static model
-        let model = process_static_gltf(
-            &document,
-            &buffers,
-            &images,
-            model_name,
-            &mut textures_to_add,
-        )?;
-        Ok((Some(model), None, Vec::new(), textures_to_add))
+        animated_models.push(animated_model);
+        animations.extend(skin_animations);
+    }
+    let morph_key = skin_key(model_name, MORPH_MODEL);
+    if let Some((morph_model, morph_animations)) =
+        process_morphed_gltf(&document, &roots, &buffers, &morph_key, skip_validation)?
+    {
+        log::info!("[GLTF] Baked unskinned morph targets as animated model '{morph_key}'");
+        animated_models.push(morph_model);
+        animations.extend(morph_animations);
+    }
+    if model.is_none() && animated_models.is_empty() {
+        log::warn!("[GLTF] No meshes found in {model_name}");
+    }
+
+    Ok(ImportedModel {
+        model,
+        prefab,
+        scene,
+        animated_models,
+        animations,
+        textures: textures_to_add,
+        materials,
+        // The baker reads the buffers and images a glTF file names itself.
+        dependencies: Vec::new(),
+    })
+}
+
+/// Name of the animated model posing a source's unskinned meshes with morph targets, keyed
+/// like a skin of that name.
+const MORPH_MODEL: &str = "morphs";
+
+/// The animated model key of each skin: the model name when there is only one, otherwise the
+/// skin's name, or its index when that is missing or shared.
+fn skin_keys(document: &gltf::Document, model_name: &str) -> Vec<String> {
+    if document.skins().len() == 1 {
+        return vec![model_name.to_string()];
+    }
+    let mut name_counts: HashMap<&str, usize> = HashMap::new();
+    for name in document.skins().filter_map(|skin| skin.name()) {
+        *name_counts.entry(name).or_default() += 1;
+    }
+    document
+        .skins()
+        .map(|skin| match skin.name() {
+            Some(name) if name_counts[name] == 1 => skin_key(model_name, name),
+            _ => skin_key(model_name, &format!("skin_{}", skin.index())),
+        })
+        .collect()
+}
+
+fn image_texture_name(model_name: &str, image_index: usize) -> String {
+    format!("{model_name}_texture_{image_index}.png")
+}
+
+/// Texture key a glTF texture reference resolves to; textures are stored per source image.
+fn gltf_texture_key(model_name: &str, texture: &gltf::Texture) -> String {
+    image_texture_name(model_name, texture.source().index())
+}
+
+/// Indices of the images that some material samples from.
+fn referenced_images(document: &gltf::Document) -> HashSet<usize> {
+    let mut referenced = HashSet::new();
+    for material in document.materials() {
+        let pbr = material.pbr_metallic_roughness();
+        let textures = [
+            pbr.base_color_texture().map(|t| t.texture()),
+            pbr.metallic_roughness_texture().map(|t| t.texture()),
+            material.normal_texture().map(|t| t.texture()),
+            material.occlusion_texture().map(|t| t.texture()),
+            material.emissive_texture().map(|t| t.texture()),
+        ];
+        referenced.extend(textures.into_iter().flatten().map(|t| t.source().index()));
     }
+    referenced
+}
+
+/// Converts decoded glTF pixels of any format to 8-bit RGBA. Single-channel images are treated
+/// as luminance and two-channel ones as luminance plus alpha, which is how they were decoded;
+/// 16-bit channels are stored native-endian and float channels are clamped to [0, 1].
+fn gltf_image_to_rgba8(image: &gltf::image::Data) -> Result<image::RgbaImage, String> {
+    use gltf::image::Format;
+    use image::{DynamicImage, ImageBuffer};
+
+    let (width, height) = (image.width, image.height);
+    let u16s = || -> Vec<u16> {
+        image.pixels.chunks_exact(2).map(|b| u16::from_ne_bytes([b[0], b[1]])).collect()
+    };
+    let f32s = || -> Vec<f32> {
+        image.pixels.chunks_exact(4).map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]])).collect()
+    };
+    let pixels = image.pixels.clone();
+
+    let dynamic = match image.format {
+        Format::R8 => ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageLuma8),
+        Format::R8G8 => ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageLumaA8),
+        Format::R8G8B8 => ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageRgb8),
+        Format::R8G8B8A8 => ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageRgba8),
+        Format::R16 => ImageBuffer::from_raw(width, height, u16s()).map(DynamicImage::ImageLuma16),
+        Format::R16G16 => ImageBuffer::from_raw(width, height, u16s()).map(DynamicImage::ImageLumaA16),
+        Format::R16G16B16 => ImageBuffer::from_raw(width, height, u16s()).map(DynamicImage::ImageRgb16),
+        Format::R16G16B16A16 => ImageBuffer::from_raw(width, height, u16s()).map(DynamicImage::ImageRgba16),
+        Format::R32G32B32FLOAT => ImageBuffer::from_raw(width, height, f32s()).map(DynamicImage::ImageRgb32F),
+        Format::R32G32B32A32FLOAT => ImageBuffer::from_raw(width, height, f32s()).map(DynamicImage::ImageRgba32F),
+    };
+
+    dynamic
+        .map(|dynamic| dynamic.to_rgba8())
+        .ok_or_else(|| format!("{} bytes do not hold a {width}x{height} {:?} image", image.pixels.len(), image.format))
 }
 
+/// `None` for the implicit default material, which is not stored.
+fn gltf_material_key(model_name: &str, material: &gltf::Material) -> Option<String> {
+    material
+        .index()
+        .map(|index| material_key(model_name, &format!("material_{index}")))
+}
+
+fn convert_material(material: &gltf::Material, model_name: &str) -> Material {
+    let pbr = material.pbr_metallic_roughness();
+    let normal = material.normal_texture();
+    let occlusion = material.occlusion_texture();
+
+    Material {
+        name: material
+            .name()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("material_{}", material.index().unwrap_or_default())),
+        base_color_factor: Vec4::from(pbr.base_color_factor()),
+        base_color_texture: pbr.base_color_texture().map(|t| gltf_texture_key(model_name, &t.texture())),
+        base_color_tex_coord: pbr.base_color_texture().map_or(0, |t| t.tex_coord()),
+        metallic_factor: pbr.metallic_factor(),
+        roughness_factor: pbr.roughness_factor(),
+        metallic_roughness_texture: pbr
+            .metallic_roughness_texture()
+            .map(|t| gltf_texture_key(model_name, &t.texture())),
+        metallic_roughness_tex_coord: pbr.metallic_roughness_texture().map_or(0, |t| t.tex_coord()),
+        normal_texture: normal.as_ref().map(|t| gltf_texture_key(model_name, &t.texture())),
+        normal_tex_coord: normal.as_ref().map_or(0, |t| t.tex_coord()),
+        normal_scale: normal.as_ref().map_or(1.0, |t| t.scale()),
+        occlusion_texture: occlusion.as_ref().map(|t| gltf_texture_key(model_name, &t.texture())),
+        occlusion_tex_coord: occlusion.as_ref().map_or(0, |t| t.tex_coord()),
+        occlusion_strength: occlusion.as_ref().map_or(1.0, |t| t.strength()),
+        emissive_factor: Vec3::from(material.emissive_factor()),
+        emissive_texture: material.emissive_texture().map(|t| gltf_texture_key(model_name, &t.texture())),
+        emissive_tex_coord: material.emissive_texture().map_or(0, |t| t.tex_coord()),
+        alpha_mode: match material.alpha_mode() {
+            gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+            gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+        },
+        alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5),
+        double_sided: material.double_sided(),
+    }
+}
+
+/// Bakes every mesh the unskinned nodes reference once, in its own space, and keeps the node
+/// hierarchy as a prefab placing them.
 fn process_static_gltf(
     document: &gltf::Document,
+    roots: &[gltf::Node],
     buffers: &[gltf::buffer::Data],
-    images: &[gltf::image::Data],
     model_name: &str,
-    textures_to_add: &mut Vec<(String, Vec<u8>)>,
-) -> Result<Model, Box<dyn std::error::Error>> {
+) -> Result<Option<(Model, Prefab)>, Box<dyn std::error::Error>> {
     let mut meshes = Vec::new();
-    let mut mesh_counter = 0;
+    let mut nodes = Vec::new();
+    // glTF mesh index -> indices of its primitives in `meshes`
+    let mut baked_meshes: HashMap<usize, Vec<usize>> = HashMap::new();
 
-    // If there's a default scene, use it. Otherwise use the first scene, or process all nodes
-    if let Some(scene) = document.default_scene().or_else(|| document.scenes().next()) {
-        log::info!("[GLTF] Processing scene: {:?}", scene.name());
-        for node in scene.nodes() {
-            process_static_node(
-                &node,
-                &Mat4::IDENTITY, // Start with identity matrix
-                &mut meshes,
-                &mut mesh_counter,
-                model_name,
-                buffers,
-                images,
-                textures_to_add,
-            )?;
-        }
-    } else {
-        // No scenes, process all root nodes
-        log::info!("[GLTF] No scenes found, processing all root nodes");
-        for node in document.nodes() {
-            // Only process nodes that don't have parents (root nodes)
-            let has_parent = document.nodes().any(|n| n.children().any(|c| c.index() == node.index()));
-            if !has_parent {
-                process_static_node(
-                    &node,
-                    &Mat4::IDENTITY, // Start with identity matrix
-                    &mut meshes,
-                    &mut mesh_counter,
-                    model_name,
-                    buffers,
-                    images,
-                    textures_to_add,
-                )?;
-            }
-        }
+    for node in roots {
+        process_static_node(node, None, &mut nodes, &mut meshes, &mut baked_meshes, model_name, buffers);
     }
 
-    // If no meshes were found through node traversal, try processing meshes directly
-    if meshes.is_empty() {
+    // If no meshes were found through node traversal, try processing meshes directly and place
+    // them all at a single root. Files with skins keep their meshes on skinned nodes, which
+    // are not static, and meshes with morph targets belong to the morph model.
+    if meshes.is_empty() && document.skins().len() == 0 {
         log::info!("[GLTF] No meshes found through node traversal, processing meshes directly");
-        for mesh in document.meshes() {
-            for primitive in mesh.primitives() {
-                let unique_mesh_name = format!("{model_name}-mesh-{mesh_counter}");
-                mesh_counter += 1;
-
-                if let Ok(processed_mesh) = process_primitive(
-                    &primitive,
-                    &unique_mesh_name,
-                    &Mat4::IDENTITY, // No transform if processed directly
-                    buffers,
-                    model_name,
-                ) {
-                    meshes.push(processed_mesh);
-                }
-            }
+        for mesh in document.meshes().filter(|mesh| !has_morph_targets(mesh)) {
+            bake_gltf_mesh(&mesh, &mut meshes, model_name, buffers);
         }
+        nodes = vec![PrefabNode {
+            name: model_name.to_string(),
+            parent_index: None,
+            translation: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+            scale: Vec3::ONE,
+            meshes: (0..meshes.len()).collect(),
+        }];
     }
 
-    log::info!("[GLTF] Processed {} meshes", meshes.len());
-
-    // Calculate model AABB
-    let mut model_aabb = AABB::default();
-    if let Some(first_mesh) = meshes.first() {
-        model_aabb = first_mesh.aabb;
-        for mesh in meshes.iter().skip(1) {
-            model_aabb.min = model_aabb.min.min(mesh.aabb.min);
-            model_aabb.max = model_aabb.max.max(mesh.aabb.max);
-        }
+    log::info!("[GLTF] Processed {} static meshes placed by {} nodes", meshes.len(), nodes.len());
+    if meshes.is_empty() {
+        return Ok(None);
     }
 
-    Ok(Model {
+    let prefab = Prefab {
+        name: model_name.to_string(),
+        model: model_name.to_string(),
+        nodes,
+    };
+
+    // The model AABB encloses every placed mesh, in prefab space.
+    let model_aabb = prefab
+        .nodes
+        .iter()
+        .zip(prefab.world_transforms())
+        .flat_map(|(node, transform)| node.meshes.iter().map(move |&mesh| (mesh, transform)))
+        .map(|(mesh, transform)| meshes[mesh].aabb.transformed(&transform))
+        .reduce(|a, b| AABB { min: a.min.min(b.min), max: a.max.max(b.max) })
+        .unwrap_or_default();
+
+    let model = Model {
         name: model_name.to_string(),
         meshes,
         aabb: model_aabb,
-    })
+    };
+    Ok(Some((model, prefab)))
+}
+
+/// The nodes a file's content hangs from: those of its default scene, or of its first one, or
+/// every node without a parent when it has no scenes.
+fn root_nodes(document: &gltf::Document) -> Vec<gltf::Node<'_>> {
+    if let Some(scene) = document.default_scene().or_else(|| document.scenes().next()) {
+        log::info!("[GLTF] Using scene: {:?}", scene.name());
+        return scene.nodes().collect();
+    }
+    log::info!("[GLTF] No scenes found, using all root nodes");
+    parentless_nodes(document)
+}
+
+/// Every node that is not the child of another, whichever scene it belongs to.
+fn parentless_nodes(document: &gltf::Document) -> Vec<gltf::Node<'_>> {
+    let children: HashSet<usize> = document
+        .nodes()
+        .flat_map(|node| node.children().map(|child| child.index()))
+        .collect();
+    document.nodes().filter(|node| !children.contains(&node.index())).collect()
+}
+
+/// Collects the cameras and `KHR_lights_punctual` lights placed by the nodes under `roots`,
+/// or `None` when there are none.
+fn process_scene(roots: &[gltf::Node], model_name: &str) -> Option<Scene> {
+    let mut scene = Scene {
+        name: model_name.to_string(),
+        cameras: Vec::new(),
+        lights: Vec::new(),
+    };
+    let mut stack: Vec<(gltf::Node, Mat4)> = roots.iter().map(|node| (node.clone(), Mat4::IDENTITY)).collect();
+    while let Some((node, parent_transform)) = stack.pop() {
+        let transform = parent_transform * Mat4::from_cols_array_2d(&node.transform().matrix());
+        // Scale does not apply to cameras or lights.
+        let (_, rotation, translation) = transform.to_scale_rotation_translation();
+
+        if let Some(camera) = node.camera() {
+            let projection = match camera.projection() {
+                gltf::camera::Projection::Perspective(p) => Projection::Perspective {
+                    yfov: p.yfov(),
+                    aspect_ratio: p.aspect_ratio(),
+                    znear: p.znear(),
+                    zfar: p.zfar(),
+                },
+                gltf::camera::Projection::Orthographic(o) => Projection::Orthographic {
+                    xmag: o.xmag(),
+                    ymag: o.ymag(),
+                    znear: o.znear(),
+                    zfar: o.zfar(),
+                },
+            };
+            scene.cameras.push(SceneCamera {
+                name: camera.name().map_or_else(|| format!("Camera_{}", camera.index()), str::to_string),
+                node_name: node_key(&node),
+                translation,
+                rotation,
+                projection,
+            });
+        }
+
+        if let Some(light) = node.light() {
+            let kind = match light.kind() {
+                gltf::khr_lights_punctual::Kind::Directional => LightKind::Directional,
+                gltf::khr_lights_punctual::Kind::Point => LightKind::Point,
+                gltf::khr_lights_punctual::Kind::Spot { inner_cone_angle, outer_cone_angle } => {
+                    LightKind::Spot { inner_cone_angle, outer_cone_angle }
+                }
+            };
+            scene.lights.push(SceneLight {
+                name: light.name().map_or_else(|| format!("Light_{}", light.index()), str::to_string),
+                node_name: node_key(&node),
+                translation,
+                rotation,
+                color: Vec3::from(light.color()),
+                intensity: light.intensity(),
+                range: light.range(),
+                kind,
+            });
+        }
+
+        stack.extend(node.children().map(|child| (child, transform)));
+    }
+
+    log::info!("[GLTF] Found {} cameras and {} lights", scene.cameras.len(), scene.lights.len());
+    if scene.cameras.is_empty() && scene.lights.is_empty() {
+        return None;
+    }
+    Some(scene)
 }
 
+/// Adds `node` and its descendants to `nodes`, parents first. Skinned meshes are posed by
+/// their skeleton instead and meshes with morph targets by the morph model, so their nodes
+/// place nothing.
 fn process_static_node(
     node: &gltf::Node,
-    parent_transform: &Mat4,
+    parent_index: Option<usize>,
+    nodes: &mut Vec<PrefabNode>,
     meshes: &mut Vec<Mesh>,
-    mesh_counter: &mut usize,
+    baked_meshes: &mut HashMap<usize, Vec<usize>>,
     model_name: &str,
     buffers: &[gltf::buffer::Data],
-    images: &[gltf::image::Data],
-    textures_to_add: &mut Vec<(String, Vec<u8>)>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let node_transform = Mat4::from_cols_array_2d(&node.transform().matrix());
-    let accumulated_transform = *parent_transform * node_transform;
-
-    if let Some(mesh) = node.mesh() {
-        log::info!("[GLTF] Processing mesh at node: {:?}", node.name());
-        for primitive in mesh.primitives() {
-            let unique_mesh_name = format!("{}-mesh-{}", model_name, *mesh_counter);
-            *mesh_counter += 1;
+) {
+    let placed = match node.mesh().filter(|mesh| node.skin().is_none() && !has_morph_targets(mesh)) {
+        Some(mesh) => baked_meshes
+            .entry(mesh.index())
+            .or_insert_with(|| {
+                log::info!("[GLTF] Processing mesh {:?} first placed at node: {:?}", mesh.name(), node.name());
+                bake_gltf_mesh(&mesh, meshes, model_name, buffers)
+            })
+            .clone(),
+        None => Vec::new(),
+    };
 
-            if let Ok(processed_mesh) = process_primitive(
-                &primitive,
-                &unique_mesh_name,
-                &accumulated_transform,
-                buffers,
-                model_name,
-            ) {
-                meshes.push(processed_mesh);
-            }
-        }
-    }
+    let (translation, rotation, scale) = node.transform().decomposed();
+    nodes.push(PrefabNode {
+        name: node_key(node),
+        parent_index,
+        translation: Vec3::from(translation),
+        rotation: Quat::from_array(rotation),
+        scale: Vec3::from(scale),
+        meshes: placed,
+    });
+    let index = nodes.len() - 1;
 
     // Process children
     for child in node.children() {
-        process_static_node(
-            &child,
-            &accumulated_transform,
-            meshes,
-            mesh_counter,
-            model_name,
-            buffers,
-            images,
-            textures_to_add,
-        )?;
+        process_static_node(&child, Some(index), nodes, meshes, baked_meshes, model_name, buffers);
     }
+}
 
-    Ok(())
+/// Appends the primitives of `mesh` that import cleanly to `meshes` and returns their indices.
+fn bake_gltf_mesh(
+    mesh: &gltf::Mesh,
+    meshes: &mut Vec<Mesh>,
+    model_name: &str,
+    buffers: &[gltf::buffer::Data],
+) -> Vec<usize> {
+    let mut indices = Vec::new();
+    for primitive in mesh.primitives() {
+        let unique_mesh_name = format!("{model_name}-mesh-{}", meshes.len());
+        match process_primitive(&primitive, &unique_mesh_name, buffers, model_name) {
+            Ok(processed_mesh) => {
+                indices.push(meshes.len());
+                meshes.push(processed_mesh);
+            }
+            Err(e) => log::warn!("[GLTF] Skipping primitive {} of mesh {:?}: {e}", primitive.index(), mesh.name()),
+        }
+    }
+    indices
 }
 
 fn process_primitive(
     primitive: &gltf::Primitive,
     mesh_name: &str,
-    transform: &Mat4,
     buffers: &[gltf::buffer::Data],
     model_name: &str,
 ) -> Result<Mesh, Box<dyn std::error::Error>> {
     // Get texture name if available
     let texture_name = primitive.material().pbr_metallic_roughness()
         .base_color_texture()
-        .map(|tex| format!("{}_texture_{}.png", model_name, tex.texture().source().index()));
+        .map(|tex| gltf_texture_key(model_name, &tex.texture()));
+    let material = gltf_material_key(model_name, &primitive.material());
 
     // Extract vertices
     let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
@@ -284,6 +487,10 @@ fn process_primitive(
         log::warn!("[GLTF]    - No texture coordinates found, using defaults");
         vec![[0.0, 0.0]; positions.len()]
     };
+    // Missing tangents are generated at bake time.
+    let tangents: Option<Vec<[f32; 4]>> = reader.read_tangents().map(|t| t.collect());
+    let uvs1: Option<Vec<[f32; 2]>> = reader.read_tex_coords(1).map(|t| t.into_f32().collect());
+    let colors: Option<Vec<[f32; 4]>> = reader.read_colors(0).map(|c| c.into_rgba_f32().collect());
 
     // Extract indices
     let indices: Vec<u32> = if let Some(indices_reader) = reader.read_indices() {
@@ -296,8 +503,9 @@ fn process_primitive(
     log::info!("[GLTF]    - Primitive has {} indices ({} triangles)",
              indices.len(), indices.len() / 3);
 
-    // Build deduplicated vertex buffer using indices
-    let mut vertex_map: HashMap<(u32, u32, u32), u32> = HashMap::new();
+    // Keep only the vertices the indices reference. Vertices with equal attributes are welded
+    // later, by the bake's optimization stage.
+    let mut vertex_map: HashMap<u32, u32> = HashMap::new();
     let mut dedup_vertices: Vec<Vertex> = Vec::new();
     let mut remapped_indices: Vec<u32> = Vec::with_capacity(indices.len());
 
@@ -305,18 +513,18 @@ fn process_primitive(
         let pos_idx = idx as usize;
         let norm_idx = idx as usize;
         let uv_idx = idx as usize;
-        let key = (pos_idx as u32, norm_idx as u32, uv_idx as u32);
-        let entry = vertex_map.entry(key).or_insert_with(|| {
+        let entry = vertex_map.entry(idx).or_insert_with(|| {
             let position = Vec3::from(positions[pos_idx]);
             let normal = Vec3::from(normals[norm_idx]).normalize_or_zero();
             let uv = uvs.get(uv_idx).copied().unwrap_or([0.0, 0.0]);
-            let transformed_pos = transform.transform_point3(position);
-            let transformed_normal = transform.transform_vector3(normal).normalize_or_zero();
+            let tangent = source_tangent(tangents.as_deref(), idx);
             let v = Vertex {
-                position: transformed_pos.extend(1.0),
-                normal: transformed_normal.extend(0.0),
+                position: position.extend(1.0),
+                normal: normal.extend(0.0),
                 uv: Vec2::new(uv[0], uv[1]),
-                _padding: [0.0; 2],
+                uv1: source_uv1(uvs1.as_deref(), idx),
+                tangent,
+                color: source_color(colors.as_deref(), idx),
             };
             dedup_vertices.push(v);
             (dedup_vertices.len() - 1) as u32
@@ -345,25 +553,50 @@ fn process_primitive(
     Ok(Mesh {
         name: mesh_name.to_string(),
         vertices: dedup_vertices,
+        quantized_vertices: None,
         indices: remapped_indices,
         texture_name,
+        material,
         meshlets,
+        lods: Vec::new(),
         aabb,
+        attributes: VertexAttributes {
+            uv1: uvs1.is_some(),
+            color: colors.is_some(),
+        },
     })
 }
 
+fn source_uv1(uvs1: Option<&[[f32; 2]]>, idx: u32) -> Vec2 {
+    uvs1.and_then(|uvs| uvs.get(idx as usize)).map_or(Vec2::ZERO, |uv| Vec2::from(*uv))
+}
+
+/// `TANGENT` of a vertex with its direction normalized, or zero when the primitive has none
+/// and the bake should generate it.
+fn source_tangent(tangents: Option<&[[f32; 4]]>, idx: u32) -> Vec4 {
+    tangents.and_then(|t| t.get(idx as usize)).map_or(Vec4::ZERO, |t| {
+        let tangent = Vec4::from(*t);
+        tangent.truncate().normalize_or_zero().extend(tangent.w)
+    })
+}
+
+/// `COLOR_0` of a vertex, white when the primitive has none.
+fn source_color(colors: Option<&[[f32; 4]]>, idx: u32) -> Vec4 {
+    colors.and_then(|c| c.get(idx as usize)).map_or(Vec4::ONE, |c| Vec4::from(*c))
+}
+
+/// An animated model with the clips authored for it.
+type AnimatedImport = (AnimatedModel, Vec<Animation>);
+
+/// Bakes the meshes bound to `skin` and the animations of its joints as the animated model
+/// `model_name`.
 fn process_animated_gltf(
     document: &gltf::Document,
+    skin: &gltf::Skin,
     buffers: &[gltf::buffer::Data],
-    images: &[gltf::image::Data],
     model_name: &str,
-    textures_to_add: &mut Vec<(String, Vec<u8>)>,
     skip_validation: bool,
-) -> Result<(AnimatedModel, Vec<Animation>), Box<dyn std::error::Error>> {
-    // Build skeleton from the first skin (most GLTF files have one skin)
-    let skin = document.skins().next()
-        .ok_or("Animated model has no skin")?;
-
+) -> Result<AnimatedImport, Box<dyn std::error::Error>> {
     log::info!("[GLTF] Building skeleton from skin: {:?}", skin.name());
 
     let mut bones = Vec::new();
@@ -422,45 +655,36 @@ fn process_animated_gltf(
     if let Some(scene) = document.default_scene().or_else(|| document.scenes().next()) {
         log::info!("[GLTF] Processing animated meshes from scene: {:?}", scene.name());
         for node in scene.nodes() {
-            process_animated_node(
-                &node,
-                &mut animated_meshes,
-                &mut mesh_counter,
-                model_name,
-                buffers,
-                &skeleton,
-                &node_to_bone,
-                textures_to_add,
-            )?;
+            process_animated_node(&node, skin.index(), &mut animated_meshes, &mut mesh_counter, model_name, buffers)?;
         }
     }
 
-    // If no meshes found, try processing all skinned meshes directly
+    // The skinned meshes may hang from nodes outside the scene, so try every hierarchy.
     if animated_meshes.is_empty() {
-        log::info!("[GLTF] No meshes found through scene traversal, checking all nodes with meshes");
-        for node in document.nodes() {
-            if node.mesh().is_some() {
-                process_animated_node(
-                    &node,
-                    &mut animated_meshes,
-                    &mut mesh_counter,
-                    model_name,
-                    buffers,
-                    &skeleton,
-                    &node_to_bone,
-                    textures_to_add,
-                )?;
-            }
+        log::info!("[GLTF] No meshes found through scene traversal, checking every root node");
+        for node in parentless_nodes(document) {
+            process_animated_node(&node, skin.index(), &mut animated_meshes, &mut mesh_counter, model_name, buffers)?;
         }
     }
 
     log::info!("[GLTF] Processed {} animated meshes", animated_meshes.len());
 
-    // Process animations
+    // Process animations. With several skins, a clip usually drives only one of them and is
+    // kept for the skins whose joints it moves.
+    let shared_clips = document.skins().len() > 1;
+    let morph_nodes: HashSet<String> = animated_meshes
+        .iter()
+        .filter(|mesh| !mesh.morph_targets.is_empty())
+        .filter_map(|mesh| mesh.node_name.clone())
+        .collect();
     let mut animations = Vec::new();
     for (anim_idx, anim) in document.animations().enumerate() {
         log::info!("[GLTF] Processing animation {}: {:?}", anim_idx, anim.name());
-        let animation = process_gltf_animation(&anim, buffers, &skeleton, &node_to_bone)?;
+        let animation = process_gltf_animation(&anim, buffers, model_name, &skeleton, &node_to_bone, &morph_nodes)?;
+        if shared_clips && animation.channels.is_empty() && animation.morph_channels.is_empty() {
+            log::info!("[GLTF]    - Does not animate '{model_name}'");
+            continue;
+        }
         animations.push(animation);
     }
 
@@ -494,210 +718,388 @@ fn process_animated_gltf(
 
 fn process_animated_node(
     node: &gltf::Node,
+    skin_index: usize,
     meshes: &mut Vec<AnimatedMesh>,
     mesh_counter: &mut usize,
     model_name: &str,
     buffers: &[gltf::buffer::Data],
-    skeleton: &Skeleton,
-    node_to_bone: &HashMap<usize, usize>,
-    textures_to_add: &mut Vec<(String, Vec<u8>)>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // For skinned meshes, we do not apply the node's transform to the vertices.
     // The vertices are in model space and will be transformed by the skeleton on the GPU.
     // We still need to traverse children, however.
     
-    if let Some(mesh) = node.mesh() {
+    if let Some(mesh) = node.mesh().filter(|_| node.skin().is_some_and(|skin| skin.index() == skin_index)) {
         log::info!("[GLTF] Processing animated mesh at node: {:?}", node.name());
         for primitive in mesh.primitives() {
             let unique_mesh_name = format!("{}-mesh-{}", model_name, *mesh_counter);
             *mesh_counter += 1;
+            meshes.push(process_skinned_primitive(&primitive, node, &mesh, unique_mesh_name, model_name, buffers, None)?);
+        }
+    }
 
-            // Get texture name
-            let texture_name = primitive.material().pbr_metallic_roughness()
-                .base_color_texture()
-                .map(|tex| format!("{}_texture_{}.png", model_name, tex.texture().source().index()));
+    // Process children recursively
+    for child in node.children() {
+        process_animated_node(&child, skin_index, meshes, mesh_counter, model_name, buffers)?;
+    }
 
-            // Extract vertex data
-            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+    Ok(())
+}
 
-            let positions: Vec<[f32; 3]> = reader
-                .read_positions()
-                .ok_or("Mesh has no positions")?
-                .collect();
+/// Bakes the unskinned meshes with morph targets as the animated model `model_name`, with the
+/// animations driving their weights. Their nodes and the ancestors of those become bones under
+/// a single root, and each mesh is weighted fully to its node, so it is placed as the prefab
+/// would place it.
+fn process_morphed_gltf(
+    document: &gltf::Document,
+    roots: &[gltf::Node],
+    buffers: &[gltf::buffer::Data],
+    model_name: &str,
+    skip_validation: bool,
+) -> Result<Option<AnimatedImport>, Box<dyn std::error::Error>> {
+    let mut bones = vec![Bone {
+        name: model_name.to_string(),
+        parent_index: None,
+        transform: Mat4::IDENTITY,
+        inverse_bind_pose: Mat4::IDENTITY,
+    }];
+    let mut node_to_bone = HashMap::new();
+    let mut meshes = Vec::new();
+    for node in roots {
+        process_morphed_node(node, 0, &mut bones, &mut node_to_bone, &mut meshes, model_name, buffers)?;
+    }
 
-            log::info!("[GLTF]    - Primitive has {} vertices", positions.len());
+    // Like skinned meshes, they may hang from nodes outside the scene.
+    if meshes.is_empty() {
+        for node in parentless_nodes(document) {
+            process_morphed_node(&node, 0, &mut bones, &mut node_to_bone, &mut meshes, model_name, buffers)?;
+        }
+    }
+    if meshes.is_empty() {
+        return Ok(None);
+    }
+    log::info!("[GLTF] Processed {} unskinned meshes with morph targets", meshes.len());
+    let skeleton = Skeleton { bones };
 
-            let normals: Vec<[f32; 3]> = if let Some(normals_iter) = reader.read_normals() {
-                normals_iter.collect()
-            } else {
-                log::warn!("[GLTF]    - No normals found, generating defaults");
-                vec![[0.0, 0.0, 1.0]; positions.len()]
-            };
+    // Only clips that drive these nodes are kept.
+    let morph_nodes: HashSet<String> = meshes.iter().filter_map(|mesh| mesh.node_name.clone()).collect();
+    let mut animations = Vec::new();
+    for anim in document.animations() {
+        let mut animation = process_gltf_animation(&anim, buffers, model_name, &skeleton, &node_to_bone, &morph_nodes)?;
+        if animation.channels.is_empty() && animation.morph_channels.is_empty() {
+            continue;
+        }
+        if !skip_validation {
+            validate_and_fix_animation_data(&mut animation, &skeleton, model_name)?;
+        }
+        animations.push(animation);
+    }
 
-            let uvs: Vec<[f32; 2]> = if let Some(tex_coords) = reader.read_tex_coords(0) {
-                tex_coords.into_f32().collect()
-            } else {
-                log::warn!("[GLTF]    - No UVs found, using defaults");
-                vec![[0.0, 0.0]; positions.len()]
-            };
+    // Mesh bounds are in the space of their node; the model's are in the space of the root.
+    let mut globals: Vec<Mat4> = Vec::with_capacity(skeleton.bones.len());
+    for bone in &skeleton.bones {
+        let parent = bone.parent_index.map_or(Mat4::IDENTITY, |parent| globals[parent]);
+        globals.push(parent * bone.transform);
+    }
+    let aabb = meshes
+        .iter()
+        .map(|mesh| {
+            let bone = mesh.vertices.first().map_or(0, |vertex| vertex.bone_indices[0] as usize);
+            mesh.aabb.transformed(&globals[bone])
+        })
+        .reduce(|a, b| AABB { min: a.min.min(b.min), max: a.max.max(b.max) })
+        .unwrap_or_default();
 
-            // Read bone weights and indices
-            let joints: Vec<[u16; 4]> = if let Some(joints_iter) = reader.read_joints(0) {
-                joints_iter.into_u16().collect()
-            } else {
-                log::warn!("[GLTF]    - No joint data found, using defaults");
-                vec![[0, 0, 0, 0]; positions.len()]
-            };
+    Ok(Some((
+        AnimatedModel {
+            name: model_name.to_string(),
+            meshes,
+            skeleton,
+            aabb,
+        },
+        animations,
+    )))
+}
 
-            let weights: Vec<[f32; 4]> = if let Some(weights_iter) = reader.read_weights(0) {
-                weights_iter.into_f32().collect()
-            } else {
-                log::warn!("[GLTF]    - No weight data found, using defaults");
-                vec![[1.0, 0.0, 0.0, 0.0]; positions.len()]
-            };
+/// Adds the meshes with morph targets of `node` and its descendants to `meshes`, and a bone
+/// for every node on the way to them.
+fn process_morphed_node(
+    node: &gltf::Node,
+    parent_index: usize,
+    bones: &mut Vec<Bone>,
+    node_to_bone: &mut HashMap<usize, usize>,
+    meshes: &mut Vec<AnimatedMesh>,
+    model_name: &str,
+    buffers: &[gltf::buffer::Data],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let index = bones.len();
+    let first_mesh = meshes.len();
+    bones.push(Bone {
+        name: node_key(node),
+        parent_index: Some(parent_index),
+        transform: Mat4::from_cols_array_2d(&node.transform().matrix()),
+        inverse_bind_pose: Mat4::IDENTITY,
+    });
+    node_to_bone.insert(node.index(), index);
+
+    if let Some(mesh) = node.mesh().filter(|mesh| node.skin().is_none() && has_morph_targets(mesh)) {
+        log::info!("[GLTF] Processing morphed mesh at node: {:?}", node.name());
+        for primitive in mesh.primitives() {
+            let mesh_name = format!("{model_name}-mesh-{}", meshes.len());
+            meshes.push(process_skinned_primitive(&primitive, node, &mesh, mesh_name, model_name, buffers, Some(index as u16))?);
+        }
+    }
+    for child in node.children() {
+        process_morphed_node(&child, index, bones, node_to_bone, meshes, model_name, buffers)?;
+    }
 
-            // Extract indices
-            let indices: Vec<u32> = if let Some(indices_reader) = reader.read_indices() {
-                indices_reader.into_u32().collect()
-            } else {
-                (0..positions.len() as u32).collect()
-            };
-            log::info!("[GLTF]    - Primitive has {} indices", indices.len());
+    // Nodes leading to no morphed mesh need no bone.
+    if bones.len() == index + 1 && meshes.len() == first_mesh {
+        bones.pop();
+        node_to_bone.remove(&node.index());
+    }
+    Ok(())
+}
+
+fn has_morph_targets(mesh: &gltf::Mesh) -> bool {
+    mesh.primitives().any(|primitive| primitive.morph_targets().len() > 0)
+}
+
+/// Reads a primitive of `mesh` as placed by `node`. Its vertices are weighted fully to
+/// `rigid_bone` when given, and to the joints the primitive names otherwise.
+fn process_skinned_primitive(
+    primitive: &gltf::Primitive,
+    node: &gltf::Node,
+    mesh: &gltf::Mesh,
+    mesh_name: String,
+    model_name: &str,
+    buffers: &[gltf::buffer::Data],
+    rigid_bone: Option<u16>,
+) -> Result<AnimatedMesh, Box<dyn std::error::Error>> {
+    // Get texture name
+    let texture_name = primitive.material().pbr_metallic_roughness()
+        .base_color_texture()
+        .map(|tex| gltf_texture_key(model_name, &tex.texture()));
+    let material = gltf_material_key(model_name, &primitive.material());
 
-            // Build deduplicated vertex buffer using indices
-            // Quantize weights to [u16; 4] for hashing
-            fn quantize_weights(w: [f32; 4]) -> [u16; 4] {
+    // Extract vertex data
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+    let positions: Vec<[f32; 3]> = reader
+        .read_positions()
+        .ok_or("Mesh has no positions")?
+        .collect();
+
+    log::info!("[GLTF]    - Primitive has {} vertices", positions.len());
+
+    let normals: Vec<[f32; 3]> = if let Some(normals_iter) = reader.read_normals() {
+        normals_iter.collect()
+    } else {
+        log::warn!("[GLTF]    - No normals found, generating defaults");
+        vec![[0.0, 0.0, 1.0]; positions.len()]
+    };
+
+    let uvs: Vec<[f32; 2]> = if let Some(tex_coords) = reader.read_tex_coords(0) {
+        tex_coords.into_f32().collect()
+    } else {
+        log::warn!("[GLTF]    - No UVs found, using defaults");
+        vec![[0.0, 0.0]; positions.len()]
+    };
+    // Missing tangents are generated at bake time.
+    let tangents: Option<Vec<[f32; 4]>> = reader.read_tangents().map(|t| t.collect());
+    let uvs1: Option<Vec<[f32; 2]>> = reader.read_tex_coords(1).map(|t| t.into_f32().collect());
+    let colors: Option<Vec<[f32; 4]>> = reader.read_colors(0).map(|c| c.into_rgba_f32().collect());
+
+    // Read bone weights and indices
+    let joints: Vec<[u16; 4]> = if let Some(bone) = rigid_bone {
+        vec![[bone, 0, 0, 0]; positions.len()]
+    } else if let Some(joints_iter) = reader.read_joints(0) {
+        joints_iter.into_u16().collect()
+    } else {
+        log::warn!("[GLTF]    - No joint data found, using defaults");
+        vec![[0, 0, 0, 0]; positions.len()]
+    };
+
+    let weights: Vec<[f32; 4]> = if rigid_bone.is_some() {
+        vec![[1.0, 0.0, 0.0, 0.0]; positions.len()]
+    } else if let Some(weights_iter) = reader.read_weights(0) {
+        weights_iter.into_f32().collect()
+    } else {
+        log::warn!("[GLTF]    - No weight data found, using defaults");
+        vec![[1.0, 0.0, 0.0, 0.0]; positions.len()]
+    };
+
+    // Extract indices
+    let indices: Vec<u32> = if let Some(indices_reader) = reader.read_indices() {
+        indices_reader.into_u32().collect()
+    } else {
+        (0..positions.len() as u32).collect()
+    };
+    log::info!("[GLTF]    - Primitive has {} indices", indices.len());
+
+    // Keep only the vertices the indices reference. Vertices with equal attributes are
+    // welded later, by the bake's optimization stage.
+    let mut vertex_map: HashMap<u32, u32> = HashMap::new();
+    let mut dedup_vertices: Vec<SkinnedVertex> = Vec::new();
+    let mut remapped_indices: Vec<u32> = Vec::with_capacity(indices.len());
+    let mut source_indices: Vec<u32> = Vec::new();
+
+    for &idx in &indices {
+        let pos_idx = idx as usize;
+        let norm_idx = idx as usize;
+        let uv_idx = idx as usize;
+        let joint = joints.get(idx as usize).copied().unwrap_or([0, 0, 0, 0]);
+        let weight = weights.get(idx as usize).copied().unwrap_or([1.0, 0.0, 0.0, 0.0]);
+        let entry = vertex_map.entry(idx).or_insert_with(|| {
+            // Normalize weights
+            let weight_sum: f32 = weight.iter().sum();
+            let normalized_weights = if weight_sum > 0.0 {
                 [
-                    (w[0] * 65535.0).round() as u16,
-                    (w[1] * 65535.0).round() as u16,
-                    (w[2] * 65535.0).round() as u16,
-                    (w[3] * 65535.0).round() as u16,
+                    weight[0] / weight_sum,
+                    weight[1] / weight_sum,
+                    weight[2] / weight_sum,
+                    weight[3] / weight_sum,
                 ]
-            }
-            let mut vertex_map: HashMap<(u32, u32, u32, [u16; 4], [u16; 4]), u32> = HashMap::new();
-            let mut dedup_vertices: Vec<SkinnedVertex> = Vec::new();
-            let mut remapped_indices: Vec<u32> = Vec::with_capacity(indices.len());
-
-            for &idx in &indices {
-                let pos_idx = idx as usize;
-                let norm_idx = idx as usize;
-                let uv_idx = idx as usize;
-                let joint = joints.get(idx as usize).copied().unwrap_or([0, 0, 0, 0]);
-                let weight = weights.get(idx as usize).copied().unwrap_or([1.0, 0.0, 0.0, 0.0]);
-                let quant_weight = quantize_weights(weight);
-                let key = (pos_idx as u32, norm_idx as u32, uv_idx as u32, joint, quant_weight);
-                let entry = vertex_map.entry(key).or_insert_with(|| {
-                    // Normalize weights
-                    let weight_sum: f32 = weight.iter().sum();
-                    let normalized_weights = if weight_sum > 0.0 {
-                        [
-                            weight[0] / weight_sum,
-                            weight[1] / weight_sum,
-                            weight[2] / weight_sum,
-                            weight[3] / weight_sum,
-                        ]
-                    } else {
-                        [1.0, 0.0, 0.0, 0.0]
-                    };
-                    let pos = positions[pos_idx];
-                    let norm = normals[norm_idx];
-                    let uv = uvs.get(uv_idx).copied().unwrap_or([0.0, 0.0]);
-                    
-                    // For skinned meshes, vertices are in model space. Do not transform them here.
-                    let pos_vec = Vec3::new(pos[0], pos[1], pos[2]);
-                    let norm_vec = Vec3::new(norm[0], norm[1], norm[2]).normalize_or_zero();
-                    
-                    dedup_vertices.push(SkinnedVertex {
-                        position: pos_vec.extend(1.0),
-                        normal: norm_vec.extend(0.0),
-                        uv: Vec2::new(uv[0], uv[1]),
-                        bone_indices: [
-                            joint[0] as u32,
-                            joint[1] as u32,
-                            joint[2] as u32,
-                            joint[3] as u32,
-                        ],
-                        bone_weights: normalized_weights,
-                        _padding: [0.0; 2],
-                    });
-                    (dedup_vertices.len() - 1) as u32
-                });
-                remapped_indices.push(*entry);
-            }
+            } else {
+                [1.0, 0.0, 0.0, 0.0]
+            };
+            let pos = positions[pos_idx];
+            let norm = normals[norm_idx];
+            let uv = uvs.get(uv_idx).copied().unwrap_or([0.0, 0.0]);
+            
+            // For skinned meshes, vertices are in model space. Do not transform them here.
+            let pos_vec = Vec3::new(pos[0], pos[1], pos[2]);
+            let norm_vec = Vec3::new(norm[0], norm[1], norm[2]).normalize_or_zero();
+            
+            dedup_vertices.push(SkinnedVertex {
+                position: pos_vec.extend(1.0),
+                normal: norm_vec.extend(0.0),
+                uv: Vec2::new(uv[0], uv[1]),
+                bone_indices: [
+                    joint[0] as u32,
+                    joint[1] as u32,
+                    joint[2] as u32,
+                    joint[3] as u32,
+                ],
+                bone_weights: normalized_weights,
+                uv1: source_uv1(uvs1.as_deref(), idx),
+                tangent: source_tangent(tangents.as_deref(), idx),
+                color: source_color(colors.as_deref(), idx),
+            });
+            source_indices.push(idx);
+            (dedup_vertices.len() - 1) as u32
+        });
+        remapped_indices.push(*entry);
+    }
 
-            // Build meshlets
-            let meshlets = build_meshlets_for_skinned_vertices(&dedup_vertices, &remapped_indices)?;
-
-            // Calculate AABB
-            let mut aabb = AABB::default();
-            if let Some(first_vtx) = dedup_vertices.first() {
-                aabb.min = first_vtx.position;
-                aabb.max = first_vtx.position;
-                for v in dedup_vertices.iter().skip(1) {
-                    aabb.min = aabb.min.min(v.position);
-                    aabb.max = aabb.max.max(v.position);
-                }
+    // Morph targets hold a delta for every source vertex; keep those of the vertices kept.
+    let pick = |deltas: &[[f32; 3]]| -> Vec<Vec3> {
+        source_indices
+            .iter()
+            .map(|&i| deltas.get(i as usize).map_or(Vec3::ZERO, |d| Vec3::from(*d)))
+            .collect()
+    };
+    let morph_targets: Vec<MorphTarget> = reader
+        .read_morph_targets()
+        .enumerate()
+        .map(|(i, (positions, normals, _))| {
+            let positions: Vec<[f32; 3]> = positions.map(|p| p.collect()).unwrap_or_default();
+            let normals: Vec<[f32; 3]> = normals.map(|n| n.collect()).unwrap_or_default();
+            MorphTarget {
+                name: format!("target_{i}"),
+                position_deltas: pick(&positions),
+                normal_deltas: if normals.is_empty() { Vec::new() } else { pick(&normals) },
             }
-
-            meshes.push(AnimatedMesh {
-                name: unique_mesh_name,
-                vertices: dedup_vertices,
-                indices: remapped_indices,
-                texture_name,
-                meshlets,
-                aabb,
-            });
-        }
+        })
+        .collect();
+    let mut morph_weights = node.weights().or(mesh.weights()).map_or_else(Vec::new, <[f32]>::to_vec);
+    morph_weights.resize(morph_targets.len(), 0.0);
+    if !morph_targets.is_empty() {
+        log::info!("[GLTF]    - Primitive has {} morph targets", morph_targets.len());
     }
 
-    // Process children recursively
-    for child in node.children() {
-        process_animated_node(
-            &child,
-            meshes,
-            mesh_counter,
-            model_name,
-            buffers,
-            skeleton,
-            node_to_bone,
-            textures_to_add,
-        )?;
+    // Build meshlets
+    let meshlets = build_meshlets_for_skinned_vertices(&dedup_vertices, &remapped_indices)?;
+
+    // Calculate AABB
+    let mut aabb = AABB::default();
+    if let Some(first_vtx) = dedup_vertices.first() {
+        aabb.min = first_vtx.position;
+        aabb.max = first_vtx.position;
+        for v in dedup_vertices.iter().skip(1) {
+            aabb.min = aabb.min.min(v.position);
+            aabb.max = aabb.max.max(v.position);
+        }
     }
 
-    Ok(())
+    Ok(AnimatedMesh {
+        name: mesh_name,
+        vertices: dedup_vertices,
+        quantized_vertices: None,
+        indices: remapped_indices,
+        texture_name,
+        material,
+        meshlets,
+        lods: Vec::new(),
+        aabb,
+        attributes: VertexAttributes {
+            uv1: uvs1.is_some(),
+            color: colors.is_some(),
+        },
+        morph_targets,
+        morph_weights,
+        node_name: Some(node_key(node)),
+        bone_palette: Vec::new(),
+    })
 }
 
 fn process_gltf_animation(
     animation: &gltf::Animation,
     buffers: &[gltf::buffer::Data],
+    model_name: &str,
     skeleton: &Skeleton,
     node_to_bone: &HashMap<usize, usize>,
+    morph_nodes: &HashSet<String>,
 ) -> Result<Animation, Box<dyn std::error::Error>> {
     let name = animation.name().unwrap_or("Unnamed Animation").to_string();
     let mut channels = Vec::new();
+    let mut morph_channels = Vec::new();
     let mut max_time: f64 = 0.0;
 
     // Group channels by target node
     let mut channel_map: HashMap<String, AnimationChannel> = HashMap::new();
 
     log::info!("[GLTF]    - Animation has {} channels", animation.channels().count());
+    let mut skipped = 0;
 
     for channel in animation.channels() {
         let target_node = channel.target().node();
         let node_index = target_node.index();
 
+        if channel.target().property() == gltf::animation::Property::MorphTargetWeights {
+            let node_name = node_key(&target_node);
+            if !morph_nodes.contains(&node_name) {
+                skipped += 1;
+                continue;
+            }
+            let morph_channel = read_morph_channel(&channel, buffers, node_name)?;
+            if let Some(last) = morph_channel.keys.last() {
+                max_time = max_time.max(last.time);
+            }
+            log::info!("[GLTF]        - Weights channel: {} keyframes", morph_channel.keys.len());
+            morph_channels.push(morph_channel);
+            continue;
+        }
+
         // Check if this node is a bone in our skeleton
         let bone_name = if let Some(&bone_idx) = node_to_bone.get(&node_index) {
             skeleton.bones[bone_idx].name.clone()
         } else {
-            let node_name_owned = match target_node.name() {
-                Some(name) => name.to_string(),
-                None => format!("Node_{node_index}"),
-            };
+            let node_name_owned = node_key(&target_node);
             if skeleton.bones.iter().any(|b| b.name == node_name_owned) {
                 node_name_owned
             } else {
-                log::warn!("[GLTF]        - Skipping channel for non-bone node: {node_name_owned}");
+                log::debug!("[GLTF]        - Skipping channel for non-bone node: {node_name_owned}");
+                skipped += 1;
                 continue;
             }
         };
@@ -709,6 +1111,7 @@ fn process_gltf_animation(
                 bone_name,
                 position_keys: Vec::new(),
                 rotation_keys: Vec::new(),
+                quantized_rotation_keys: None,
                 scale_keys: Vec::new(),
             });
 
@@ -716,39 +1119,49 @@ fn process_gltf_animation(
         let times: Vec<f32> = reader.read_inputs()
             .ok_or("Animation channel has no input times")?
             .collect();
+        let interpolation = sampler_interpolation(&channel);
 
         match reader.read_outputs() {
             Some(gltf::animation::util::ReadOutputs::Translations(translations)) => {
-                let positions: Vec<[f32; 3]> = translations.collect();
-                log::info!("[GLTF]        - Translation channel: {} keyframes", positions.len());
-                for (time, pos) in times.iter().zip(positions.iter()) {
+                let keys = sampler_keys(translations.collect(), interpolation);
+                log::info!("[GLTF]        - Translation channel: {} {:?} keyframes", keys.len(), interpolation);
+                for (time, (in_tangent, pos, out_tangent)) in times.iter().zip(keys) {
                     entry.position_keys.push(PositionKey {
                         time: *time as f64,
-                        position: Vec3::from(*pos),
+                        position: Vec3::from(pos),
+                        interpolation,
+                        in_tangent: Vec3::from(in_tangent),
+                        out_tangent: Vec3::from(out_tangent),
                     });
                     max_time = max_time.max(*time as f64);
                 }
             }
             Some(gltf::animation::util::ReadOutputs::Rotations(rotations)) => {
-                let quats: Vec<[f32; 4]> = rotations.into_f32().collect();
-                log::info!("[GLTF]        - Rotation channel: {} keyframes", quats.len());
-                for (time, quat) in times.iter().zip(quats.iter()) {
+                let keys = sampler_keys(rotations.into_f32().collect(), interpolation);
+                log::info!("[GLTF]        - Rotation channel: {} {:?} keyframes", keys.len(), interpolation);
+                for (time, (in_tangent, quat, out_tangent)) in times.iter().zip(keys) {
                     // GLTF quaternions are [x, y, z, w]
                     let rotation = Quat::from_xyzw(quat[0], quat[1], quat[2], quat[3]).normalize();
                     entry.rotation_keys.push(RotationKey {
                         time: *time as f64,
                         rotation,
+                        interpolation,
+                        in_tangent: Vec4::from(in_tangent),
+                        out_tangent: Vec4::from(out_tangent),
                     });
                     max_time = max_time.max(*time as f64);
                 }
             }
             Some(gltf::animation::util::ReadOutputs::Scales(scales)) => {
-                let scales: Vec<[f32; 3]> = scales.collect();
-                log::info!("[GLTF]        - Scale channel: {} keyframes", scales.len());
-                for (time, scale) in times.iter().zip(scales.iter()) {
+                let keys = sampler_keys(scales.collect(), interpolation);
+                log::info!("[GLTF]        - Scale channel: {} {:?} keyframes", keys.len(), interpolation);
+                for (time, (in_tangent, scale, out_tangent)) in times.iter().zip(keys) {
                     entry.scale_keys.push(ScaleKey {
                         time: *time as f64,
-                        scale: Vec3::from(*scale),
+                        scale: Vec3::from(scale),
+                        interpolation,
+                        in_tangent: Vec3::from(in_tangent),
+                        out_tangent: Vec3::from(out_tangent),
                     });
                     max_time = max_time.max(*time as f64);
                 }
@@ -759,20 +1172,127 @@ fn process_gltf_animation(
         }
     }
 
+    if skipped > 0 {
+        log::info!("[GLTF]    - Skipped {skipped} channels for nodes outside the skeleton");
+    }
     channels = channel_map.into_values().collect();
-    log::info!("[GLTF]    - Processed {} bone channels, duration: {:.2}s", channels.len(), max_time);
+    log::info!(
+        "[GLTF]    - Processed {} bone and {} weights channels, duration: {:.2}s",
+        channels.len(),
+        morph_channels.len(),
+        max_time
+    );
 
     Ok(Animation {
         name,
+        skeleton: model_name.to_string(),
         duration_in_ticks: max_time,
         ticks_per_second: 1.0, // GLTF uses seconds directly
         channels,
+        morph_channels,
     })
 }
 
-// --- Helper Functions (Unchanged) ---
+/// Reads a weights channel, whose outputs hold one weight per morph target for every key.
+fn read_morph_channel(
+    channel: &gltf::animation::Channel,
+    buffers: &[gltf::buffer::Data],
+    node_name: String,
+) -> Result<MorphChannel, Box<dyn std::error::Error>> {
+    let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+    let times: Vec<f32> = reader.read_inputs().ok_or("Animation channel has no input times")?.collect();
+    let Some(gltf::animation::util::ReadOutputs::MorphTargetWeights(weights)) = reader.read_outputs() else {
+        return Err(format!("Weights channel for '{node_name}' has no weights").into());
+    };
+    let weights: Vec<f32> = weights.into_f32().collect();
+    let interpolation = sampler_interpolation(channel);
+
+    // Cubic spline keys are stored as in-tangents, values and out-tangents, each for every target.
+    let per_key = if interpolation == Interpolation::CubicSpline { 3 } else { 1 };
+    let target_count = if times.is_empty() { 0 } else { weights.len() / (times.len() * per_key) };
+    if target_count == 0 {
+        return Ok(MorphChannel { node_name, keys: Vec::new() });
+    }
+    let keys = times
+        .iter()
+        .zip(weights.chunks_exact(target_count * per_key))
+        .map(|(&time, outputs)| {
+            let targets: Vec<&[f32]> = outputs.chunks_exact(target_count).collect();
+            match interpolation {
+                Interpolation::CubicSpline => MorphKey {
+                    time: time as f64,
+                    weights: targets[1].to_vec(),
+                    interpolation,
+                    in_tangents: targets[0].to_vec(),
+                    out_tangents: targets[2].to_vec(),
+                },
+                _ => MorphKey {
+                    time: time as f64,
+                    weights: targets[0].to_vec(),
+                    interpolation,
+                    ..Default::default()
+                },
+            }
+        })
+        .collect();
+    Ok(MorphChannel { node_name, keys })
+}
+
+fn sampler_interpolation(channel: &gltf::animation::Channel) -> Interpolation {
+    match channel.sampler().interpolation() {
+        gltf::animation::Interpolation::Step => Interpolation::Step,
+        gltf::animation::Interpolation::Linear => Interpolation::Linear,
+        gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+    }
+}
+
+/// Name channels use for a node: its own, or one made from its index.
+fn node_key(node: &gltf::Node) -> String {
+    match node.name() {
+        Some(name) => name.to_string(),
+        None => format!("Node_{}", node.index()),
+    }
+}
+
+/// Groups sampler outputs into (in-tangent, value, out-tangent) per key. Cubic spline samplers
+/// store all three for every key; the others store only values and get zero tangents.
+fn sampler_keys<T: Copy + Default>(outputs: Vec<T>, interpolation: Interpolation) -> Vec<(T, T, T)> {
+    match interpolation {
+        Interpolation::CubicSpline => outputs.chunks_exact(3).map(|k| (k[0], k[1], k[2])).collect(),
+        _ => outputs.into_iter().map(|value| (T::default(), value, T::default())).collect(),
+    }
+}
+
+/// How strongly meshlet building favours triangles facing the same way, which tightens the
+/// normal cones used for backface culling at some cost in meshlet count.
+const CONE_WEIGHT: f32 = 0.25;
+
+/// Converts meshopt's meshlets to the stored form along with their culling bounds.
+fn convert_meshlets(meshlets: &meshopt::Meshlets, adapter: &meshopt::VertexDataAdapter) -> Vec<Meshlet> {
+    meshlets
+        .meshlets
+        .iter()
+        .zip(meshlets.iter())
+        .map(|(m, meshlet)| {
+            let bounds = meshopt::compute_meshlet_bounds(meshlet, adapter);
+            Meshlet {
+                vertex_offset: m.vertex_offset,
+                triangle_offset: m.triangle_offset,
+                vertex_count: m.vertex_count,
+                triangle_count: m.triangle_count,
+                bounds: MeshletBounds {
+                    center: Vec3::from(bounds.center),
+                    radius: bounds.radius,
+                    cone_apex: Vec3::from(bounds.cone_apex),
+                    cone_axis: Vec3::from(bounds.cone_axis),
+                    cone_cutoff: bounds.cone_cutoff,
+                },
+            }
+        })
+        .collect()
+}
 
-fn build_meshlets_for_vertices(
+pub fn build_meshlets_for_vertices(
     vertices: &[Vertex],
     indices: &[u32],
 ) -> Result<Option<Meshlets>, Box<dyn std::error::Error>> {
@@ -784,8 +1304,8 @@ fn build_meshlets_for_vertices(
     let vertex_stride = std::mem::size_of::<Vertex>();
     let vertex_data_bytes = bytemuck::cast_slice(vertices);
 
-    let adapter = VertexDataAdapter::new(vertex_data_bytes, vertex_stride, 0).unwrap();
-    let meshlets_result = build_meshlets(indices, &adapter, MAX_VERTICES, MAX_TRIANGLES, 0.0);
+    let adapter = VertexDataAdapter::new(vertex_data_bytes, vertex_stride, 0)?;
+    let meshlets_result = build_meshlets(indices, &adapter, MAX_VERTICES, MAX_TRIANGLES, CONE_WEIGHT);
 
     if meshlets_result.meshlets.is_empty() {
         log::warn!("[GLTF]    - No meshlets generated for static mesh");
@@ -794,16 +1314,7 @@ fn build_meshlets_for_vertices(
 
     log::info!("[GLTF]    - Generated {} meshlets for static mesh", meshlets_result.meshlets.len());
 
-    let converted_meshlets = meshlets_result
-        .meshlets
-        .iter()
-        .map(|m| Meshlet {
-            vertex_offset: m.vertex_offset,
-            triangle_offset: m.triangle_offset,
-            vertex_count: m.vertex_count,
-            triangle_count: m.triangle_count,
-        })
-        .collect();
+    let converted_meshlets = convert_meshlets(&meshlets_result, &adapter);
 
     Ok(Some(Meshlets {
         meshlets: converted_meshlets,
@@ -812,7 +1323,7 @@ fn build_meshlets_for_vertices(
     }))
 }
 
-fn build_meshlets_for_skinned_vertices(
+pub fn build_meshlets_for_skinned_vertices(
     vertices: &[SkinnedVertex],
     indices: &[u32],
 ) -> Result<Option<Meshlets>, Box<dyn std::error::Error>> {
@@ -824,21 +1335,12 @@ fn build_meshlets_for_skinned_vertices(
     let vertex_stride = std::mem::size_of::<SkinnedVertex>();
     let vertex_data_bytes = bytemuck::cast_slice(vertices);
 
-    let adapter = VertexDataAdapter::new(vertex_data_bytes, vertex_stride, 0).unwrap();
-    let meshlets_result = build_meshlets(indices, &adapter, MAX_VERTICES, MAX_TRIANGLES, 0.0);
+    let adapter = VertexDataAdapter::new(vertex_data_bytes, vertex_stride, 0)?;
+    let meshlets_result = build_meshlets(indices, &adapter, MAX_VERTICES, MAX_TRIANGLES, CONE_WEIGHT);
 
     log::info!("[GLTF]    - Generated {} meshlets for animated mesh", meshlets_result.meshlets.len());
 
-    let converted_meshlets = meshlets_result
-        .meshlets
-        .iter()
-        .map(|m| Meshlet {
-            vertex_offset: m.vertex_offset,
-            triangle_offset: m.triangle_offset,
-            vertex_count: m.vertex_count,
-            triangle_count: m.triangle_count,
-        })
-        .collect();
+    let converted_meshlets = convert_meshlets(&meshlets_result, &adapter);
 
     Ok(Some(Meshlets {
         meshlets: converted_meshlets,
@@ -847,7 +1349,9 @@ fn build_meshlets_for_skinned_vertices(
     }))
 }
 
-fn validate_and_fix_animation_data(
+/// Rejects clips that cannot play on `skeleton`, sorts their keys, keeps rotations on one
+/// hemisphere and pads every bone track to cover the whole clip.
+pub fn validate_and_fix_animation_data(
     animation: &mut Animation,
     skeleton: &Skeleton,
     model_name: &str,
@@ -868,6 +1372,10 @@ fn validate_and_fix_animation_data(
         ));
     }
 
+    for channel in &mut animation.morph_channels {
+        channel.keys.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(std::cmp::Ordering::Equal));
+    }
+
     for channel in &mut animation.channels {
         if !skeleton.bones.iter().any(|b| b.name == channel.bone_name) {
             return Err(format!(
@@ -880,7 +1388,10 @@ fn validate_and_fix_animation_data(
         channel.rotation_keys.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(std::cmp::Ordering::Equal));
         channel.scale_keys.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(std::cmp::Ordering::Equal));
 
-        if !channel.rotation_keys.is_empty() {
+        // Flipping a spline key would bend the curve through it, so only channels without
+        // spline segments are moved onto one hemisphere.
+        let has_spline_rotations = channel.rotation_keys.iter().any(|k| k.interpolation == Interpolation::CubicSpline);
+        if !has_spline_rotations {
             for i in 1..channel.rotation_keys.len() {
                 let (left, right) = channel.rotation_keys.split_at_mut(i);
                 let prev_quat = left[i - 1].rotation;
@@ -895,34 +1406,77 @@ fn validate_and_fix_animation_data(
         let has_keyframes = !channel.position_keys.is_empty() || !channel.rotation_keys.is_empty() || !channel.scale_keys.is_empty();
 
         if has_keyframes {
-            if channel.position_keys.first().is_none_or(|k| k.time > EPSILON) {
-                let first_pos = channel.position_keys.first().map_or(Vec3::ZERO, |k| k.position);
-                channel.position_keys.insert(0, PositionKey { time: 0.0, position: first_pos });
-            }
-            if channel.rotation_keys.first().is_none_or(|k| k.time > EPSILON) {
-                let first_rot = channel.rotation_keys.first().map_or(Quat::IDENTITY, |k| k.rotation);
-                channel.rotation_keys.insert(0, RotationKey { time: 0.0, rotation: first_rot });
-            }
-            if channel.scale_keys.first().is_none_or(|k| k.time > EPSILON) {
-                let first_scale = channel.scale_keys.first().map_or(Vec3::ONE, |k| k.scale);
-                channel.scale_keys.insert(0, ScaleKey { time: 0.0, scale: first_scale });
-            }
+            let duration = animation.duration_in_ticks;
+            let rest_position = PositionKey { position: Vec3::ZERO, ..Default::default() };
+            let rest_rotation = RotationKey { rotation: Quat::IDENTITY, ..Default::default() };
+            let rest_scale = ScaleKey { scale: Vec3::ONE, ..Default::default() };
+            pad_track(&mut channel.position_keys, rest_position, duration, EPSILON);
+            pad_track(&mut channel.rotation_keys, rest_rotation, duration, EPSILON);
+            pad_track(&mut channel.scale_keys, rest_scale, duration, EPSILON);
+        }
+    }
 
-            channel.position_keys.retain(|k| (k.time - animation.duration_in_ticks).abs() > EPSILON);
-            channel.rotation_keys.retain(|k| (k.time - animation.duration_in_ticks).abs() > EPSILON);
-            channel.scale_keys.retain(|k| (k.time - animation.duration_in_ticks).abs() > EPSILON);
+    Ok(())
+}
 
-            if let Some(first_pos) = channel.position_keys.first().map(|k| k.position) {
-                channel.position_keys.push(PositionKey { time: animation.duration_in_ticks, position: first_pos });
+/// The parts of a key that padding a track to the clip's length touches.
+trait PadKey: Copy {
+    fn time(&self) -> f64;
+    fn set_time(&mut self, time: f64);
+    fn clear_tangents(&mut self, incoming: bool, outgoing: bool);
+}
+
+macro_rules! impl_pad_key {
+    ($key:ty) => {
+        impl PadKey for $key {
+            fn time(&self) -> f64 {
+                self.time
             }
-            if let Some(first_scale) = channel.scale_keys.first().map(|k| k.scale) {
-                channel.scale_keys.push(ScaleKey { time: animation.duration_in_ticks, scale: first_scale });
+            fn set_time(&mut self, time: f64) {
+                self.time = time;
             }
-            if let Some(first_rot) = channel.rotation_keys.first().map(|k| k.rotation) {
-                channel.rotation_keys.push(RotationKey { time: animation.duration_in_ticks, rotation: first_rot });
+            fn clear_tangents(&mut self, incoming: bool, outgoing: bool) {
+                if incoming {
+                    self.in_tangent = Default::default();
+                }
+                if outgoing {
+                    self.out_tangent = Default::default();
+                }
             }
         }
+    };
+}
+
+impl_pad_key!(PositionKey);
+impl_pad_key!(RotationKey);
+impl_pad_key!(ScaleKey);
+
+/// Makes a track cover 0 to `duration` by holding its first value from 0 and its last value
+/// to the end. Authored keys keep their values, so a clip that does not loop ends on its last
+/// pose; wrapping a looping clip is left to the sampler. Added keys take their mode from the
+/// authored key next to them; an empty track gets `rest`.
+fn pad_track<K: PadKey>(keys: &mut Vec<K>, rest: K, duration: f64, epsilon: f64) {
+    match keys.first_mut() {
+        None => keys.push(rest),
+        Some(first) if first.time() > epsilon => {
+            // glTF ignores the first key's in-tangent. Now that a key precedes it, it is cleared
+            // along with the padding key's out-tangent so the first value is held flat.
+            first.clear_tangents(true, false);
+            let mut hold = *first;
+            hold.set_time(0.0);
+            hold.clear_tangents(false, true);
+            keys.insert(0, hold);
+        }
+        Some(_) => {}
     }
 
-    Ok(())
+    let Some(last) = keys.last_mut() else { return };
+    if last.time() < duration - epsilon {
+        // Likewise the last key's out-tangent, now that a key follows it.
+        last.clear_tangents(false, true);
+        let mut hold = *last;
+        hold.set_time(duration);
+        hold.clear_tangents(true, false);
+        keys.push(hold);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,242 @@
+// database/src/keyframes.rs - Keyframe reduction and rotation quantization for baked animation clips
+
+use glam::{Quat, Vec3};
+use types::{Animation, Interpolation, PositionKey, RotationKey, ScaleKey};
+
+/// How far a compressed clip may stray from the imported one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyframeSettings {
+    /// Largest distance, in model units, a reduced position track may be off by at any key.
+    pub position_tolerance: f32,
+    /// Largest angle, in radians, a reduced rotation track may be off by at any key.
+    pub rotation_tolerance: f32,
+    /// Largest difference a reduced scale track may show in any axis at any key.
+    pub scale_tolerance: f32,
+    /// Store the rotations of step and linear tracks as packed smallest-three quaternions.
+    pub quantize_rotations: bool,
+}
+
+impl Default for KeyframeSettings {
+    fn default() -> Self {
+        Self {
+            position_tolerance: 1e-4,
+            rotation_tolerance: 5e-4,
+            scale_tolerance: 1e-4,
+            quantize_rotations: false,
+        }
+    }
+}
+
+/// What compressing one or more clips saved, and the largest error it introduced at any of
+/// the imported keys.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CompressionStats {
+    pub keys_before: usize,
+    pub keys_after: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    pub max_position_error: f32,
+    pub max_rotation_error: f32,
+    pub max_scale_error: f32,
+}
+
+impl CompressionStats {
+    pub fn add(&mut self, other: &CompressionStats) {
+        self.keys_before += other.keys_before;
+        self.keys_after += other.keys_after;
+        self.bytes_before += other.bytes_before;
+        self.bytes_after += other.bytes_after;
+        self.max_position_error = self.max_position_error.max(other.max_position_error);
+        self.max_rotation_error = self.max_rotation_error.max(other.max_rotation_error);
+        self.max_scale_error = self.max_scale_error.max(other.max_scale_error);
+    }
+
+    pub fn log(&self, name: &str) {
+        let saved = 100.0 * (1.0 - self.bytes_after as f64 / self.bytes_before.max(1) as f64);
+        log::info!(
+            "[DB]    - Keys for '{name}': {} -> {}, {} -> {} bytes ({saved:.1}% saved), max error {:.2e} / {:.2e} rad / {:.2e}",
+            self.keys_before,
+            self.keys_after,
+            self.bytes_before,
+            self.bytes_after,
+            self.max_position_error,
+            self.max_rotation_error,
+            self.max_scale_error
+        );
+    }
+}
+
+/// The parts of a key the reduction needs, so one pass serves every track type.
+trait TrackKey: Copy {
+    type Value: Copy;
+
+    fn time(&self) -> f64;
+    fn value(&self) -> Self::Value;
+    fn interpolation(&self) -> Interpolation;
+    /// The linear interpolation the runtime sampler uses for this track.
+    fn lerp(a: Self::Value, b: Self::Value, t: f32) -> Self::Value;
+    fn distance(a: Self::Value, b: Self::Value) -> f32;
+}
+
+impl TrackKey for PositionKey {
+    type Value = Vec3;
+
+    fn time(&self) -> f64 {
+        self.time
+    }
+    fn value(&self) -> Vec3 {
+        self.position
+    }
+    fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+    fn lerp(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+        a.lerp(b, t)
+    }
+    fn distance(a: Vec3, b: Vec3) -> f32 {
+        a.distance(b)
+    }
+}
+
+impl TrackKey for RotationKey {
+    type Value = Quat;
+
+    fn time(&self) -> f64 {
+        self.time
+    }
+    fn value(&self) -> Quat {
+        self.rotation
+    }
+    fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+    fn lerp(a: Quat, b: Quat, t: f32) -> Quat {
+        a.slerp(b, t)
+    }
+    fn distance(a: Quat, b: Quat) -> f32 {
+        // `Quat::angle_between` goes through an approximate acos, whose error near zero is the
+        // size of the tolerance; the half-angle's sine and cosine stay exact for small angles.
+        let delta = a.conjugate() * b;
+        2.0 * delta.xyz().length().atan2(delta.w.abs())
+    }
+}
+
+impl TrackKey for ScaleKey {
+    type Value = Vec3;
+
+    fn time(&self) -> f64 {
+        self.time
+    }
+    fn value(&self) -> Vec3 {
+        self.scale
+    }
+    fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+    fn lerp(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+        a.lerp(b, t)
+    }
+    fn distance(a: Vec3, b: Vec3) -> f32 {
+        (a - b).abs().max_element()
+    }
+}
+
+/// Drops the keys of every channel that its neighbours reproduce within `settings`, then
+/// quantizes the rotations if asked to. Tracks with cubic spline keys are left as imported,
+/// since their tangents would have to be refitted.
+pub fn compress_animation(animation: &mut Animation, settings: &KeyframeSettings) -> CompressionStats {
+    let mut stats = CompressionStats {
+        bytes_before: bincode::serialized_size(animation).unwrap_or(0),
+        ..Default::default()
+    };
+
+    for channel in &mut animation.channels {
+        let original = (channel.position_keys.clone(), channel.rotation_keys.clone(), channel.scale_keys.clone());
+        stats.keys_before += original.0.len() + original.1.len() + original.2.len();
+
+        reduce_track(&mut channel.position_keys, settings.position_tolerance);
+        reduce_track(&mut channel.rotation_keys, settings.rotation_tolerance);
+        reduce_track(&mut channel.scale_keys, settings.scale_tolerance);
+        if settings.quantize_rotations && !channel.rotation_keys.is_empty() {
+            channel.quantize_rotations();
+        }
+        stats.keys_after += channel.position_keys.len() + channel.rotation_key_count() + channel.scale_keys.len();
+
+        // Measured against what the runtime will actually sample, quantization included.
+        stats.max_position_error = stats.max_position_error.max(track_error(&original.0, &channel.position_keys));
+        stats.max_rotation_error = stats
+            .max_rotation_error
+            .max(track_error(&original.1, &channel.decoded_rotation_keys()));
+        stats.max_scale_error = stats.max_scale_error.max(track_error(&original.2, &channel.scale_keys));
+    }
+
+    stats.bytes_after = bincode::serialized_size(animation).unwrap_or(0);
+    stats.log(&animation.name);
+    stats
+}
+
+/// Greedily grows each segment from the last kept key for as long as every key it skips is
+/// reproduced within `tolerance` and shares the mode of the key the segment starts at. The
+/// first and last keys always stay.
+fn reduce_track<K: TrackKey>(keys: &mut Vec<K>, tolerance: f32) {
+    if keys.len() < 3 || keys.iter().any(|k| k.interpolation() == Interpolation::CubicSpline) {
+        return;
+    }
+
+    let mut kept = vec![0];
+    let mut anchor = 0;
+    let mut end = 2;
+    while end < keys.len() {
+        if spans(&keys[anchor..=end], tolerance) {
+            end += 1;
+        } else {
+            anchor = end - 1;
+            kept.push(anchor);
+            end = anchor + 2;
+        }
+    }
+    kept.push(keys.len() - 1);
+
+    *keys = kept.into_iter().map(|i| keys[i]).collect();
+}
+
+/// Whether the first and last of `keys` reproduce every key between them.
+fn spans<K: TrackKey>(keys: &[K], tolerance: f32) -> bool {
+    let (first, last) = (keys[0], keys[keys.len() - 1]);
+    keys[1..keys.len() - 1].iter().all(|key| {
+        key.interpolation() == first.interpolation()
+            && K::distance(segment_value(&first, &last, key.time()), key.value()) <= tolerance
+    })
+}
+
+fn segment_value<K: TrackKey>(a: &K, b: &K, time: f64) -> K::Value {
+    match a.interpolation() {
+        Interpolation::Step => a.value(),
+        _ => K::lerp(a.value(), b.value(), ((time - a.time()) / (b.time() - a.time())) as f32),
+    }
+}
+
+/// The largest distance between `original` and `reduced` sampled at the original keys.
+/// Tracks that kept cubic keys were not changed and count as exact.
+fn track_error<K: TrackKey>(original: &[K], reduced: &[K]) -> f32 {
+    if reduced.iter().any(|k| k.interpolation() == Interpolation::CubicSpline) {
+        return 0.0;
+    }
+    original
+        .iter()
+        .filter_map(|key| Some(K::distance(sample(reduced, key.time())?, key.value())))
+        .fold(0.0, f32::max)
+}
+
+/// Samples a step or linear track like the runtime does, holding the ends.
+fn sample<K: TrackKey>(keys: &[K], time: f64) -> Option<K::Value> {
+    let (first, last) = (keys.first()?, keys.last()?);
+    if time <= first.time() {
+        return Some(first.value());
+    }
+    if time >= last.time() {
+        return Some(last.value());
+    }
+    let next = keys.partition_point(|k| k.time() <= time);
+    Some(segment_value(&keys[next - 1], &keys[next], time))
+}
@@ -0,0 +1,37 @@
+// database/src/lib.rs - Typed access to the redb asset database, and the baker that fills it
+
+mod error;
+mod store;
+mod texture;
+
+#[cfg(feature = "bake")]
+mod bake;
+#[cfg(feature = "bake")]
+pub mod gltf_loader;
+#[cfg(feature = "bake")]
+pub mod keyframes;
+#[cfg(feature = "bake")]
+pub mod lod;
+#[cfg(feature = "bake")]
+mod migrate;
+#[cfg(feature = "bake")]
+pub mod optimize;
+#[cfg(feature = "bake")]
+pub mod mipmap;
+#[cfg(feature = "bake")]
+pub mod palette;
+#[cfg(feature = "bake")]
+pub mod russimp_loader;
+#[cfg(feature = "bake")]
+pub mod tangents;
+#[cfg(feature = "bake")]
+pub mod validate;
+
+pub use error::DatabaseError;
+pub use store::{AssetKind, AssetReader, AssetStore, AssetWriter, Record};
+pub use texture::TextureData;
+
+#[cfg(feature = "bake")]
+pub use bake::{BakeProgress, BakeReport, BakeSettings, ModelDatabase};
+#[cfg(feature = "bake")]
+pub use migrate::{MIGRATIONS, Migration, current_meta, migrate, migrate_with};
@@ -0,0 +1,140 @@
+// database/src/lod.rs - Simplified level-of-detail chains for baked meshes
+
+use meshopt::{SimplifyOptions, VertexDataAdapter};
+use types::{AnimatedMesh, Mesh, MeshLod, SkinnedVertex, Vertex};
+
+use crate::gltf_loader::{build_meshlets_for_skinned_vertices, build_meshlets_for_vertices};
+
+/// How many LODs to generate per mesh and how aggressively.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LodSettings {
+    /// Maximum number of levels after the full-detail mesh.
+    pub levels: usize,
+    /// Fraction of the previous level's triangles each level aims to keep.
+    pub ratio: f32,
+    /// Largest deviation a level may introduce, relative to the mesh extent.
+    pub max_error: f32,
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self {
+            levels: 3,
+            ratio: 0.5,
+            max_error: 0.05,
+        }
+    }
+}
+
+/// How much normals and UVs count against positions when the simplifier measures error.
+const NORMAL_WEIGHT: f32 = 0.5;
+const UV_WEIGHT: f32 = 1.0;
+
+/// The indices of one simplified level and its error in model units.
+type Level = (Vec<u32>, f32);
+
+/// A level may keep at most this share of its parent's triangles, i.e. it has to drop at
+/// least a tenth of them, to be kept.
+const MAX_KEPT_SHARE: f32 = 0.9;
+
+pub fn build_lods(mesh: &mut Mesh, settings: &LodSettings) -> Result<(), Box<dyn std::error::Error>> {
+    let attributes: Vec<f32> = mesh
+        .vertices
+        .iter()
+        .flat_map(|v| [v.normal.x, v.normal.y, v.normal.z, v.uv.x, v.uv.y])
+        .collect();
+    let chain = simplify_chain(
+        bytemuck::cast_slice(&mesh.vertices),
+        std::mem::size_of::<Vertex>(),
+        &attributes,
+        &mesh.indices,
+        SimplifyOptions::None,
+        settings,
+    )?;
+    for (indices, error) in chain {
+        let meshlets = build_meshlets_for_vertices(&mesh.vertices, &indices)?;
+        mesh.lods.push(MeshLod { indices, meshlets, error });
+    }
+    log_chain(&mesh.name, mesh.indices.len(), &mesh.lods);
+    Ok(())
+}
+
+/// Skinned meshes are often split along bone or material seams that have to line up with
+/// their neighbours while deforming, so their borders stay locked.
+pub fn build_skinned_lods(mesh: &mut AnimatedMesh, settings: &LodSettings) -> Result<(), Box<dyn std::error::Error>> {
+    let attributes: Vec<f32> = mesh
+        .vertices
+        .iter()
+        .flat_map(|v| [v.normal.x, v.normal.y, v.normal.z, v.uv.x, v.uv.y])
+        .collect();
+    let chain = simplify_chain(
+        bytemuck::cast_slice(&mesh.vertices),
+        std::mem::size_of::<SkinnedVertex>(),
+        &attributes,
+        &mesh.indices,
+        SimplifyOptions::LockBorder,
+        settings,
+    )?;
+    for (indices, error) in chain {
+        let meshlets = build_meshlets_for_skinned_vertices(&mesh.vertices, &indices)?;
+        mesh.lods.push(MeshLod { indices, meshlets, error });
+    }
+    log_chain(&mesh.name, mesh.indices.len(), &mesh.lods);
+    Ok(())
+}
+
+/// Simplifies `indices` level by level, each from the previous one, until the target error or
+/// level count is reached or the simplifier stops making progress. Errors are converted from
+/// meshopt's relative measure to model units.
+fn simplify_chain(
+    vertex_bytes: &[u8],
+    vertex_stride: usize,
+    attributes: &[f32],
+    indices: &[u32],
+    options: SimplifyOptions,
+    settings: &LodSettings,
+) -> Result<Vec<Level>, Box<dyn std::error::Error>> {
+    // Positions lead every vertex type.
+    let adapter = VertexDataAdapter::new(vertex_bytes, vertex_stride, 0)?;
+    let scale = meshopt::simplify_scale(&adapter);
+    let weights = [NORMAL_WEIGHT, NORMAL_WEIGHT, NORMAL_WEIGHT, UV_WEIGHT, UV_WEIGHT];
+    let locks = vec![false; adapter.vertex_count];
+
+    let mut chain: Vec<Level> = Vec::new();
+    let mut previous = indices;
+    for _ in 0..settings.levels {
+        let target_count = ((previous.len() / 3) as f32 * settings.ratio) as usize * 3;
+        if target_count < 3 {
+            break;
+        }
+        let mut error = 0.0;
+        let simplified = meshopt::simplify_with_attributes_and_locks(
+            previous,
+            &adapter,
+            attributes,
+            &weights,
+            weights.len() * std::mem::size_of::<f32>(),
+            &locks,
+            target_count,
+            settings.max_error,
+            options,
+            Some(&mut error),
+        );
+        if simplified.is_empty() || simplified.len() as f32 > previous.len() as f32 * MAX_KEPT_SHARE {
+            break;
+        }
+        // Each level is simplified from the last, so errors accumulate.
+        let error = error * scale + chain.last().map_or(0.0, |(_, e)| *e);
+        chain.push((simplified, error));
+        previous = &chain.last().unwrap().0;
+    }
+    Ok(chain)
+}
+
+fn log_chain(name: &str, index_count: usize, lods: &[MeshLod]) {
+    let triangles: Vec<String> = std::iter::once(index_count / 3)
+        .chain(lods.iter().map(|lod| lod.indices.len() / 3))
+        .map(|count| count.to_string())
+        .collect();
+    log::info!("[DB]    - LODs for '{name}': {} triangles", triangles.join(" / "));
+}
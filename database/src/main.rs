@@ -1,215 +1,207 @@
-// database/src/main.rs - Key changes for GLTF support
-
-mod gltf_loader;
-
-use std::env;
-use std::fs;
-use std::path::{Path, PathBuf};
-use redb::Database;
-use russimp::scene::{Scene, PostProcess};
-use types::{MODEL_TABLE, TEXTURE_TABLE, ANIMATED_MODEL_TABLE, ANIMATION_TABLE, Model};
-
-pub struct ModelDatabase {
-    db: Database,
-    use_gltf: bool, // Add flag to choose loader
+// database/src/main.rs - Command-line front end for the asset baker
+
+mod commands;
+
+use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+use database::keyframes::KeyframeSettings;
+use database::lod::LodSettings;
+use database::optimize::OptimizeSettings;
+use database::{AssetKind, AssetStore, BakeSettings, ModelDatabase};
+
+/// Bakes and manages the redb asset database used by the renderer.
+#[derive(Parser)]
+#[command(name = "database", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
 }
 
-impl ModelDatabase {
-    pub fn new<P: AsRef<Path>>(path: P, use_gltf: bool) -> Result<Self, Box<dyn std::error::Error>> {
-        let db = Database::create(path)?;
-        Ok(Self { db, use_gltf })
-    }
-
-    pub fn populate_from_assets<P: AsRef<Path>>(
-        &self,
-        assets_dir: P,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let write_txn = self.db.begin_write()?;
-        {
-            let mut model_table = write_txn.open_table(MODEL_TABLE)?;
-            let mut texture_table = write_txn.open_table(TEXTURE_TABLE)?;
-            let mut animated_model_table = write_txn.open_table(ANIMATED_MODEL_TABLE)?;
-            let mut animation_table = write_txn.open_table(ANIMATION_TABLE)?;
-
-            fn visit_dir(
-                dir: &Path,
-                model_table: &mut redb::Table<&str, &[u8]>,
-                texture_table: &mut redb::Table<&str, &[u8]>,
-                animated_model_table: &mut redb::Table<&str, &[u8]>,
-                animation_table: &mut redb::Table<&str, &[u8]>,
-                use_gltf: bool,
-            ) -> Result<(), Box<dyn std::error::Error>> {
-                if dir.is_dir() {
-                    for entry in fs::read_dir(dir)? {
-                        let entry = entry?;
-                        let path = entry.path();
-                        if path.is_dir() {
-                            visit_dir(
-                                &path,
-                                model_table,
-                                texture_table,
-                                animated_model_table,
-                                animation_table,
-                                use_gltf,
-                            )?;
-                        } else {
-                            process_file(
-                                &path,
-                                model_table,
-                                texture_table,
-                                animated_model_table,
-                                animation_table,
-                                use_gltf,
-                            )?;
-                        }
-                    }
-                }
-                Ok(())
-            }
-
-            fn process_file(
-                path: &Path,
-                model_table: &mut redb::Table<&str, &[u8]>,
-                texture_table: &mut redb::Table<&str, &[u8]>,
-                animated_model_table: &mut redb::Table<&str, &[u8]>,
-                animation_table: &mut redb::Table<&str, &[u8]>,
-                use_gltf: bool,
-            ) -> Result<(), Box<dyn std::error::Error>> {
-                let extension = path.extension().and_then(|s| s.to_str());
-                let file_name = path
-                    .file_name()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or_default();
-
-                match extension {
-                    Some("gltf") | Some("glb") => {
-                        let model_name = path
-                            .file_stem()
-                            .and_then(|s| s.to_str())
-                            .unwrap_or("unknown_model");
-
-                        log::info!("[DB] Processing model: {model_name} (using {})", 
-                            if use_gltf { "GLTF" } else { "russimp" });
-
-                        if use_gltf {
-                            // Use GLTF loader
-                            let (static_model, animated_model, animations, textures) = 
-                                crate::gltf_loader::load_gltf_model(path, model_name, false)?;
-                            
-                            // Save textures
-                            for (texture_name, texture_data) in textures {
-                                texture_table.insert(texture_name.as_str(), texture_data.as_slice())?;
-                            }
-                            
-                            // Save model or animated model
-                            if let Some(model) = static_model {
-                                let encoded_model = bincode::serialize(&model)?;
-                                model_table.insert(model_name, encoded_model.as_slice())?;
-                            } else if let Some(animated_model) = animated_model {
-                                let encoded_model = bincode::serialize(&animated_model)?;
-                                animated_model_table.insert(model_name, encoded_model.as_slice())?;
-                                
-                                // Save animations
-                                for animation in animations {
-                                    let encoded_animation = bincode::serialize(&animation)?;
-                                    animation_table.insert(animation.name.as_str(), encoded_animation.as_slice())?;
-                                }
-                            }
-                        } else {
-                            // Use existing russimp loader
-                            let scene = Scene::from_file(
-                                path.to_str().unwrap(),
-                                vec![
-                                    PostProcess::Triangulate,
-                                    PostProcess::JoinIdenticalVertices,
-                                    PostProcess::GenerateSmoothNormals,
-                                ],
-                            )?;
-
-                            // ... (rest of existing russimp processing code)
-                        }
-                    }
-                    Some("png") => {
-                        log::info!("[DB] Processing texture: {file_name}");
-                        let texture_bytes = fs::read(path)?;
-                        texture_table.insert(file_name, texture_bytes.as_slice())?;
-                    }
-                    _ => {
-                        // Skip other file types
-                    }
-                }
-                Ok(())
-            }
-
-            visit_dir(assets_dir.as_ref(), &mut model_table, &mut texture_table, &mut animated_model_table, &mut animation_table, self.use_gltf)?;
-        }
-        write_txn.commit()?;
-        Ok(())
-    }
-
-    pub fn get_model(&self, model_name: &str) -> Result<Option<Model>, Box<dyn std::error::Error>> {
-        let read_txn = self.db.begin_read()?;
-        let model_table = read_txn.open_table(MODEL_TABLE)?;
-        
-        if let Some(model_data) = model_table.get(model_name)? {
-            let model: Model = bincode::deserialize(model_data.value())?;
-            Ok(Some(model))
-        } else {
-            Ok(None)
-        }
-    }
+#[derive(Subcommand)]
+enum Command {
+    /// Import every model (glTF, FBX, OBJ, DAE, 3DS) and PNG under SRC into DB, re-baking only changed files
+    Bake {
+        #[arg(default_value = "assets/models")]
+        src: PathBuf,
+        #[arg(long, default_value = "assets/models.redb")]
+        db: PathBuf,
+        /// Use the glTF importer instead of russimp for .gltf/.glb files
+        #[arg(long)]
+        gltf: bool,
+        /// Number of simplified LODs to generate after the full-detail mesh
+        #[arg(long, default_value_t = LodSettings::default().levels)]
+        lod_levels: usize,
+        /// Fraction of the previous LOD's triangles each LOD aims to keep
+        #[arg(long, default_value_t = LodSettings::default().ratio)]
+        lod_ratio: f32,
+        /// Largest deviation a LOD may introduce, relative to the mesh size
+        #[arg(long, default_value_t = LodSettings::default().max_error)]
+        lod_max_error: f32,
+        /// Weld vertices whose attributes all agree to within this; 0 welds only identical ones
+        #[arg(long, default_value_t = OptimizeSettings::default().weld_epsilon)]
+        weld_epsilon: f32,
+        /// Largest distance a dropped position key may be reproduced off by
+        #[arg(long, default_value_t = KeyframeSettings::default().position_tolerance)]
+        position_tolerance: f32,
+        /// Largest angle, in radians, a dropped rotation key may be reproduced off by
+        #[arg(long, default_value_t = KeyframeSettings::default().rotation_tolerance)]
+        rotation_tolerance: f32,
+        /// Largest difference a dropped scale key may be reproduced off by
+        #[arg(long, default_value_t = KeyframeSettings::default().scale_tolerance)]
+        scale_tolerance: f32,
+        /// Store animation rotations as 6-byte smallest-three quaternions
+        #[arg(long)]
+        quantize_rotations: bool,
+        /// Fail the sources whose validation finds errors instead of baking them anyway
+        #[arg(long)]
+        strict: bool,
+        /// Write the validation findings of the imported sources to PATH as JSON
+        #[arg(long, value_name = "PATH")]
+        report: Option<PathBuf>,
+        /// Number of files imported at once; 0 uses one per core
+        #[arg(long, short, default_value_t = 0)]
+        jobs: usize,
+        /// Store the meshes of sources whose path under SRC starts with PREFIX in the quantized
+        /// vertex format; repeat for several prefixes, or pass "" for everything
+        #[arg(long, value_name = "PREFIX")]
+        quantize: Vec<String>,
+    },
+    /// List the entries of every table together with their stored size
+    List {
+        #[arg(long, default_value = "assets/models.redb")]
+        db: PathBuf,
+        /// Only list one kind of asset
+        #[arg(long, value_enum)]
+        kind: Option<AssetKind>,
+    },
+    /// Print mesh, meshlet, bone and channel statistics for a key
+    Inspect {
+        key: String,
+        #[arg(long, default_value = "assets/models.redb")]
+        db: PathBuf,
+        #[arg(long, value_enum)]
+        kind: Option<AssetKind>,
+    },
+    /// Write a texture (as KTX2, or its top mip level when OUT ends in .png) or a model (as Wavefront OBJ) back to disk
+    Extract {
+        key: String,
+        out: PathBuf,
+        #[arg(long, default_value = "assets/models.redb")]
+        db: PathBuf,
+        #[arg(long, value_enum)]
+        kind: Option<AssetKind>,
+    },
+    /// Upgrade a database baked by an older version in place
+    Migrate {
+        #[arg(long, default_value = "assets/models.redb")]
+        db: PathBuf,
+    },
+    /// Remove a key from the database
+    Delete {
+        key: String,
+        #[arg(long, default_value = "assets/models.redb")]
+        db: PathBuf,
+        /// Required when the key exists in more than one table
+        #[arg(long, value_enum)]
+        kind: Option<AssetKind>,
+    },
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize logger
     env_logger::init();
-    
-    log::info!("Starting database populator");
 
-    // Parse command line arguments
-    let args: Vec<String> = env::args().collect();
-    let use_gltf = args.iter().any(|arg| arg == "--gltf");
-    
-    if use_gltf {
-        log::info!("Using GLTF loader");
-    } else {
-        log::info!("Using russimp loader (default)");
-    }
-
-    let mut workspace_root = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?);
-    workspace_root.pop(); // Go up to the workspace root from the crate root
-
-    let db_path = workspace_root.join("assets/models.redb");
-    let assets_path = workspace_root.join("assets/models");
-
-    let db = ModelDatabase::new(&db_path, use_gltf)?;
-    db.populate_from_assets(&assets_path)?;
-    log::info!("Database populated successfully from {assets_path:?}");
+    match Cli::parse().command {
+        Command::Bake {
+            src,
+            db,
+            gltf,
+            lod_levels,
+            lod_ratio,
+            lod_max_error,
+            weld_epsilon,
+            position_tolerance,
+            rotation_tolerance,
+            scale_tolerance,
+            quantize_rotations,
+            strict,
+            report: report_path,
+            jobs,
+            quantize,
+        } => {
+            log::info!("Starting database populator");
+            if gltf {
+                log::info!("Using GLTF loader");
+            } else {
+                log::info!("Using russimp loader (default)");
+            }
 
-    // Example of retrieving a model
-    if let Some(model) = db.get_model("cube")? {
-        log::info!(
-            "Successfully retrieved model 'cube' with {} meshes.",
-            model.meshes.len()
-        );
-        for mesh in &model.meshes {
-            log::info!("    - Mesh: {}", mesh.name);
-            log::info!("      - Vertices: {}", mesh.vertices.len());
-            log::info!("      - Indices: {}", mesh.indices.len());
-            if let Some(meshlets) = &mesh.meshlets {
-                log::info!("      - Meshlets: {}", meshlets.meshlets.len());
+            let settings = BakeSettings {
+                lod: LodSettings {
+                    levels: lod_levels,
+                    ratio: lod_ratio,
+                    max_error: lod_max_error,
+                },
+                optimize: OptimizeSettings { weld_epsilon },
+                keyframes: KeyframeSettings {
+                    position_tolerance,
+                    rotation_tolerance,
+                    scale_tolerance,
+                    quantize_rotations,
+                },
+                quantize,
+            };
+            let db = ModelDatabase::new(&db, gltf)?
+                .with_settings(settings)
+                .with_jobs(jobs)
+                .with_strict(strict)
+                .with_progress(Box::new(|finished, total, source, failed| {
+                    eprintln!("[{finished}/{total}] {}{source}", if failed { "FAILED " } else { "" });
+                }));
+            let report = db.populate_from_assets(&src)?;
+            println!(
+                "{} added, {} updated, {} unchanged, {} removed, {} failed",
+                report.added.len(),
+                report.updated.len(),
+                report.unchanged.len(),
+                report.removed.len(),
+                report.failed.len()
+            );
+            if let Some(path) = report_path {
+                std::fs::write(&path, report.validation.to_json())?;
+                println!("Validation report written to {}", path.display());
             }
-            // Print first 3 vertices for inspection
-            for (j, v) in mesh.vertices.iter().take(3).enumerate() {
-                log::info!(
-                    "      - Vertex {}: [{}, {}, {}]",
-                    j, v.position.x, v.position.y, v.position.z
-                );
+            for (source, error) in &report.failed {
+                eprintln!("  {source}: {error}");
+            }
+            if !report.failed.is_empty() {
+                return Err(format!("{} of the sources failed to bake", report.failed.len()).into());
+            }
+        }
+        Command::List { db, kind } => commands::list(&AssetStore::open(&db)?, kind)?,
+        Command::Inspect { key, db, kind } => {
+            commands::inspect(&AssetStore::open_current(&db)?, &key, kind)?
+        }
+        Command::Extract { key, out, db, kind } => {
+            commands::extract(&AssetStore::open_current(&db)?, &key, kind, &out)?
+        }
+        Command::Migrate { db } => {
+            let store = AssetStore::open(&db)?;
+            let from = store.format_version()?;
+            let applied = database::migrate(&store)?;
+            if applied.is_empty() {
+                println!("Database is already at version {}", types::FORMAT_VERSION);
+            } else {
+                for step in &applied {
+                    println!("  {step}");
+                }
+                println!("Migrated from version {from} to {}", types::FORMAT_VERSION);
             }
         }
-    } else {
-        log::error!("Could not retrieve model 'cube'");
+        Command::Delete { key, db, kind } => {
+            commands::delete(&AssetStore::open_current(&db)?, &key, kind)?
+        }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
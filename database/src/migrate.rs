@@ -0,0 +1,170 @@
+// database/src/migrate.rs - In-place upgrades of databases baked with an older FORMAT_VERSION
+
+use types::{DatabaseMeta, FORMAT_VERSION, SOURCE_MANIFEST_TABLE, TEXTURE_BLOB_TABLE};
+
+use crate::{AssetKind, AssetStore, AssetWriter, DatabaseError};
+
+/// Upgrades a database from `from` to `from + 1`. Runs inside the same write transaction as
+/// every other step, so a failed migration leaves the database untouched.
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    pub apply: fn(&AssetWriter) -> Result<(), DatabaseError>,
+}
+
+/// Every known upgrade step, in order. When bumping `types::FORMAT_VERSION`, add an entry
+/// for the previous version here.
+///
+/// Every step past the first drops the stored assets and the manifest, so the next bake
+/// imports all sources again. A step that rewrote records instead would have to decode them
+/// with structs frozen at its own version, never with the current `types`, which later
+/// versions keep changing.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 0,
+        description: "record the format version in the meta table",
+        apply: |_| Ok(()),
+    },
+    Migration {
+        from: 1,
+        description: "key animations by model/clip and record their skeleton; everything is rebaked",
+        apply: require_rebake,
+    },
+    Migration {
+        from: 2,
+        description: "add materials, which only the sources have; everything is rebaked",
+        apply: require_rebake,
+    },
+    Migration {
+        from: 3,
+        description: "re-encode PNG textures as KTX2 with full mip chains; everything is rebaked",
+        apply: require_rebake,
+    },
+    Migration {
+        from: 4,
+        description: "store texture contents once per unique image; everything is rebaked",
+        apply: require_rebake,
+    },
+    Migration {
+        from: 5,
+        description: "add mesh LOD chains and record bake settings; everything is rebaked",
+        apply: require_rebake,
+    },
+    Migration {
+        from: 6,
+        description: "add meshlet bounding spheres and normal cones; everything is rebaked",
+        apply: require_rebake,
+    },
+    Migration {
+        from: 7,
+        description: "add quantized vertex storage; everything is rebaked",
+        apply: require_rebake,
+    },
+    Migration {
+        from: 8,
+        description: "add vertex tangents; everything is rebaked",
+        apply: require_rebake,
+    },
+    Migration {
+        from: 9,
+        description: "add a second UV set and vertex colors; everything is rebaked",
+        apply: require_rebake,
+    },
+    Migration {
+        from: 10,
+        description: "bake every skin and the unskinned meshes of a source, listed in a composite; everything is rebaked",
+        apply: require_rebake,
+    },
+    Migration {
+        from: 11,
+        description: "keep keyframe interpolation modes and spline tangents; everything is rebaked",
+        apply: require_rebake,
+    },
+    Migration {
+        from: 12,
+        description: "add morph targets and their weights channels; everything is rebaked",
+        apply: require_rebake,
+    },
+    Migration {
+        from: 13,
+        description: "keep the node hierarchy of static models as prefabs; everything is rebaked",
+        apply: require_rebake,
+    },
+    Migration {
+        from: 14,
+        description: "add authored cameras and punctual lights as scenes; everything is rebaked",
+        apply: require_rebake,
+    },
+    Migration {
+        from: 15,
+        description: "reduce animation keys and optionally quantize rotations; everything is rebaked",
+        apply: require_rebake,
+    },
+    Migration {
+        from: 16,
+        description: "split skinned meshes that weight more bones than the palette holds; everything is rebaked",
+        apply: require_rebake,
+    },
+    Migration {
+        from: 17,
+        description: "record the texture files assimp sources read; everything is rebaked",
+        apply: require_rebake,
+    },
+];
+
+/// The meta entry written by this build of the baker.
+pub fn current_meta() -> DatabaseMeta {
+    DatabaseMeta {
+        format_version: FORMAT_VERSION,
+        baker_version: env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
+
+/// Brings `store` up to `FORMAT_VERSION` and returns the descriptions of the steps applied.
+/// Nothing is applied when any step is missing.
+pub fn migrate(store: &AssetStore) -> Result<Vec<&'static str>, DatabaseError> {
+    migrate_with(store, MIGRATIONS)
+}
+
+/// [`migrate`] through `migrations` instead of [`MIGRATIONS`].
+pub fn migrate_with(store: &AssetStore, migrations: &[Migration]) -> Result<Vec<&'static str>, DatabaseError> {
+    let found = store.format_version()?;
+    if found > FORMAT_VERSION {
+        return Err(DatabaseError::VersionMismatch { found, expected: FORMAT_VERSION });
+    }
+    if found == FORMAT_VERSION {
+        return Ok(Vec::new());
+    }
+
+    let mut steps = Vec::new();
+    for version in found..FORMAT_VERSION {
+        let migration = migrations
+            .iter()
+            .find(|m| m.from == version)
+            .ok_or(DatabaseError::NoMigration { from: version })?;
+        steps.push(migration);
+    }
+
+    let writer = store.write()?;
+    let mut applied = Vec::new();
+    for migration in steps {
+        log::info!("[DB] Migrating from version {}: {}", migration.from, migration.description);
+        (migration.apply)(&writer)?;
+        applied.push(migration.description);
+    }
+    writer.put(DatabaseMeta::KEY, &current_meta())?;
+    writer.commit()?;
+    Ok(applied)
+}
+
+/// For changes that cannot be derived from what is stored: empties every asset table and the
+/// manifest, so the next bake imports all sources again.
+fn require_rebake(writer: &AssetWriter) -> Result<(), DatabaseError> {
+    for kind in AssetKind::ALL {
+        writer.clear(kind.table())?;
+    }
+    writer.clear(TEXTURE_BLOB_TABLE)?;
+    writer.clear(SOURCE_MANIFEST_TABLE)?;
+    log::warn!("[DB] Stored assets were dropped; run `database bake` to rebuild them");
+    Ok(())
+}
@@ -0,0 +1,134 @@
+// database/src/mipmap.rs - Offline mip chain generation for baked textures
+
+use std::collections::HashMap;
+
+use glam::Vec3;
+use types::Material;
+
+use crate::TextureData;
+
+/// What a texture is sampled as, which decides how its mips are filtered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureUsage {
+    /// sRGB-encoded color (base color, emissive), averaged in linear space.
+    Color,
+    /// Linear data (metallic-roughness, occlusion), averaged as stored.
+    Data,
+    /// Tangent-space normals, averaged as vectors and renormalized.
+    Normal,
+}
+
+/// How each texture key referenced by `materials` is used. A texture used as color by any
+/// material stays color.
+pub fn texture_usages<'a>(materials: impl IntoIterator<Item = &'a Material>) -> HashMap<String, TextureUsage> {
+    let mut usages = HashMap::new();
+    let mut color = Vec::new();
+    for material in materials {
+        for key in [&material.metallic_roughness_texture, &material.occlusion_texture].into_iter().flatten() {
+            usages.insert(key.clone(), TextureUsage::Data);
+        }
+        if let Some(key) = &material.normal_texture {
+            usages.insert(key.clone(), TextureUsage::Normal);
+        }
+        color.extend([&material.base_color_texture, &material.emissive_texture].into_iter().flatten());
+    }
+    for key in color {
+        usages.insert(key.clone(), TextureUsage::Color);
+    }
+    usages
+}
+
+/// Textures no material references, such as standalone PNGs, are treated as color.
+pub fn usage_of(usages: &HashMap<String, TextureUsage>, key: &str) -> TextureUsage {
+    usages.get(key).copied().unwrap_or(TextureUsage::Color)
+}
+
+/// Builds the full mip chain of `image` with a 2x2 box filter, each level from the previous one.
+pub fn build_mip_chain(image: &image::RgbaImage, usage: TextureUsage) -> TextureData {
+    let mut texture = TextureData {
+        width: image.width(),
+        height: image.height(),
+        srgb: usage == TextureUsage::Color,
+        levels: vec![image.as_raw().clone()],
+    };
+
+    let to_linear = srgb_to_linear_table();
+    let mut level = 0;
+    while texture.level_size(level) != (1, 1) {
+        let (width, height) = texture.level_size(level);
+        let next = downsample(&texture.levels[level], width, height, |texels| match usage {
+            TextureUsage::Color => average_srgb(texels, &to_linear),
+            TextureUsage::Data => average_linear(texels),
+            TextureUsage::Normal => average_normal(texels),
+        });
+        texture.levels.push(next);
+        level += 1;
+    }
+    texture
+}
+
+/// Halves a `width` x `height` RGBA8 level, combining each 2x2 block with `filter`. Odd edges
+/// reuse the last row or column.
+fn downsample(src: &[u8], width: u32, height: u32, filter: impl Fn([[u8; 4]; 4]) -> [u8; 4]) -> Vec<u8> {
+    let (dst_width, dst_height) = ((width / 2).max(1), (height / 2).max(1));
+    let texel = |x: u32, y: u32| {
+        let i = ((y.min(height - 1) * width + x.min(width - 1)) * 4) as usize;
+        [src[i], src[i + 1], src[i + 2], src[i + 3]]
+    };
+
+    let mut dst = Vec::with_capacity((dst_width * dst_height * 4) as usize);
+    for y in 0..dst_height {
+        for x in 0..dst_width {
+            let (sx, sy) = (x * 2, y * 2);
+            dst.extend_from_slice(&filter([
+                texel(sx, sy),
+                texel(sx + 1, sy),
+                texel(sx, sy + 1),
+                texel(sx + 1, sy + 1),
+            ]));
+        }
+    }
+    dst
+}
+
+fn average_linear(texels: [[u8; 4]; 4]) -> [u8; 4] {
+    std::array::from_fn(|c| {
+        let sum: u32 = texels.iter().map(|t| t[c] as u32).sum();
+        ((sum + 2) / 4) as u8
+    })
+}
+
+fn average_srgb(texels: [[u8; 4]; 4], to_linear: &[f32; 256]) -> [u8; 4] {
+    let mut out = average_linear(texels);
+    for c in 0..3 {
+        let linear = texels.iter().map(|t| to_linear[t[c] as usize]).sum::<f32>() / 4.0;
+        out[c] = (linear_to_srgb(linear) * 255.0).round() as u8;
+    }
+    out
+}
+
+fn average_normal(texels: [[u8; 4]; 4]) -> [u8; 4] {
+    let sum: Vec3 = texels
+        .iter()
+        .map(|t| Vec3::new(t[0] as f32, t[1] as f32, t[2] as f32) / 127.5 - 1.0)
+        .sum();
+    let normal = sum.try_normalize().unwrap_or(Vec3::Z);
+    let encoded = (normal * 0.5 + 0.5) * 255.0;
+    [
+        encoded.x.round() as u8,
+        encoded.y.round() as u8,
+        encoded.z.round() as u8,
+        average_linear(texels)[3],
+    ]
+}
+
+fn srgb_to_linear_table() -> [f32; 256] {
+    std::array::from_fn(|i| {
+        let c = i as f32 / 255.0;
+        if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    })
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+}
@@ -0,0 +1,156 @@
+// database/src/optimize.rs - Vertex welding and GPU-friendly reordering of baked meshes
+
+use std::collections::HashMap;
+
+use bytemuck::Pod;
+use meshopt::VertexDataAdapter;
+use types::{AnimatedMesh, Mesh, SkinnedVertex, Vertex};
+
+use crate::gltf_loader::{build_meshlets_for_skinned_vertices, build_meshlets_for_vertices};
+
+/// How the optimization stage treats vertices.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OptimizeSettings {
+    /// Vertices whose attributes all round to the same multiple of this are welded into one.
+    /// Zero only welds bit-identical vertices.
+    pub weld_epsilon: f32,
+}
+
+impl Default for OptimizeSettings {
+    fn default() -> Self {
+        Self { weld_epsilon: 1e-6 }
+    }
+}
+
+/// Overdraw optimization may make the vertex cache this much worse in exchange.
+const OVERDRAW_THRESHOLD: f32 = 1.05;
+
+/// Cache model the ACMR in the bake log is measured against.
+const CACHE_SIZE: u32 = 16;
+
+/// Welds `mesh`, reorders its triangles for the vertex cache and overdraw and its vertices
+/// for fetch locality, then rebuilds its meshlets from the result.
+pub fn optimize_mesh(mesh: &mut Mesh, settings: &OptimizeSettings) -> Result<(), Box<dyn std::error::Error>> {
+    let before = (mesh.vertices.len(), acmr(&mesh.indices, mesh.vertices.len()));
+    let (origins, indices) = optimize(&mesh.vertices, &mesh.indices, Some(settings.weld_epsilon), |v: &Vertex| {
+        let [tx, ty, tz, tw] = v.tangent.to_array();
+        let [r, g, b, a] = v.color.to_array();
+        [
+            v.position.x, v.position.y, v.position.z, v.normal.x, v.normal.y, v.normal.z, v.uv.x, v.uv.y,
+            v.uv1.x, v.uv1.y, tx, ty, tz, tw, r, g, b, a,
+        ]
+    })?;
+    let vertices: Vec<Vertex> = origins.iter().map(|&i| mesh.vertices[i as usize]).collect();
+    mesh.meshlets = build_meshlets_for_vertices(&vertices, &indices)?;
+    mesh.vertices = vertices;
+    mesh.indices = indices;
+    log_stats(&mesh.name, before, (mesh.vertices.len(), acmr(&mesh.indices, mesh.vertices.len())));
+    Ok(())
+}
+
+/// Like `optimize_mesh`, but only welds vertices that also share their bones and weights, and
+/// never welds vertices of a mesh with morph targets, whose deltas may tell them apart.
+pub fn optimize_skinned_mesh(mesh: &mut AnimatedMesh, settings: &OptimizeSettings) -> Result<(), Box<dyn std::error::Error>> {
+    let before = (mesh.vertices.len(), acmr(&mesh.indices, mesh.vertices.len()));
+    let weld_epsilon = Some(settings.weld_epsilon).filter(|_| mesh.morph_targets.is_empty());
+    let (origins, indices) = optimize(&mesh.vertices, &mesh.indices, weld_epsilon, |v: &SkinnedVertex| {
+        let [b0, b1, b2, b3] = v.bone_indices.map(|i| i as f32);
+        let [w0, w1, w2, w3] = v.bone_weights;
+        let [tx, ty, tz, tw] = v.tangent.to_array();
+        let [r, g, b, a] = v.color.to_array();
+        [
+            v.position.x, v.position.y, v.position.z, v.normal.x, v.normal.y, v.normal.z, v.uv.x, v.uv.y,
+            v.uv1.x, v.uv1.y, b0, b1, b2, b3, w0, w1, w2, w3, tx, ty, tz, tw, r, g, b, a,
+        ]
+    })?;
+    let vertices: Vec<SkinnedVertex> = origins.iter().map(|&i| mesh.vertices[i as usize]).collect();
+    for target in &mut mesh.morph_targets {
+        target.remap(&origins);
+    }
+    mesh.meshlets = build_meshlets_for_skinned_vertices(&vertices, &indices)?;
+    mesh.vertices = vertices;
+    mesh.indices = indices;
+    log_stats(&mesh.name, before, (mesh.vertices.len(), acmr(&mesh.indices, mesh.vertices.len())));
+    Ok(())
+}
+
+/// Returns the optimized indices and, for each vertex they refer to, the index of the input
+/// vertex it is a copy of. Vertices are only welded when `weld_epsilon` is given.
+fn optimize<V: Pod + Default, const N: usize>(
+    vertices: &[V],
+    indices: &[u32],
+    weld_epsilon: Option<f32>,
+    attributes: impl Fn(&V) -> [f32; N],
+) -> Result<(Vec<u32>, Vec<u32>), Box<dyn std::error::Error>> {
+    let (origins, indices) = match weld_epsilon {
+        Some(epsilon) => weld(vertices, indices, epsilon, attributes),
+        None => compact(indices),
+    };
+    let welded: Vec<V> = origins.iter().map(|&i| vertices[i as usize]).collect();
+    let mut indices = meshopt::optimize_vertex_cache(&indices, welded.len());
+    // Positions lead every vertex type.
+    let adapter = VertexDataAdapter::new(bytemuck::cast_slice(&welded), std::mem::size_of::<V>(), 0)?;
+    meshopt::optimize_overdraw_in_place(&mut indices, &adapter, OVERDRAW_THRESHOLD);
+    let origins = meshopt::optimize_vertex_fetch(&mut indices, &origins);
+    Ok((origins, indices))
+}
+
+/// Merges vertices whose attributes quantize to the same values, keeping the first of each
+/// group, and drops vertices no index refers to. Returns the kept vertices' input indices.
+fn weld<V: Copy, const N: usize>(
+    vertices: &[V],
+    indices: &[u32],
+    epsilon: f32,
+    attributes: impl Fn(&V) -> [f32; N],
+) -> (Vec<u32>, Vec<u32>) {
+    let quantize = |x: f32| {
+        if epsilon > 0.0 {
+            (x / epsilon).round() as i64
+        } else {
+            // Adding zero folds -0.0 into 0.0.
+            i64::from((x + 0.0).to_bits())
+        }
+    };
+
+    let mut origins = Vec::new();
+    let mut by_key: HashMap<[i64; N], u32> = HashMap::new();
+    let mut remap: HashMap<u32, u32> = HashMap::new();
+    let indices = indices
+        .iter()
+        .map(|&index| {
+            *remap.entry(index).or_insert_with(|| {
+                *by_key.entry(attributes(&vertices[index as usize]).map(quantize)).or_insert_with(|| {
+                    origins.push(index);
+                    (origins.len() - 1) as u32
+                })
+            })
+        })
+        .collect();
+    (origins, indices)
+}
+
+/// Drops vertices no index refers to without merging any.
+fn compact(indices: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut origins = Vec::new();
+    let mut remap: HashMap<u32, u32> = HashMap::new();
+    let indices = indices
+        .iter()
+        .map(|&index| {
+            *remap.entry(index).or_insert_with(|| {
+                origins.push(index);
+                (origins.len() - 1) as u32
+            })
+        })
+        .collect();
+    (origins, indices)
+}
+
+fn acmr(indices: &[u32], vertex_count: usize) -> f32 {
+    meshopt::analyze_vertex_cache(indices, vertex_count, CACHE_SIZE, 0, 0).acmr
+}
+
+fn log_stats(name: &str, (vertices_before, acmr_before): (usize, f32), (vertices_after, acmr_after): (usize, f32)) {
+    log::info!(
+        "[DB]    - Optimized '{name}': {vertices_before} -> {vertices_after} vertices, ACMR {acmr_before:.3} -> {acmr_after:.3}"
+    );
+}
@@ -0,0 +1,150 @@
+// database/src/palette.rs - Splitting of skinned meshes whose joints do not fit the bone palette
+
+use std::collections::HashMap;
+
+use types::{AABB, AnimatedMesh, AnimatedModel, BONE_PALETTE_SIZE};
+
+use crate::gltf_loader::build_meshlets_for_skinned_vertices;
+
+/// Replaces every mesh of `model` that weights a joint past `BONE_PALETTE_SIZE` with parts
+/// that each weight at most that many bones, named through their `bone_palette`.
+pub fn split_for_palette(model: &mut AnimatedModel) -> Result<(), Box<dyn std::error::Error>> {
+    if !model.meshes.iter().any(exceeds_palette) {
+        return Ok(());
+    }
+    let mut meshes = Vec::with_capacity(model.meshes.len());
+    for mesh in std::mem::take(&mut model.meshes) {
+        if !exceeds_palette(&mesh) {
+            meshes.push(mesh);
+            continue;
+        }
+        let parts = split_mesh(&mesh)?;
+        log::info!(
+            "[DB]    - '{}' weights joints past the {BONE_PALETTE_SIZE}-bone palette; split into {} parts",
+            mesh.name,
+            parts.len()
+        );
+        meshes.extend(parts);
+    }
+    model.meshes = meshes;
+    Ok(())
+}
+
+fn exceeds_palette(mesh: &AnimatedMesh) -> bool {
+    mesh.vertices
+        .iter()
+        .any(|v| weighted_joints(v.bone_indices, v.bone_weights).any(|j| j as usize >= BONE_PALETTE_SIZE))
+}
+
+fn weighted_joints(joints: [u32; 4], weights: [f32; 4]) -> impl Iterator<Item = u32> {
+    joints.into_iter().zip(weights).filter(|(_, w)| *w > 0.0).map(|(j, _)| j)
+}
+
+/// Fills each part with the remaining triangles whose joints still fit its palette, in index
+/// order, so neighbouring triangles mostly land together. A triangle names at most twelve
+/// joints, so every part takes at least one.
+fn split_mesh(mesh: &AnimatedMesh) -> Result<Vec<AnimatedMesh>, Box<dyn std::error::Error>> {
+    if mesh.indices.iter().any(|&i| i as usize >= mesh.vertices.len()) {
+        return Err(format!("'{}' has indices past its vertices", mesh.name).into());
+    }
+    let triangles: Vec<&[u32]> = mesh.indices.chunks_exact(3).collect();
+    let mut assigned = vec![false; triangles.len()];
+    let mut remaining = triangles.len();
+
+    let mut parts = Vec::new();
+    while remaining > 0 {
+        let mut palette: Vec<u32> = Vec::new();
+        let mut slots: HashMap<u32, u32> = HashMap::new();
+        let mut part_triangles = Vec::new();
+        for (t, triangle) in triangles.iter().enumerate() {
+            if assigned[t] {
+                continue;
+            }
+            let mut joints: Vec<u32> = triangle
+                .iter()
+                .map(|&i| &mesh.vertices[i as usize])
+                .flat_map(|v| weighted_joints(v.bone_indices, v.bone_weights))
+                .collect();
+            joints.sort_unstable();
+            joints.dedup();
+            let added = joints.iter().filter(|j| !slots.contains_key(j)).count();
+            if slots.len() + added > BONE_PALETTE_SIZE {
+                continue;
+            }
+            for joint in joints {
+                slots.entry(joint).or_insert_with(|| {
+                    palette.push(joint);
+                    palette.len() as u32 - 1
+                });
+            }
+            assigned[t] = true;
+            remaining -= 1;
+            part_triangles.push(*triangle);
+        }
+        parts.push(build_part(mesh, parts.len(), &part_triangles, palette, &slots)?);
+    }
+    Ok(parts)
+}
+
+/// A mesh of `triangles` alone, with its joints renumbered into `palette` and its morph
+/// targets following its vertices.
+fn build_part(
+    mesh: &AnimatedMesh,
+    index: usize,
+    triangles: &[&[u32]],
+    palette: Vec<u32>,
+    slots: &HashMap<u32, u32>,
+) -> Result<AnimatedMesh, Box<dyn std::error::Error>> {
+    let mut remap: HashMap<u32, u32> = HashMap::new();
+    let mut origins = Vec::new();
+    let indices: Vec<u32> = triangles
+        .iter()
+        .flat_map(|t| t.iter())
+        .map(|&i| {
+            *remap.entry(i).or_insert_with(|| {
+                origins.push(i);
+                origins.len() as u32 - 1
+            })
+        })
+        .collect();
+
+    let vertices: Vec<_> = origins
+        .iter()
+        .map(|&i| {
+            let mut vertex = mesh.vertices[i as usize];
+            // Unweighted slots are never read, so they may point anywhere in the palette.
+            for (joint, weight) in vertex.bone_indices.iter_mut().zip(vertex.bone_weights) {
+                *joint = if weight > 0.0 { slots[joint] } else { 0 };
+            }
+            vertex
+        })
+        .collect();
+
+    let mut morph_targets = mesh.morph_targets.clone();
+    for target in &mut morph_targets {
+        target.remap(&origins);
+    }
+
+    let aabb = vertices
+        .iter()
+        .map(|v| AABB { min: v.position, max: v.position })
+        .reduce(|a, b| AABB { min: a.min.min(b.min), max: a.max.max(b.max) })
+        .unwrap_or(mesh.aabb);
+
+    Ok(AnimatedMesh {
+        name: format!("{}-part-{index}", mesh.name),
+        meshlets: build_meshlets_for_skinned_vertices(&vertices, &indices)?,
+        vertices,
+        quantized_vertices: None,
+        indices,
+        texture_name: mesh.texture_name.clone(),
+        material: mesh.material.clone(),
+        lods: Vec::new(),
+        aabb,
+        attributes: mesh.attributes,
+        morph_targets,
+        morph_weights: mesh.morph_weights.clone(),
+        node_name: mesh.node_name.clone(),
+        bone_palette: palette,
+    })
+}
@@ -0,0 +1,742 @@
+use glam::{Mat4, Quat, Vec2, Vec3, Vec4};
+use russimp::material::{DataContent, Material as AiMaterial, PropertyTypeInfo, TextureType};
+use russimp::node::Node;
+use russimp::scene::{PostProcess, Scene};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::rc::Rc;
+use std::path::PathBuf;
+use types::{
+    material_key, AlphaMode, AnimatedMesh, AnimatedModel, Animation, AnimationChannel, Bone,
+    Material, Mesh, Model, PositionKey, RotationKey, ScaleKey, Skeleton, SkinnedVertex, Vertex,
+    VertexAttributes, AABB,
+};
+
+use crate::gltf_loader::{
+    ImportedModel, build_meshlets_for_skinned_vertices, build_meshlets_for_vertices, validate_and_fix_animation_data,
+};
+
+/// File extensions handed to assimp. glTF is listed too so it can be baked without `--gltf`.
+pub const RUSSIMP_EXTENSIONS: &[&str] = &["fbx", "obj", "dae", "3ds", "gltf", "glb"];
+
+/// Assimp's default when a file does not specify a tick rate.
+const DEFAULT_TICKS_PER_SECOND: f64 = 25.0;
+
+pub fn load_russimp_model<P: AsRef<Path>>(
+    path: P,
+    model_name: &str,
+) -> Result<ImportedModel, Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    log::info!("[Assimp] Loading model: {} from {:?}", model_name, path);
+
+    let scene = Scene::from_file(
+        path.to_str().ok_or("Source path is not valid UTF-8")?,
+        vec![
+            PostProcess::Triangulate,
+            PostProcess::JoinIdenticalVertices,
+            PostProcess::GenerateSmoothNormals,
+            PostProcess::LimitBoneWeights,
+            // Assimp keeps OpenGL's bottom-left UV origin; the renderer samples like glTF.
+            PostProcess::FlipUVs,
+        ],
+    )?;
+    let root = scene.root.clone().ok_or("Scene has no root node")?;
+
+    log::info!("[Assimp] Scene info:");
+    log::info!("  - Meshes: {}", scene.meshes.len());
+    log::info!("  - Materials: {}", scene.materials.len());
+    log::info!("  - Animations: {}", scene.animations.len());
+
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    let dependencies = external_texture_paths(&scene, base_dir);
+    let mut textures = Vec::new();
+    let materials: Vec<(String, Material)> = scene
+        .materials
+        .iter()
+        .enumerate()
+        .map(|(idx, material)| {
+            let key = material_key(model_name, &format!("material_{idx}"));
+            (key, convert_material(material, idx, model_name, base_dir, &mut textures))
+        })
+        .collect();
+    let mesh_materials: Vec<MeshMaterial> = materials
+        .iter()
+        .map(|(key, material)| MeshMaterial {
+            key: key.clone(),
+            texture_name: material.base_color_texture.clone(),
+        })
+        .collect();
+
+    let is_skinned = scene.meshes.iter().any(|mesh| !mesh.bones.is_empty());
+    if is_skinned {
+        log::info!("[Assimp] Processing as animated model");
+        let (animated_model, animations) =
+            process_animated_scene(&scene, &root, model_name, &mesh_materials)?;
+        Ok(ImportedModel {
+            animated_models: vec![animated_model],
+            animations,
+            textures,
+            materials,
+            dependencies,
+            ..Default::default()
+        })
+    } else {
+        log::info!("[Assimp] Processing as static model");
+        let model = process_static_scene(&scene, &root, model_name, &mesh_materials)?;
+        Ok(ImportedModel {
+            model: Some(model),
+            textures,
+            materials,
+            dependencies,
+            ..Default::default()
+        })
+    }
+}
+
+fn to_mat4(m: &russimp::Matrix4x4) -> Mat4 {
+    // Assimp matrices are row-major.
+    Mat4::from_cols_array(&[
+        m.a1, m.b1, m.c1, m.d1, m.a2, m.b2, m.c2, m.d2, m.a3, m.b3, m.c3, m.d3, m.a4, m.b4, m.c4,
+        m.d4,
+    ])
+}
+
+fn to_vec3(v: &russimp::Vector3D) -> Vec3 {
+    Vec3::new(v.x, v.y, v.z)
+}
+
+/// The source's tangent for vertex `i` with its bitangent sign in `w`, or zero when the file
+/// has none and the bake should generate them.
+fn source_tangent(source: &russimp::mesh::Mesh, i: usize, normal: Vec3) -> Vec4 {
+    let (Some(tangent), Some(bitangent)) = (source.tangents.get(i), source.bitangents.get(i)) else {
+        return Vec4::ZERO;
+    };
+    let tangent = to_vec3(tangent);
+    let sign = if normal.cross(tangent).dot(to_vec3(bitangent)) < 0.0 { -1.0 } else { 1.0 };
+    tangent.normalize_or_zero().extend(sign)
+}
+
+fn source_uv(uvs: Option<&Vec<russimp::Vector3D>>, i: usize) -> Vec2 {
+    uvs.and_then(|uvs| uvs.get(i)).map_or(Vec2::ZERO, |uv| Vec2::new(uv.x, uv.y))
+}
+
+/// The vertex color of vertex `i`, white when the mesh has none.
+fn source_color(colors: Option<&Vec<russimp::Color4D>>, i: usize) -> Vec4 {
+    colors.and_then(|c| c.get(i)).map_or(Vec4::ONE, |c| Vec4::new(c.r, c.g, c.b, c.a))
+}
+
+/// Tangents move with the geometry; a mirroring transform also flips the bitangent.
+fn transform_tangent(transform: &Mat4, tangent: Vec4) -> Vec4 {
+    if tangent == Vec4::ZERO {
+        return tangent;
+    }
+    transform
+        .transform_vector3(tangent.truncate())
+        .normalize_or_zero()
+        .extend(tangent.w * transform.determinant().signum())
+}
+
+/// What a mesh needs to know about its material.
+struct MeshMaterial {
+    key: String,
+    texture_name: Option<String>,
+}
+
+fn float_property(material: &AiMaterial, key: &str) -> Option<Vec<f32>> {
+    material.properties.iter().find_map(|p| match &p.data {
+        PropertyTypeInfo::FloatArray(values) if p.key == key && !values.is_empty() => Some(values.clone()),
+        _ => None,
+    })
+}
+
+/// Maps assimp's material keys onto the glTF-style `Material`, storing each texture it finds
+/// as `{model}_texture_{material}[_{slot}].png`.
+fn convert_material(
+    material: &AiMaterial,
+    idx: usize,
+    model_name: &str,
+    base_dir: &Path,
+    textures: &mut Vec<(String, image::RgbaImage)>,
+) -> Material {
+    let mut load = |slot: &str, types: &[TextureType]| -> Option<String> {
+        let texture_name = if slot.is_empty() {
+            format!("{model_name}_texture_{idx}.png")
+        } else {
+            format!("{model_name}_texture_{idx}_{slot}.png")
+        };
+        match load_material_texture(material, types, base_dir) {
+            Ok(Some(image)) => {
+                textures.push((texture_name.clone(), image));
+                Some(texture_name)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                log::warn!("[Assimp] Skipping {} texture of material {idx}: {e}", if slot.is_empty() { "base color" } else { slot });
+                None
+            }
+        }
+    };
+
+    let mut result = Material {
+        name: material
+            .properties
+            .iter()
+            .find_map(|p| match &p.data {
+                PropertyTypeInfo::String(name) if p.key == "?mat.name" => Some(name.clone()),
+                _ => None,
+            })
+            .unwrap_or_else(|| format!("material_{idx}")),
+        base_color_texture: load("", &[TextureType::BaseColor, TextureType::Diffuse]),
+        normal_texture: load("normal", &[TextureType::NormalCamera, TextureType::Normals]),
+        occlusion_texture: load("occlusion", &[TextureType::AmbientOcclusion, TextureType::LightMap]),
+        emissive_texture: load("emissive", &[TextureType::EmissionColor, TextureType::Emissive]),
+        // Formats without PBR data look closest to authored with a dielectric, fairly rough surface.
+        metallic_factor: 0.0,
+        roughness_factor: 0.8,
+        ..Default::default()
+    };
+
+    if let Some(color) = float_property(material, "$clr.base").or_else(|| float_property(material, "$clr.diffuse")) {
+        result.base_color_factor = Vec4::new(
+            color[0],
+            color.get(1).copied().unwrap_or(color[0]),
+            color.get(2).copied().unwrap_or(color[0]),
+            color.get(3).copied().unwrap_or(1.0),
+        );
+    }
+    if let Some(opacity) = float_property(material, "$mat.opacity") {
+        result.base_color_factor.w *= opacity[0];
+    }
+    if result.base_color_factor.w < 1.0 {
+        result.alpha_mode = AlphaMode::Blend;
+    }
+    if let Some(metallic) = float_property(material, "$mat.metallicFactor") {
+        result.metallic_factor = metallic[0];
+    }
+    if let Some(roughness) = float_property(material, "$mat.roughnessFactor") {
+        result.roughness_factor = roughness[0];
+    }
+    if let Some(emissive) = float_property(material, "$clr.emissive") {
+        result.emissive_factor = Vec3::new(
+            emissive[0],
+            emissive.get(1).copied().unwrap_or(emissive[0]),
+            emissive.get(2).copied().unwrap_or(emissive[0]),
+        );
+    }
+    result.double_sided = material.properties.iter().any(|p| {
+        p.key == "$mat.twosided" && matches!(&p.data, PropertyTypeInfo::IntegerArray(v) if v.first().is_some_and(|&v| v != 0))
+    });
+    result
+}
+
+/// Decodes the first texture of `types` found on a material, reading it from the scene when
+/// embedded and from next to the source file otherwise.
+fn load_material_texture(
+    material: &AiMaterial,
+    types: &[TextureType],
+    base_dir: &Path,
+) -> Result<Option<image::RgbaImage>, Box<dyn std::error::Error>> {
+    let texture = types.iter().find_map(|t| material.textures.get(t));
+
+    let image = match texture.map(|t| t.borrow()) {
+        Some(texture) => match &texture.data {
+            DataContent::Texel(texels) if !texels.is_empty() => {
+                let pixels = texels.iter().flat_map(|t| [t.r, t.g, t.b, t.a]).collect();
+                image::RgbaImage::from_raw(texture.width, texture.height, pixels)
+                    .ok_or("Embedded texture size does not match its texels")?
+            }
+            DataContent::Bytes(bytes) if !bytes.is_empty() => {
+                image::load_from_memory(bytes)?.to_rgba8()
+            }
+            _ => load_external_texture(&texture.filename, base_dir)?,
+        },
+        None => {
+            let file = material.properties.iter().find_map(|p| match &p.data {
+                PropertyTypeInfo::String(file) if p.key == "$tex.file" && types.contains(&p.semantic) => {
+                    Some(file.clone())
+                }
+                _ => None,
+            });
+            match file {
+                Some(file) => load_external_texture(&file, base_dir)?,
+                None => return Ok(None),
+            }
+        }
+    };
+
+    Ok(Some(image))
+}
+
+fn load_external_texture(
+    file: &str,
+    base_dir: &Path,
+) -> Result<image::RgbaImage, Box<dyn std::error::Error>> {
+    let candidate = resolve_external_texture(file, base_dir)
+        .ok_or_else(|| format!("texture '{file}' not found next to the source"))?;
+    log::info!("[Assimp]    - Loading texture {:?}", candidate);
+    Ok(image::open(&candidate)?.to_rgba8())
+}
+
+/// DCC tools often write absolute or Windows paths, so fall back to the bare file name.
+fn resolve_external_texture(file: &str, base_dir: &Path) -> Option<PathBuf> {
+    let normalized = file.replace('\\', "/");
+    let file_name = normalized.rsplit('/').next().unwrap_or(&normalized);
+    [base_dir.join(&normalized), base_dir.join(file_name)]
+        .into_iter()
+        .find(|candidate| candidate.is_file())
+}
+
+/// The files the materials of `scene` load their textures from, resolved the way
+/// `load_external_texture` resolves them, so the baker can hash them with the source.
+fn external_texture_paths(scene: &Scene, base_dir: &Path) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for material in &scene.materials {
+        let texture_files = material.textures.values().filter_map(|texture| {
+            let texture = texture.borrow();
+            let embedded = matches!(&texture.data, DataContent::Texel(t) if !t.is_empty())
+                || matches!(&texture.data, DataContent::Bytes(b) if !b.is_empty());
+            (!embedded).then(|| texture.filename.clone())
+        });
+        let property_files = material.properties.iter().filter_map(|p| match &p.data {
+            PropertyTypeInfo::String(file) if p.key == "$tex.file" => Some(file.clone()),
+            _ => None,
+        });
+        for file in texture_files.chain(property_files) {
+            // Embedded textures are named `*0`, `*1`, ... and live in the source itself.
+            if file.starts_with('*') {
+                continue;
+            }
+            if let Some(resolved) = resolve_external_texture(&file, base_dir) {
+                paths.push(resolved);
+            }
+        }
+    }
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+fn compute_aabb<'a>(positions: impl Iterator<Item = &'a glam::Vec4>) -> AABB {
+    let mut positions = positions.peekable();
+    let Some(&first) = positions.peek() else {
+        return AABB::default();
+    };
+    positions.fold(AABB { min: *first, max: *first }, |aabb, p| AABB {
+        min: aabb.min.min(*p),
+        max: aabb.max.max(*p),
+    })
+}
+
+fn merge_aabbs(aabbs: impl Iterator<Item = AABB>) -> AABB {
+    aabbs
+        .reduce(|a, b| AABB { min: a.min.min(b.min), max: a.max.max(b.max) })
+        .unwrap_or_default()
+}
+
+fn triangle_indices(mesh: &russimp::mesh::Mesh) -> Vec<u32> {
+    // Triangulate leaves point and line primitives alone; they have nothing to render.
+    mesh.faces
+        .iter()
+        .filter(|face| face.0.len() == 3)
+        .flat_map(|face| face.0.iter().copied())
+        .collect()
+}
+
+fn process_static_scene(
+    scene: &Scene,
+    root: &Rc<Node>,
+    model_name: &str,
+    materials: &[MeshMaterial],
+) -> Result<Model, Box<dyn std::error::Error>> {
+    let mut meshes = Vec::new();
+    process_static_node(scene, root, &Mat4::IDENTITY, model_name, materials, &mut meshes)?;
+    log::info!("[Assimp] Processed {} meshes", meshes.len());
+
+    let aabb = merge_aabbs(meshes.iter().map(|m| m.aabb));
+    Ok(Model {
+        name: model_name.to_string(),
+        meshes,
+        aabb,
+    })
+}
+
+fn process_static_node(
+    scene: &Scene,
+    node: &Rc<Node>,
+    parent_transform: &Mat4,
+    model_name: &str,
+    materials: &[MeshMaterial],
+    meshes: &mut Vec<Mesh>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let transform = *parent_transform * to_mat4(&node.transformation);
+    let normal_transform = transform.inverse().transpose();
+
+    for &mesh_idx in &node.meshes {
+        let source = &scene.meshes[mesh_idx as usize];
+        let indices = triangle_indices(source);
+        if indices.is_empty() {
+            continue;
+        }
+        let uvs = source.texture_coords.first().and_then(|c| c.as_ref());
+        let uvs1 = source.texture_coords.get(1).and_then(|c| c.as_ref());
+        let colors = source.colors.first().and_then(|c| c.as_ref());
+        let material = materials.get(source.material_index as usize);
+
+        let vertices: Vec<Vertex> = source
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(i, position)| {
+                let normal = source.normals.get(i).map_or(Vec3::Z, to_vec3);
+                let uv = source_uv(uvs, i);
+                let tangent = source_tangent(source, i, normal);
+                Vertex {
+                    position: transform.transform_point3(to_vec3(position)).extend(1.0),
+                    normal: normal_transform.transform_vector3(normal).normalize_or_zero().extend(0.0),
+                    uv,
+                    uv1: source_uv(uvs1, i),
+                    tangent: transform_tangent(&transform, tangent),
+                    color: source_color(colors, i),
+                }
+            })
+            .collect();
+
+        log::info!("[Assimp]    - Mesh '{}': {} vertices, {} triangles", source.name, vertices.len(), indices.len() / 3);
+
+        let meshlets = build_meshlets_for_vertices(&vertices, &indices)?;
+        let aabb = compute_aabb(vertices.iter().map(|v| &v.position));
+        meshes.push(Mesh {
+            name: format!("{}-mesh-{}", model_name, meshes.len()),
+            vertices,
+            quantized_vertices: None,
+            indices,
+            texture_name: material.and_then(|m| m.texture_name.clone()),
+            material: material.map(|m| m.key.clone()),
+            meshlets,
+            lods: Vec::new(),
+            aabb,
+            attributes: VertexAttributes {
+                uv1: uvs1.is_some(),
+                color: colors.is_some(),
+            },
+        });
+    }
+
+    for child in node.children.borrow().iter() {
+        process_static_node(scene, child, &transform, model_name, materials, meshes)?;
+    }
+    Ok(())
+}
+
+/// Bones are every node that is a skinning bone or an ancestor of one, in parent-first order.
+/// Ancestors are kept so their transforms (unit and axis conversion, pivots) still apply.
+struct SkeletonBuilder {
+    bones: Vec<Bone>,
+    /// Bind-pose global transform of each bone.
+    globals: Vec<Mat4>,
+    by_name: HashMap<String, usize>,
+}
+
+impl SkeletonBuilder {
+    fn new(root: &Rc<Node>, offsets: &HashMap<String, Mat4>) -> Self {
+        let mut keep = HashSet::new();
+        mark_bone_ancestors(root, offsets, &mut keep);
+
+        let mut builder = Self {
+            bones: Vec::new(),
+            globals: Vec::new(),
+            by_name: HashMap::new(),
+        };
+        builder.add(root, None, Mat4::IDENTITY, offsets, &keep);
+        builder
+    }
+
+    fn add(
+        &mut self,
+        node: &Rc<Node>,
+        parent_index: Option<usize>,
+        parent_global: Mat4,
+        offsets: &HashMap<String, Mat4>,
+        keep: &HashSet<*const Node>,
+    ) {
+        if !keep.contains(&Rc::as_ptr(node)) {
+            return;
+        }
+        let transform = to_mat4(&node.transformation);
+        let global = parent_global * transform;
+        let index = self.bones.len();
+        self.bones.push(Bone {
+            name: node.name.clone(),
+            parent_index,
+            transform,
+            inverse_bind_pose: offsets.get(&node.name).copied().unwrap_or(Mat4::IDENTITY),
+        });
+        self.globals.push(global);
+        self.by_name.insert(node.name.clone(), index);
+
+        for child in node.children.borrow().iter() {
+            self.add(child, Some(index), global, offsets, keep);
+        }
+    }
+
+    /// The closest bone at or above `node`, with its index.
+    fn nearest_bone(&self, node: &Rc<Node>) -> Option<usize> {
+        let mut current = Some(node.clone());
+        while let Some(node) = current {
+            if let Some(&index) = self.by_name.get(&node.name) {
+                return Some(index);
+            }
+            current = node.parent.borrow().upgrade();
+        }
+        None
+    }
+}
+
+fn mark_bone_ancestors(
+    node: &Rc<Node>,
+    offsets: &HashMap<String, Mat4>,
+    keep: &mut HashSet<*const Node>,
+) -> bool {
+    let mut has_bone = offsets.contains_key(&node.name);
+    for child in node.children.borrow().iter() {
+        has_bone |= mark_bone_ancestors(child, offsets, keep);
+    }
+    if has_bone {
+        keep.insert(Rc::as_ptr(node));
+    }
+    has_bone
+}
+
+fn process_animated_scene(
+    scene: &Scene,
+    root: &Rc<Node>,
+    model_name: &str,
+    materials: &[MeshMaterial],
+) -> Result<(AnimatedModel, Vec<Animation>), Box<dyn std::error::Error>> {
+    let offsets: HashMap<String, Mat4> = scene
+        .meshes
+        .iter()
+        .flat_map(|mesh| &mesh.bones)
+        .map(|bone| (bone.name.clone(), to_mat4(&bone.offset_matrix)))
+        .collect();
+    let skeleton = SkeletonBuilder::new(root, &offsets);
+    log::info!("[Assimp] Skeleton has {} bones ({} skinning bones)", skeleton.bones.len(), offsets.len());
+
+    let mut meshes = Vec::new();
+    process_animated_node(scene, root, &Mat4::IDENTITY, &skeleton, model_name, materials, &mut meshes)?;
+    log::info!("[Assimp] Processed {} animated meshes", meshes.len());
+
+    let mut animations: Vec<Animation> = scene
+        .animations
+        .iter()
+        .enumerate()
+        .map(|(idx, animation)| convert_animation(animation, idx, model_name, &skeleton.by_name))
+        .collect();
+
+    // The same checks and padding glTF clips get.
+    let skeleton = Skeleton { bones: skeleton.bones };
+    for animation in &mut animations {
+        validate_and_fix_animation_data(animation, &skeleton, model_name)?;
+    }
+
+    let aabb = merge_aabbs(meshes.iter().map(|m| m.aabb));
+    Ok((
+        AnimatedModel {
+            name: model_name.to_string(),
+            meshes,
+            skeleton,
+            aabb,
+        },
+        animations,
+    ))
+}
+
+fn process_animated_node(
+    scene: &Scene,
+    node: &Rc<Node>,
+    parent_transform: &Mat4,
+    skeleton: &SkeletonBuilder,
+    model_name: &str,
+    materials: &[MeshMaterial],
+    meshes: &mut Vec<AnimatedMesh>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let node_global = *parent_transform * to_mat4(&node.transformation);
+
+    // Vertices without weights (and meshes without bones) follow the nearest bone rigidly:
+    // pre-transform them so that bone's skinning matrix puts them back where the node had them.
+    let rigid_bone = skeleton.nearest_bone(node).unwrap_or(0);
+    let rigid_bind = match skeleton.bones.get(rigid_bone) {
+        Some(bone) => {
+            (skeleton.globals[rigid_bone] * bone.inverse_bind_pose).inverse() * node_global
+        }
+        None => node_global,
+    };
+    let rigid_normal = rigid_bind.inverse().transpose();
+
+    for &mesh_idx in &node.meshes {
+        let source = &scene.meshes[mesh_idx as usize];
+        let indices = triangle_indices(source);
+        if indices.is_empty() {
+            continue;
+        }
+        let uvs = source.texture_coords.first().and_then(|c| c.as_ref());
+        let uvs1 = source.texture_coords.get(1).and_then(|c| c.as_ref());
+        let colors = source.colors.first().and_then(|c| c.as_ref());
+        let material = materials.get(source.material_index as usize);
+
+        let mut influences: Vec<Vec<(u32, f32)>> = vec![Vec::new(); source.vertices.len()];
+        for bone in &source.bones {
+            let Some(&bone_index) = skeleton.by_name.get(&bone.name) else {
+                continue;
+            };
+            for weight in &bone.weights {
+                if weight.weight > 0.0
+                    && let Some(list) = influences.get_mut(weight.vertex_id as usize)
+                {
+                    list.push((bone_index as u32, weight.weight));
+                }
+            }
+        }
+
+        let mut unweighted = 0;
+        let vertices: Vec<SkinnedVertex> = source
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(i, position)| {
+                let mut position = to_vec3(position);
+                let mut normal = source.normals.get(i).map_or(Vec3::Z, to_vec3);
+                let mut tangent = source_tangent(source, i, normal);
+                let uv = source_uv(uvs, i);
+
+                let list = &mut influences[i];
+                list.sort_by(|a, b| b.1.total_cmp(&a.1));
+                list.truncate(4);
+                let mut bone_indices = [0u32; 4];
+                let mut bone_weights = [0.0f32; 4];
+                let total: f32 = list.iter().map(|(_, w)| w).sum();
+                if total > 0.0 {
+                    for (slot, (index, weight)) in list.iter().enumerate() {
+                        bone_indices[slot] = *index;
+                        bone_weights[slot] = weight / total;
+                    }
+                } else {
+                    unweighted += 1;
+                    bone_indices[0] = rigid_bone as u32;
+                    bone_weights[0] = 1.0;
+                    position = rigid_bind.transform_point3(position);
+                    normal = rigid_normal.transform_vector3(normal);
+                    tangent = transform_tangent(&rigid_bind, tangent);
+                }
+
+                SkinnedVertex {
+                    position: position.extend(1.0),
+                    normal: normal.normalize_or_zero().extend(0.0),
+                    uv,
+                    uv1: source_uv(uvs1, i),
+                    bone_indices,
+                    bone_weights,
+                    tangent,
+                    color: source_color(colors, i),
+                }
+            })
+            .collect();
+
+        log::info!("[Assimp]    - Mesh '{}': {} vertices, {} triangles, {} bones", source.name, vertices.len(), indices.len() / 3, source.bones.len());
+        if unweighted > 0 && !source.bones.is_empty() {
+            log::warn!("[Assimp]    - {unweighted} vertices have no bone weights; attached to '{}'",
+                skeleton.bones.get(rigid_bone).map_or("", |b| b.name.as_str()));
+        }
+
+        let meshlets = build_meshlets_for_skinned_vertices(&vertices, &indices)?;
+        let aabb = compute_aabb(vertices.iter().map(|v| &v.position));
+        meshes.push(AnimatedMesh {
+            name: format!("{}-mesh-{}", model_name, meshes.len()),
+            vertices,
+            quantized_vertices: None,
+            indices,
+            texture_name: material.and_then(|m| m.texture_name.clone()),
+            material: material.map(|m| m.key.clone()),
+            meshlets,
+            lods: Vec::new(),
+            aabb,
+            attributes: VertexAttributes {
+                uv1: uvs1.is_some(),
+                color: colors.is_some(),
+            },
+            morph_targets: Vec::new(),
+            morph_weights: Vec::new(),
+            node_name: None,
+            bone_palette: Vec::new(),
+        });
+    }
+
+    for child in node.children.borrow().iter() {
+        process_animated_node(scene, child, &node_global, skeleton, model_name, materials, meshes)?;
+    }
+    Ok(())
+}
+
+fn convert_animation(
+    animation: &russimp::animation::Animation,
+    idx: usize,
+    model_name: &str,
+    bones: &HashMap<String, usize>,
+) -> Animation {
+    let name = if animation.name.is_empty() {
+        format!("Take {idx}")
+    } else {
+        animation.name.clone()
+    };
+    let ticks_per_second = if animation.ticks_per_second > 0.0 {
+        animation.ticks_per_second
+    } else {
+        DEFAULT_TICKS_PER_SECOND
+    };
+
+    let channels: Vec<AnimationChannel> = animation
+        .channels
+        .iter()
+        .filter(|channel| {
+            let known = bones.contains_key(&channel.name);
+            if !known {
+                log::debug!("[Assimp]    - Dropping channel for non-bone node '{}'", channel.name);
+            }
+            known
+        })
+        .map(|channel| AnimationChannel {
+            bone_name: channel.name.clone(),
+            position_keys: channel
+                .position_keys
+                .iter()
+                .map(|k| PositionKey { time: k.time, position: to_vec3(&k.value), ..Default::default() })
+                .collect(),
+            rotation_keys: channel
+                .rotation_keys
+                .iter()
+                .map(|k| RotationKey {
+                    time: k.time,
+                    rotation: Quat::from_xyzw(k.value.x, k.value.y, k.value.z, k.value.w).normalize(),
+                    ..Default::default()
+                })
+                .collect(),
+            quantized_rotation_keys: None,
+            scale_keys: channel
+                .scaling_keys
+                .iter()
+                .map(|k| ScaleKey { time: k.time, scale: to_vec3(&k.value), ..Default::default() })
+                .collect(),
+        })
+        .collect();
+
+    log::info!("[Assimp] Animation '{}': {} channels, {:.2}s", name, channels.len(), animation.duration / ticks_per_second);
+
+    Animation {
+        name,
+        skeleton: model_name.to_string(),
+        duration_in_ticks: animation.duration,
+        ticks_per_second,
+        channels,
+        morph_channels: Vec::new(),
+    }
+}
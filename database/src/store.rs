@@ -0,0 +1,412 @@
+use std::path::Path;
+
+use redb::{Database, ReadTransaction, ReadableTable, TableDefinition, TableHandle, WriteTransaction};
+use serde::{Serialize, de::DeserializeOwned};
+use types::{
+    ANIMATED_MODEL_TABLE, ANIMATION_TABLE, AnimatedModel, Animation, COMPOSITE_TABLE, Composite,
+    DatabaseMeta, FORMAT_VERSION, MATERIAL_TABLE, META_TABLE, MODEL_TABLE, Material, Model, PREFAB_TABLE,
+    Prefab, SCENE_TABLE, SOURCE_MANIFEST_TABLE, Scene, SourceManifestEntry, TEXTURE_BLOB_TABLE, TEXTURE_TABLE, TextureRef,
+};
+
+use crate::DatabaseError;
+
+pub(crate) type Table = TableDefinition<'static, &'static str, &'static [u8]>;
+type ReadTable = redb::ReadOnlyTable<&'static str, &'static [u8]>;
+type Entry<T> = Result<(String, T), DatabaseError>;
+
+/// A value stored bincode-encoded under a string key in one of the asset tables.
+pub trait Record: Serialize + DeserializeOwned {
+    const TABLE: Table;
+
+    fn decode(key: &str, bytes: &[u8]) -> Result<Self, DatabaseError> {
+        bincode::deserialize(bytes).map_err(|source| DatabaseError::Decode {
+            table: Self::TABLE.name().to_string(),
+            key: key.to_string(),
+            source,
+        })
+    }
+
+    fn encode(&self, key: &str) -> Result<Vec<u8>, DatabaseError> {
+        bincode::serialize(self).map_err(|source| DatabaseError::Encode {
+            table: Self::TABLE.name().to_string(),
+            key: key.to_string(),
+            source,
+        })
+    }
+}
+
+impl Record for Model {
+    const TABLE: Table = MODEL_TABLE;
+}
+
+impl Record for AnimatedModel {
+    const TABLE: Table = ANIMATED_MODEL_TABLE;
+}
+
+impl Record for Animation {
+    const TABLE: Table = ANIMATION_TABLE;
+}
+
+impl Record for Material {
+    const TABLE: Table = MATERIAL_TABLE;
+}
+
+impl Record for Composite {
+    const TABLE: Table = COMPOSITE_TABLE;
+}
+
+impl Record for Prefab {
+    const TABLE: Table = PREFAB_TABLE;
+}
+
+impl Record for Scene {
+    const TABLE: Table = SCENE_TABLE;
+}
+
+impl Record for TextureRef {
+    const TABLE: Table = TEXTURE_TABLE;
+}
+
+impl Record for SourceManifestEntry {
+    const TABLE: Table = SOURCE_MANIFEST_TABLE;
+}
+
+impl Record for DatabaseMeta {
+    const TABLE: Table = META_TABLE;
+}
+
+/// The user-facing asset tables, for tools that work on any of them by key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bake", derive(clap::ValueEnum))]
+pub enum AssetKind {
+    Model,
+    AnimatedModel,
+    Animation,
+    Texture,
+    Material,
+    Composite,
+    Prefab,
+    Scene,
+}
+
+impl AssetKind {
+    pub const ALL: [AssetKind; 8] = [
+        AssetKind::Model,
+        AssetKind::AnimatedModel,
+        AssetKind::Animation,
+        AssetKind::Texture,
+        AssetKind::Material,
+        AssetKind::Composite,
+        AssetKind::Prefab,
+        AssetKind::Scene,
+    ];
+
+    pub fn table(self) -> Table {
+        match self {
+            AssetKind::Model => MODEL_TABLE,
+            AssetKind::AnimatedModel => ANIMATED_MODEL_TABLE,
+            AssetKind::Animation => ANIMATION_TABLE,
+            AssetKind::Texture => TEXTURE_TABLE,
+            AssetKind::Material => MATERIAL_TABLE,
+            AssetKind::Composite => COMPOSITE_TABLE,
+            AssetKind::Prefab => PREFAB_TABLE,
+            AssetKind::Scene => SCENE_TABLE,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AssetKind::Model => "Models",
+            AssetKind::AnimatedModel => "Animated models",
+            AssetKind::Animation => "Animations",
+            AssetKind::Texture => "Textures",
+            AssetKind::Material => "Materials",
+            AssetKind::Composite => "Composites",
+            AssetKind::Prefab => "Prefabs",
+            AssetKind::Scene => "Scenes",
+        }
+    }
+
+    /// The keys of this kind recorded in a manifest entry.
+    pub fn manifest_keys(self, entry: &mut SourceManifestEntry) -> &mut Vec<String> {
+        match self {
+            AssetKind::Model => &mut entry.models,
+            AssetKind::AnimatedModel => &mut entry.animated_models,
+            AssetKind::Animation => &mut entry.animations,
+            AssetKind::Texture => &mut entry.textures,
+            AssetKind::Material => &mut entry.materials,
+            AssetKind::Composite => &mut entry.composites,
+            AssetKind::Prefab => &mut entry.prefabs,
+            AssetKind::Scene => &mut entry.scenes,
+        }
+    }
+}
+
+/// Handle to a redb asset database file.
+pub struct AssetStore {
+    db: Database,
+}
+
+impl AssetStore {
+    /// Opens `path`, creating an empty database if it does not exist.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, DatabaseError> {
+        Ok(Self { db: Database::create(path)? })
+    }
+
+    /// Opens an existing database.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, DatabaseError> {
+        Ok(Self { db: Database::open(path)? })
+    }
+
+    /// Opens an existing database and fails unless it was written with the current
+    /// `FORMAT_VERSION`, so stale data is reported instead of failing to decode entry by entry.
+    pub fn open_current<P: AsRef<Path>>(path: P) -> Result<Self, DatabaseError> {
+        let store = Self::open(path)?;
+        let found = store.format_version()?;
+        if found != FORMAT_VERSION {
+            return Err(DatabaseError::VersionMismatch { found, expected: FORMAT_VERSION });
+        }
+        Ok(store)
+    }
+
+    pub fn meta(&self) -> Result<Option<DatabaseMeta>, DatabaseError> {
+        self.get(DatabaseMeta::KEY)
+    }
+
+    /// The stored format version, or 0 for databases that predate the meta table.
+    pub fn format_version(&self) -> Result<u32, DatabaseError> {
+        Ok(self.meta()?.map_or(0, |meta| meta.format_version))
+    }
+
+    /// Starts a consistent read-only view of every table.
+    pub fn read(&self) -> Result<AssetReader, DatabaseError> {
+        Ok(AssetReader { txn: self.db.begin_read()? })
+    }
+
+    /// Starts a write transaction; nothing is visible to readers until [`AssetWriter::commit`].
+    pub fn write(&self) -> Result<AssetWriter, DatabaseError> {
+        Ok(AssetWriter { txn: self.db.begin_write()? })
+    }
+
+    pub fn get<T: Record>(&self, key: &str) -> Result<Option<T>, DatabaseError> {
+        self.read()?.get(key)
+    }
+
+    pub fn put<T: Record>(&self, key: &str, value: &T) -> Result<(), DatabaseError> {
+        let writer = self.write()?;
+        writer.put(key, value)?;
+        writer.commit()
+    }
+
+    /// Removes `key` from the table of `kind` and from the manifest entry that produced it,
+    /// so the manifest keeps describing what is actually stored. That entry's content hash is
+    /// forgotten too, so the next bake imports the source again and restores the key.
+    pub fn remove(&self, kind: AssetKind, key: &str) -> Result<bool, DatabaseError> {
+        let writer = self.write()?;
+        let removed = match kind {
+            AssetKind::Texture => writer.remove_texture(key)?,
+            _ => writer.remove_raw(kind.table(), key)?,
+        };
+
+        let mut rewritten = Vec::new();
+        for result in writer.iter::<SourceManifestEntry>()? {
+            let (source, mut entry) = result?;
+            let keys = kind.manifest_keys(&mut entry);
+            let before = keys.len();
+            keys.retain(|k| k != key);
+            if keys.len() != before {
+                entry.content_hash = [0; 32];
+                rewritten.push((source, entry));
+            }
+        }
+        for (source, entry) in rewritten {
+            writer.put(&source, &entry)?;
+        }
+
+        writer.commit()?;
+        Ok(removed)
+    }
+}
+
+/// A read transaction over the asset tables. Tables that were never created read as empty.
+pub struct AssetReader {
+    txn: ReadTransaction,
+}
+
+impl AssetReader {
+    fn open(&self, table: Table) -> Result<Option<ReadTable>, DatabaseError> {
+        match self.txn.open_table(table) {
+            Ok(table) => Ok(Some(table)),
+            Err(redb::TableError::TableDoesNotExist(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn get<T: Record>(&self, key: &str) -> Result<Option<T>, DatabaseError> {
+        match self.get_raw(T::TABLE, key)? {
+            Some(bytes) => T::decode(key, &bytes).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_raw(&self, table: Table, key: &str) -> Result<Option<Vec<u8>>, DatabaseError> {
+        let Some(table) = self.open(table)? else {
+            return Ok(None);
+        };
+        Ok(table.get(key)?.map(|value| value.value().to_vec()))
+    }
+
+    /// Decodes every entry of `T`'s table. Each item fails independently, so one entry that
+    /// no longer matches its type does not hide the rest.
+    pub fn iter<T: Record>(
+        &self,
+    ) -> Result<impl Iterator<Item = Entry<T>> + use<T>, DatabaseError> {
+        let range = match self.open(T::TABLE)? {
+            Some(table) => Some(table.range::<&str>(..)?),
+            None => None,
+        };
+        Ok(range.into_iter().flatten().map(|result| {
+            let (key, value) = result?;
+            let key = key.value().to_string();
+            let value = T::decode(&key, value.value())?;
+            Ok((key, value))
+        }))
+    }
+
+    /// Every key of a table together with the size of its stored value.
+    pub fn entries(&self, table: Table) -> Result<Vec<(String, usize)>, DatabaseError> {
+        let Some(table) = self.open(table)? else {
+            return Ok(Vec::new());
+        };
+        let mut entries = Vec::new();
+        for result in table.iter()? {
+            let (key, value) = result?;
+            entries.push((key.value().to_string(), value.value().len()));
+        }
+        Ok(entries)
+    }
+
+    /// The KTX2 bytes of the texture named `name`; see [`crate::TextureData::from_ktx2`].
+    pub fn get_texture(&self, name: &str) -> Result<Option<Vec<u8>>, DatabaseError> {
+        match self.get::<TextureRef>(name)? {
+            Some(texture) => self.get_raw(TEXTURE_BLOB_TABLE, &texture.blob),
+            None => Ok(None),
+        }
+    }
+
+    /// Every unique texture as `(blob key, KTX2 bytes)`. Names map onto blob keys through
+    /// the `TextureRef` entries returned by `iter::<TextureRef>()`.
+    pub fn texture_blobs(
+        &self,
+    ) -> Result<impl Iterator<Item = Entry<Vec<u8>>> + use<>, DatabaseError> {
+        let range = match self.open(TEXTURE_BLOB_TABLE)? {
+            Some(table) => Some(table.range::<&str>(..)?),
+            None => None,
+        };
+        Ok(range.into_iter().flatten().map(|result| {
+            let (key, value) = result?;
+            Ok((key.value().to_string(), value.value().to_vec()))
+        }))
+    }
+}
+
+/// A write transaction over the asset tables.
+pub struct AssetWriter {
+    txn: WriteTransaction,
+}
+
+impl AssetWriter {
+    pub fn get<T: Record>(&self, key: &str) -> Result<Option<T>, DatabaseError> {
+        let table = self.txn.open_table(T::TABLE)?;
+        let value = table.get(key)?;
+        value.map(|value| T::decode(key, value.value())).transpose()
+    }
+
+    pub fn put<T: Record>(&self, key: &str, value: &T) -> Result<(), DatabaseError> {
+        let bytes = value.encode(key)?;
+        self.put_raw(T::TABLE, key, &bytes)
+    }
+
+    pub fn put_raw(&self, table: Table, key: &str, bytes: &[u8]) -> Result<(), DatabaseError> {
+        self.txn.open_table(table)?.insert(key, bytes)?;
+        Ok(())
+    }
+
+    pub fn remove<T: Record>(&self, key: &str) -> Result<bool, DatabaseError> {
+        self.remove_raw(T::TABLE, key)
+    }
+
+    pub fn remove_raw(&self, table: Table, key: &str) -> Result<bool, DatabaseError> {
+        Ok(self.txn.open_table(table)?.remove(key)?.is_some())
+    }
+
+    /// Drops every entry of a table.
+    pub fn clear(&self, table: Table) -> Result<(), DatabaseError> {
+        self.txn.delete_table(table)?;
+        Ok(())
+    }
+
+    /// Decodes every entry of `T`'s table as it stands inside this transaction.
+    pub fn iter<T: Record>(&self) -> Result<Vec<Entry<T>>, DatabaseError> {
+        let table = self.txn.open_table(T::TABLE)?;
+        let mut items = Vec::new();
+        for result in table.iter()? {
+            let (key, value) = result?;
+            let key = key.value().to_string();
+            items.push(T::decode(&key, value.value()).map(|value| (key, value)));
+        }
+        Ok(items)
+    }
+
+    /// Every raw entry of a table as it stands inside this transaction.
+    pub fn entries_raw(&self, table: Table) -> Result<Vec<(String, Vec<u8>)>, DatabaseError> {
+        let table = self.txn.open_table(table)?;
+        let mut items = Vec::new();
+        for result in table.iter()? {
+            let (key, value) = result?;
+            items.push((key.value().to_string(), value.value().to_vec()));
+        }
+        Ok(items)
+    }
+
+    /// Stores KTX2 `bytes` under `name`. The bytes go into the blob table keyed by their hash,
+    /// so storing the same image under several names keeps a single copy.
+    pub fn put_texture(&self, name: &str, bytes: &[u8]) -> Result<(), DatabaseError> {
+        let blob = blake3::hash(bytes).to_hex().to_string();
+        if self.txn.open_table(TEXTURE_BLOB_TABLE)?.get(blob.as_str())?.is_none() {
+            self.put_raw(TEXTURE_BLOB_TABLE, &blob, bytes)?;
+        }
+        let previous = self.get::<TextureRef>(name)?;
+        self.put(name, &TextureRef { blob: blob.clone() })?;
+        if let Some(previous) = previous.filter(|p| p.blob != blob) {
+            self.release_blob(&previous.blob)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the name `name`, and its blob once no other name refers to it.
+    pub fn remove_texture(&self, name: &str) -> Result<bool, DatabaseError> {
+        let Some(texture) = self.get::<TextureRef>(name)? else {
+            return Ok(false);
+        };
+        self.remove::<TextureRef>(name)?;
+        self.release_blob(&texture.blob)?;
+        Ok(true)
+    }
+
+    fn release_blob(&self, blob: &str) -> Result<(), DatabaseError> {
+        let referenced = self
+            .iter::<TextureRef>()?
+            .into_iter()
+            .any(|entry| entry.is_ok_and(|(_, texture)| texture.blob == blob));
+        if !referenced {
+            self.remove_raw(TEXTURE_BLOB_TABLE, blob)?;
+        }
+        Ok(())
+    }
+
+    pub fn commit(self) -> Result<(), DatabaseError> {
+        self.txn.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+// database/src/tangents.rs - MikkTSpace tangents for meshes whose source has none
+
+use std::collections::HashMap;
+
+use bevy_mikktspace::Geometry;
+use glam::Vec4;
+use types::{AnimatedMesh, Mesh, SkinnedVertex, Vertex};
+
+/// The attributes MikkTSpace reads from a vertex and the tangent it writes back.
+trait TangentVertex: Copy {
+    fn position(&self) -> [f32; 3];
+    fn normal(&self) -> [f32; 3];
+    fn uv(&self) -> [f32; 2];
+    fn tangent(&mut self) -> &mut Vec4;
+}
+
+impl TangentVertex for Vertex {
+    fn position(&self) -> [f32; 3] {
+        self.position.truncate().to_array()
+    }
+    fn normal(&self) -> [f32; 3] {
+        self.normal.truncate().to_array()
+    }
+    fn uv(&self) -> [f32; 2] {
+        self.uv.to_array()
+    }
+    fn tangent(&mut self) -> &mut Vec4 {
+        &mut self.tangent
+    }
+}
+
+impl TangentVertex for SkinnedVertex {
+    fn position(&self) -> [f32; 3] {
+        self.position.truncate().to_array()
+    }
+    fn normal(&self) -> [f32; 3] {
+        self.normal.truncate().to_array()
+    }
+    fn uv(&self) -> [f32; 2] {
+        self.uv.to_array()
+    }
+    fn tangent(&mut self) -> &mut Vec4 {
+        &mut self.tangent
+    }
+}
+
+/// An indexed triangle list as MikkTSpace sees it, collecting one tangent per corner.
+struct Corners<'a, V> {
+    vertices: &'a [V],
+    indices: &'a [u32],
+    tangents: Vec<[f32; 4]>,
+}
+
+impl<V: TangentVertex> Corners<'_, V> {
+    fn vertex(&self, face: usize, vert: usize) -> &V {
+        &self.vertices[self.indices[face * 3 + vert] as usize]
+    }
+}
+
+impl<V: TangentVertex> Geometry for Corners<'_, V> {
+    fn num_faces(&self) -> usize {
+        self.indices.len() / 3
+    }
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).position()
+    }
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).normal()
+    }
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.vertex(face, vert).uv()
+    }
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.tangents[face * 3 + vert] = tangent;
+    }
+}
+
+/// Fills in the tangents of the vertices the source gave none, keeping the authored ones.
+pub fn generate_tangents(mesh: &mut Mesh) {
+    if let Some(split) = generate(&mut mesh.vertices, &mut mesh.indices, &mesh.name) {
+        log_generated(&mesh.name, split.len());
+    }
+}
+
+/// Split vertices copy the morph target deltas of the vertex they were split from.
+pub fn generate_skinned_tangents(mesh: &mut AnimatedMesh) {
+    let vertex_count = mesh.vertices.len() as u32;
+    if let Some(split) = generate(&mut mesh.vertices, &mut mesh.indices, &mesh.name) {
+        if !split.is_empty() {
+            let origins: Vec<u32> = (0..vertex_count).chain(split.iter().copied()).collect();
+            for target in &mut mesh.morph_targets {
+                target.remap(&origins);
+            }
+        }
+        log_generated(&mesh.name, split.len());
+    }
+}
+
+/// Runs MikkTSpace over the mesh and returns, for each vertex that had to be split because its
+/// corners were given different tangents, the vertex it was split from, or `None` when nothing
+/// was generated. Split vertices are appended in that order. Vertices with an authored tangent,
+/// which has a non-zero bitangent sign, keep it; a mesh merged from several primitives may have
+/// them for only some of its vertices.
+fn generate<V: TangentVertex>(vertices: &mut Vec<V>, indices: &mut [u32], name: &str) -> Option<Vec<u32>> {
+    let authored: Vec<bool> = vertices.iter_mut().map(|v| v.tangent().w != 0.0).collect();
+    if authored.iter().all(|&a| a) {
+        return None;
+    }
+
+    let mut corners = Corners {
+        vertices: vertices.as_slice(),
+        indices: &*indices,
+        tangents: vec![[0.0; 4]; indices.len()],
+    };
+    if !bevy_mikktspace::generate_tangents(&mut corners) {
+        log::warn!("[DB]    - Could not generate tangents for '{name}'");
+        return None;
+    }
+    let tangents = corners.tangents;
+
+    // Corners sharing a vertex normally agree; where they don't, the vertex is duplicated.
+    let mut assigned: Vec<Option<[u32; 4]>> = vec![None; vertices.len()];
+    let mut splits: HashMap<(u32, [u32; 4]), u32> = HashMap::new();
+    let mut split_from = Vec::new();
+    for (index, tangent) in indices.iter_mut().zip(tangents) {
+        if authored[*index as usize] {
+            continue;
+        }
+        let bits = tangent.map(f32::to_bits);
+        match assigned[*index as usize] {
+            None => {
+                assigned[*index as usize] = Some(bits);
+                *vertices[*index as usize].tangent() = Vec4::from(tangent);
+            }
+            Some(existing) if existing == bits => {}
+            Some(_) => {
+                *index = *splits.entry((*index, bits)).or_insert_with(|| {
+                    let mut vertex = vertices[*index as usize];
+                    *vertex.tangent() = Vec4::from(tangent);
+                    vertices.push(vertex);
+                    split_from.push(*index);
+                    (vertices.len() - 1) as u32
+                });
+            }
+        }
+    }
+    Some(split_from)
+}
+
+fn log_generated(name: &str, split: usize) {
+    log::info!("[DB]    - Generated tangents for '{name}' ({split} vertices split)");
+}
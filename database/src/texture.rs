@@ -0,0 +1,154 @@
+// database/src/texture.rs - KTX2 container for the mip chains stored in TEXTURE_TABLE
+
+use std::num::NonZeroU8;
+
+use ktx2::{
+    ChannelTypeQualifiers, ColorModel, ColorPrimaries, DataFormatFlags, DfdBlockHeaderBasic,
+    DfdHeader, Format, Header, Index, LevelIndex, SampleInformation, TransferFunction,
+};
+
+use crate::DatabaseError;
+
+const BYTES_PER_TEXEL: usize = 4;
+
+/// An RGBA8 texture with its full mip chain, as stored in `TEXTURE_TABLE`. Level 0 is the
+/// full-size image and each following level halves both dimensions, down to 1x1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureData {
+    pub width: u32,
+    pub height: u32,
+    /// Whether the texels are sRGB-encoded color; data textures such as normal maps are not.
+    pub srgb: bool,
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl TextureData {
+    /// The size of mip `level`, never smaller than 1x1.
+    pub fn level_size(&self, level: usize) -> (u32, u32) {
+        ((self.width >> level).max(1), (self.height >> level).max(1))
+    }
+
+    /// Serializes the chain as an uncompressed KTX2 file. Level data is written smallest
+    /// first, as the specification requires.
+    pub fn to_ktx2(&self) -> Vec<u8> {
+        let dfd = basic_dfd(self.srgb);
+        let level_index_length = self.levels.len() * LevelIndex::LENGTH;
+        let dfd_offset = Header::LENGTH + level_index_length;
+        let mut offset = (dfd_offset + dfd.len()) as u64;
+
+        let mut index = vec![None; self.levels.len()];
+        for (level, data) in self.levels.iter().enumerate().rev() {
+            index[level] = Some(LevelIndex {
+                byte_offset: offset,
+                byte_length: data.len() as u64,
+                uncompressed_byte_length: data.len() as u64,
+            });
+            offset += data.len() as u64;
+        }
+
+        let header = Header {
+            format: Some(if self.srgb { Format::R8G8B8A8_SRGB } else { Format::R8G8B8A8_UNORM }),
+            type_size: 1,
+            pixel_width: self.width,
+            pixel_height: self.height,
+            pixel_depth: 0,
+            layer_count: 0,
+            face_count: 1,
+            level_count: self.levels.len() as u32,
+            supercompression_scheme: None,
+            index: Index {
+                dfd_byte_offset: dfd_offset as u32,
+                dfd_byte_length: dfd.len() as u32,
+                kvd_byte_offset: 0,
+                kvd_byte_length: 0,
+                sgd_byte_offset: 0,
+                sgd_byte_length: 0,
+            },
+        };
+
+        let mut bytes = Vec::with_capacity(offset as usize);
+        bytes.extend_from_slice(&header.as_bytes());
+        for entry in index.into_iter().flatten() {
+            bytes.extend_from_slice(&entry.as_bytes());
+        }
+        bytes.extend_from_slice(&dfd);
+        for data in self.levels.iter().rev() {
+            bytes.extend_from_slice(data);
+        }
+        bytes
+    }
+
+    /// Reads a container written by [`TextureData::to_ktx2`]. Only uncompressed RGBA8 2D
+    /// textures are accepted.
+    pub fn from_ktx2(key: &str, bytes: &[u8]) -> Result<Self, DatabaseError> {
+        let invalid = |reason: String| DatabaseError::Texture { key: key.to_string(), reason };
+
+        let reader = ktx2::Reader::new(bytes).map_err(|e| invalid(e.to_string()))?;
+        let header = reader.header();
+        let srgb = match header.format {
+            Some(Format::R8G8B8A8_SRGB) => true,
+            Some(Format::R8G8B8A8_UNORM) => false,
+            other => return Err(invalid(format!("unsupported format {other:?}"))),
+        };
+        if header.supercompression_scheme.is_some() || header.layer_count > 1 || header.face_count != 1 {
+            return Err(invalid("only uncompressed single-layer 2D textures are supported".into()));
+        }
+
+        let mut texture = TextureData {
+            width: header.pixel_width,
+            height: header.pixel_height,
+            srgb,
+            levels: Vec::new(),
+        };
+        for (level, data) in reader.levels().enumerate() {
+            let (width, height) = texture.level_size(level);
+            let expected = width as usize * height as usize * BYTES_PER_TEXEL;
+            if data.data.len() != expected {
+                return Err(invalid(format!(
+                    "level {level} holds {} bytes, expected {expected} for {width}x{height}",
+                    data.data.len()
+                )));
+            }
+            texture.levels.push(data.data.to_vec());
+        }
+        Ok(texture)
+    }
+}
+
+/// The data format descriptor of an RGBA8 texture: one basic block with a sample per channel.
+fn basic_dfd(srgb: bool) -> Vec<u8> {
+    let block_header = DfdBlockHeaderBasic {
+        color_model: Some(ColorModel::RGBSDA),
+        color_primaries: Some(ColorPrimaries::BT709),
+        transfer_function: Some(if srgb { TransferFunction::SRGB } else { TransferFunction::Linear }),
+        flags: DataFormatFlags::STRAIGHT_ALPHA,
+        texel_block_dimensions: [NonZeroU8::MIN; 4],
+        bytes_planes: [BYTES_PER_TEXEL as u8, 0, 0, 0, 0, 0, 0, 0],
+    };
+
+    let mut block = block_header.as_bytes().to_vec();
+    // KHR_DF_CHANNEL_RGBSDA_{RED, GREEN, BLUE, ALPHA}; alpha is never sRGB-encoded.
+    for (channel, channel_type) in [0u8, 1, 2, 15].into_iter().enumerate() {
+        let qualifiers = if channel_type == 15 && srgb {
+            ChannelTypeQualifiers::LINEAR
+        } else {
+            ChannelTypeQualifiers::empty()
+        };
+        let sample = SampleInformation {
+            bit_offset: channel as u16 * 8,
+            bit_length: NonZeroU8::new(8).unwrap(),
+            channel_type,
+            channel_type_qualifiers: qualifiers,
+            sample_positions: [0; 4],
+            lower: 0,
+            upper: 255,
+        };
+        block.extend_from_slice(&sample.as_bytes());
+    }
+
+    let block_size = (DfdHeader::LENGTH + block.len()) as u16;
+    let mut dfd = ((4 + block_size as u32).to_le_bytes()).to_vec();
+    dfd.extend_from_slice(&DfdHeader::BASIC.as_bytes(block_size));
+    dfd.extend_from_slice(&block);
+    dfd
+}
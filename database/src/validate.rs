@@ -0,0 +1,237 @@
+// database/src/validate.rs - Checks of imported meshes and skeletons, reported per asset
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use glam::{Vec2, Vec3};
+use serde::Serialize;
+use types::{AnimatedModel, Model, Skeleton};
+
+/// How far the bone weights of a vertex may sum away from one.
+const WEIGHT_SUM_TOLERANCE: f32 = 1e-3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Bakes, but probably does not look as authored.
+    Warning,
+    /// Cannot render correctly; fails the bake in strict mode.
+    Error,
+}
+
+/// One kind of problem found in an asset, with how often it occurs.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Issue {
+    /// Triangles with a repeated vertex or no area.
+    DegenerateTriangles { count: usize },
+    IndicesOutOfRange { count: usize },
+    NonFinitePositions { count: usize },
+    ZeroLengthNormals { count: usize },
+    /// The mesh is textured but every vertex has the same UV, which is what the importers
+    /// substitute when a source has none.
+    MissingUvs,
+    /// Vertices whose bone weights do not sum to one; `worst_sum` is the furthest off.
+    UnnormalizedWeights { count: usize, worst_sum: f32 },
+    /// Weighted joints that are not bones of the skeleton.
+    JointsOutOfRange { count: usize, max_joint: u32, bone_count: usize },
+    /// Bones without a parent besides the first root, which the hierarchy treats as separate
+    /// skeletons.
+    UnparentedBones { bones: Vec<String> },
+}
+
+impl Issue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Issue::IndicesOutOfRange { .. }
+            | Issue::NonFinitePositions { .. }
+            | Issue::JointsOutOfRange { .. } => Severity::Error,
+            Issue::DegenerateTriangles { .. }
+            | Issue::ZeroLengthNormals { .. }
+            | Issue::MissingUvs
+            | Issue::UnnormalizedWeights { .. }
+            | Issue::UnparentedBones { .. } => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::DegenerateTriangles { count } => write!(f, "{count} degenerate triangles"),
+            Issue::IndicesOutOfRange { count } => write!(f, "{count} indices past the vertex list"),
+            Issue::NonFinitePositions { count } => write!(f, "{count} NaN or infinite positions"),
+            Issue::ZeroLengthNormals { count } => write!(f, "{count} zero-length normals"),
+            Issue::MissingUvs => write!(f, "textured without UVs"),
+            Issue::UnnormalizedWeights { count, worst_sum } => {
+                write!(f, "{count} vertices whose bone weights do not sum to 1 (worst {worst_sum})")
+            }
+            Issue::JointsOutOfRange { count, max_joint, bone_count } => {
+                write!(f, "{count} weighted joints past the {bone_count} bones of the skeleton (up to {max_joint})")
+            }
+            Issue::UnparentedBones { bones } => write!(f, "unparented bones: {}", bones.join(", ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    /// The model, or `model/mesh` for a problem in one mesh.
+    pub asset: String,
+    pub severity: Severity,
+    #[serde(flatten)]
+    pub issue: Issue,
+}
+
+impl Finding {
+    pub fn new(asset: String, issue: Issue) -> Self {
+        Self { asset, severity: issue.severity(), issue }
+    }
+}
+
+/// Findings by source key, for the sources a bake imported.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    pub sources: BTreeMap<String, Vec<Finding>>,
+}
+
+impl ValidationReport {
+    /// Records what validation found in `source_key` and, in strict mode, returns why the
+    /// source fails.
+    pub fn add(&mut self, source_key: &str, findings: Vec<Finding>, strict: bool) -> Option<String> {
+        let errors = findings.iter().filter(|f| f.severity == Severity::Error).count();
+        if !findings.is_empty() {
+            self.sources.insert(source_key.to_string(), findings);
+        }
+        (strict && errors > 0).then(|| format!("{errors} validation errors"))
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.sources.values().flatten().filter(|f| f.severity == severity).count()
+    }
+
+    pub fn to_json(&self) -> String {
+        let report = serde_json::json!({
+            "errors": self.count(Severity::Error),
+            "warnings": self.count(Severity::Warning),
+            "sources": self.sources,
+        });
+        serde_json::to_string_pretty(&report).unwrap_or_default()
+    }
+}
+
+pub fn validate_model(model: &Model) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for mesh in &model.meshes {
+        let asset = format!("{}/{}", model.name, mesh.name);
+        let positions: Vec<Vec3> = mesh.vertices.iter().map(|v| v.position.truncate()).collect();
+        let normals: Vec<Vec3> = mesh.vertices.iter().map(|v| v.normal.truncate()).collect();
+        let uvs: Vec<Vec2> = mesh.vertices.iter().map(|v| v.uv).collect();
+        let textured = mesh.texture_name.is_some();
+        check_geometry(&asset, &positions, &normals, &uvs, &mesh.indices, textured, &mut findings);
+    }
+    findings
+}
+
+pub fn validate_animated_model(model: &AnimatedModel) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    check_skeleton(&model.name, &model.skeleton, &mut findings);
+    let bone_count = model.skeleton.bones.len();
+
+    for mesh in &model.meshes {
+        let asset = format!("{}/{}", model.name, mesh.name);
+        let positions: Vec<Vec3> = mesh.vertices.iter().map(|v| v.position.truncate()).collect();
+        let normals: Vec<Vec3> = mesh.vertices.iter().map(|v| v.normal.truncate()).collect();
+        let uvs: Vec<Vec2> = mesh.vertices.iter().map(|v| v.uv).collect();
+        let textured = mesh.texture_name.is_some();
+        check_geometry(&asset, &positions, &normals, &uvs, &mesh.indices, textured, &mut findings);
+
+        let mut unnormalized = (0, 1.0f32);
+        let mut out_of_range = (0, 0);
+        for vertex in &mesh.vertices {
+            let sum: f32 = vertex.bone_weights.iter().sum();
+            if (sum - 1.0).abs() > WEIGHT_SUM_TOLERANCE {
+                unnormalized.0 += 1;
+                if (sum - 1.0).abs() > (unnormalized.1 - 1.0).abs() {
+                    unnormalized.1 = sum;
+                }
+            }
+            // Unweighted slots often hold joint 0 or junk, and are never read.
+            for (&joint, _) in vertex.bone_indices.iter().zip(&vertex.bone_weights).filter(|(_, w)| **w > 0.0) {
+                if joint as usize >= bone_count {
+                    out_of_range = (out_of_range.0 + 1, out_of_range.1.max(joint));
+                }
+            }
+        }
+        if unnormalized.0 > 0 {
+            let issue = Issue::UnnormalizedWeights { count: unnormalized.0, worst_sum: unnormalized.1 };
+            findings.push(Finding::new(asset.clone(), issue));
+        }
+        if out_of_range.0 > 0 {
+            let issue = Issue::JointsOutOfRange { count: out_of_range.0, max_joint: out_of_range.1, bone_count };
+            findings.push(Finding::new(asset, issue));
+        }
+    }
+    findings
+}
+
+fn check_geometry(
+    asset: &str,
+    positions: &[Vec3],
+    normals: &[Vec3],
+    uvs: &[Vec2],
+    indices: &[u32],
+    textured: bool,
+    findings: &mut Vec<Finding>,
+) {
+    let mut issues = Vec::new();
+
+    let out_of_range = indices.iter().filter(|&&i| i as usize >= positions.len()).count();
+    if out_of_range > 0 {
+        issues.push(Issue::IndicesOutOfRange { count: out_of_range });
+    }
+
+    let degenerate = indices
+        .chunks_exact(3)
+        .filter(|t| t.iter().all(|&i| (i as usize) < positions.len()))
+        .filter(|t| {
+            let [a, b, c] = [t[0], t[1], t[2]].map(|i| positions[i as usize]);
+            let (ab, ac) = (b - a, c - a);
+            // Relative to the edges, so the test does not depend on the model's scale.
+            t[0] == t[1] || t[1] == t[2] || t[0] == t[2]
+                || ab.cross(ac).length_squared() <= f32::EPSILON * ab.length_squared() * ac.length_squared()
+        })
+        .count();
+    if degenerate > 0 {
+        issues.push(Issue::DegenerateTriangles { count: degenerate });
+    }
+
+    let non_finite = positions.iter().filter(|p| !p.is_finite()).count();
+    if non_finite > 0 {
+        issues.push(Issue::NonFinitePositions { count: non_finite });
+    }
+
+    let zero_normals = normals.iter().filter(|n| n.length_squared() < 1e-12).count();
+    if zero_normals > 0 {
+        issues.push(Issue::ZeroLengthNormals { count: zero_normals });
+    }
+
+    if textured && uvs.len() > 1 && uvs.iter().all(|&uv| uv == uvs[0]) {
+        issues.push(Issue::MissingUvs);
+    }
+
+    findings.extend(issues.into_iter().map(|issue| Finding::new(asset.to_string(), issue)));
+}
+
+fn check_skeleton(asset: &str, skeleton: &Skeleton, findings: &mut Vec<Finding>) {
+    let unparented: Vec<String> = skeleton
+        .bones
+        .iter()
+        .filter(|bone| bone.parent_index.is_none())
+        .skip(1)
+        .map(|bone| bone.name.clone())
+        .collect();
+    if !unparented.is_empty() {
+        findings.push(Finding::new(asset.to_string(), Issue::UnparentedBones { bones: unparented }));
+    }
+}
@@ -0,0 +1,95 @@
+//! Evaluates step, linear and cubic spline tracks the way the animation system plays them.
+
+use glam::{Quat, Vec3, Vec4};
+use types::{
+    Interpolation, MorphKey, PositionKey, RotationKey, ScaleKey, hermite, sample_position, sample_rotation,
+    sample_scale, sample_weights,
+};
+
+fn position_key(time: f64, x: f32, interpolation: Interpolation) -> PositionKey {
+    PositionKey { time, position: Vec3::new(x, 0.0, 0.0), interpolation, ..Default::default() }
+}
+
+#[test]
+fn step_keys_hold_until_the_next_key() {
+    let keys = [position_key(0.0, 1.0, Interpolation::Step), position_key(10.0, 5.0, Interpolation::Step)];
+    assert_eq!(sample_position(5.0, &keys), Some(Vec3::X));
+    assert_eq!(sample_position(9.999, &keys), Some(Vec3::X));
+    assert_eq!(sample_position(10.0, &keys), Some(Vec3::X * 5.0));
+
+    // Outside the keys the nearest one is held, whatever the mode.
+    assert_eq!(sample_position(-1.0, &keys), Some(Vec3::X));
+    assert_eq!(sample_position(20.0, &keys), Some(Vec3::X * 5.0));
+
+    let scales = [
+        ScaleKey { time: 0.0, scale: Vec3::ONE, interpolation: Interpolation::Step, ..Default::default() },
+        ScaleKey { time: 2.0, scale: Vec3::splat(3.0), ..Default::default() },
+    ];
+    assert_eq!(sample_scale(1.5, &scales), Some(Vec3::ONE));
+}
+
+#[test]
+fn linear_keys_interpolate_and_empty_tracks_have_no_value() {
+    let keys = [position_key(0.0, 1.0, Interpolation::Linear), position_key(4.0, 5.0, Interpolation::Linear)];
+    assert_eq!(sample_position(1.0, &keys), Some(Vec3::X * 2.0));
+    assert_eq!(sample_position(1.0, &[]), None);
+}
+
+#[test]
+fn hermite_matches_the_basis_functions() {
+    // Endpoints are hit exactly, and with flat tangents the midpoint is halfway.
+    assert_eq!(hermite(2.0, 7.0, 4.0, -3.0, 0.0), 2.0);
+    assert_eq!(hermite(2.0, 7.0, 4.0, -3.0, 1.0), 4.0);
+    assert_eq!(hermite(0.0, 0.0, 1.0, 0.0, 0.5), 0.5);
+    // Each tangent alone contributes its basis function: t³ - 2t² + t and t³ - t².
+    assert_eq!(hermite(0.0, 1.0, 0.0, 0.0, 0.5), 0.125);
+    assert_eq!(hermite(0.0, 0.0, 0.0, 1.0, 0.5), -0.125);
+    assert!((hermite(0.0, 1.0, 0.0, 0.0, 0.25) - 0.140625).abs() < 1e-6);
+}
+
+#[test]
+fn spline_tangents_are_scaled_by_the_segment_length() {
+    // Tangents are per tick, so a segment twice as long bends twice as far.
+    let keys = [
+        PositionKey { out_tangent: Vec3::X, ..position_key(0.0, 0.0, Interpolation::CubicSpline) },
+        position_key(2.0, 0.0, Interpolation::CubicSpline),
+    ];
+    let midpoint = sample_position(1.0, &keys).unwrap();
+    assert!((midpoint.x - 0.25).abs() < 1e-6, "{midpoint}");
+
+    let weights = [
+        MorphKey {
+            time: 0.0,
+            weights: vec![0.0],
+            interpolation: Interpolation::CubicSpline,
+            in_tangents: vec![0.0],
+            out_tangents: vec![1.0],
+        },
+        MorphKey {
+            time: 4.0,
+            weights: vec![0.0],
+            interpolation: Interpolation::CubicSpline,
+            in_tangents: vec![0.0],
+            out_tangents: vec![0.0],
+        },
+    ];
+    assert_eq!(sample_weights(2.0, &weights), Some(vec![0.5]));
+}
+
+#[test]
+fn spline_rotations_stay_unit_length() {
+    let half_turn = Quat::from_rotation_y(std::f32::consts::PI);
+    let keys = [
+        RotationKey {
+            time: 0.0,
+            rotation: Quat::IDENTITY,
+            interpolation: Interpolation::CubicSpline,
+            out_tangent: Vec4::new(0.0, 1.0, 0.0, 0.0),
+            ..Default::default()
+        },
+        RotationKey { time: 1.0, rotation: half_turn, interpolation: Interpolation::CubicSpline, ..Default::default() },
+    ];
+    let rotation = sample_rotation(0.5, &keys).unwrap();
+    assert!((rotation.length() - 1.0).abs() < 1e-5);
+    assert_eq!(sample_rotation(1.0, &keys), Some(half_turn));
+}
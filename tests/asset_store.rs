@@ -0,0 +1,278 @@
+//! Round-trips values through the typed `database` store API, and upgrades old databases.
+
+use database::{AssetKind, AssetStore, DatabaseError, TextureData};
+use glam::Vec4;
+use types::{AABB, Animation, DatabaseMeta, FORMAT_VERSION, Model, SourceManifestEntry};
+
+fn temp_store(name: &str) -> (AssetStore, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(format!("asset_store_{name}_{}.redb", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    (AssetStore::create(&path).unwrap(), path)
+}
+
+fn model(name: &str) -> Model {
+    Model {
+        name: name.to_string(),
+        meshes: Vec::new(),
+        aabb: AABB {
+            min: Vec4::splat(-1.0),
+            max: Vec4::splat(1.0),
+        },
+    }
+}
+
+#[test]
+fn put_get_iter_remove() {
+    let (store, path) = temp_store("roundtrip");
+
+    // Tables that were never written read as empty rather than failing.
+    assert!(store.get::<Model>("missing").unwrap().is_none());
+    assert_eq!(store.read().unwrap().iter::<Animation>().unwrap().count(), 0);
+
+    let writer = store.write().unwrap();
+    writer.put("a", &model("a")).unwrap();
+    writer.put("b", &model("b")).unwrap();
+    writer.put_texture("t.png", &[1, 2, 3]).unwrap();
+    writer.put(
+        "src/a.glb",
+        &SourceManifestEntry {
+            models: vec!["a".to_string()],
+            content_hash: [7; 32],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    writer.commit().unwrap();
+
+    let reader = store.read().unwrap();
+    let names: Vec<String> = reader.iter::<Model>().unwrap().map(|r| r.unwrap().1.name).collect();
+    assert_eq!(names, ["a", "b"]);
+    assert_eq!(reader.get_texture("t.png").unwrap().as_deref(), Some(&[1u8, 2, 3][..]));
+    drop(reader);
+
+    // Removing through the store also drops the key from the manifest entry that claimed it,
+    // and forgets that source's hash so the next bake brings the key back.
+    assert!(store.remove(AssetKind::Model, "a").unwrap());
+    assert!(!store.remove(AssetKind::Model, "a").unwrap());
+    assert!(store.get::<Model>("a").unwrap().is_none());
+    let entry = store.get::<SourceManifestEntry>("src/a.glb").unwrap().unwrap();
+    assert!(entry.models.is_empty());
+    assert_eq!(entry.content_hash, [0; 32]);
+
+    drop(store);
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn undecodable_entry_is_reported_per_key() {
+    let (store, path) = temp_store("decode");
+
+    let writer = store.write().unwrap();
+    writer.put("good", &model("good")).unwrap();
+    writer.put_raw(AssetKind::Model.table(), "bad", &[0xff]).unwrap();
+    writer.commit().unwrap();
+
+    let results: Vec<_> = store.read().unwrap().iter::<Model>().unwrap().collect();
+    assert_eq!(results.len(), 2);
+    assert!(matches!(&results[0], Err(DatabaseError::Decode { key, .. }) if key == "bad"));
+    assert_eq!(results[1].as_ref().unwrap().0, "good");
+
+    drop(store);
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn open_current_rejects_other_format_versions() {
+    let (store, path) = temp_store("version");
+    assert_eq!(store.format_version().unwrap(), 0);
+    drop(store);
+    assert!(matches!(
+        AssetStore::open_current(&path),
+        Err(DatabaseError::VersionMismatch { found: 0, expected: FORMAT_VERSION })
+    ));
+
+    let meta = DatabaseMeta {
+        format_version: FORMAT_VERSION,
+        baker_version: "test".to_string(),
+    };
+    AssetStore::open(&path).unwrap().put(DatabaseMeta::KEY, &meta).unwrap();
+    assert!(AssetStore::open_current(&path).is_ok());
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn texture_mip_chain_roundtrips_through_ktx2() {
+    let texture = TextureData {
+        width: 4,
+        height: 2,
+        srgb: true,
+        levels: vec![vec![10; 4 * 2 * 4], vec![20; 2 * 4], vec![30; 4]],
+    };
+    let bytes = texture.to_ktx2();
+    assert_eq!(TextureData::from_ktx2("t", &bytes).unwrap(), texture);
+
+    let linear = TextureData { srgb: false, ..texture.clone() };
+    assert!(!TextureData::from_ktx2("t", &linear.to_ktx2()).unwrap().srgb);
+
+    // A level whose size does not match the header is rejected rather than uploaded.
+    let truncated = TextureData { levels: vec![vec![0; 8]], ..texture };
+    assert!(matches!(
+        TextureData::from_ktx2("t", &truncated.to_ktx2()),
+        Err(DatabaseError::Texture { .. })
+    ));
+    assert!(TextureData::from_ktx2("t", b"not a texture").is_err());
+}
+
+#[test]
+fn identical_textures_share_one_blob() {
+    let (store, path) = temp_store("texture_blobs");
+    let blob_count = |store: &AssetStore| store.read().unwrap().texture_blobs().unwrap().count();
+
+    let writer = store.write().unwrap();
+    writer.put_texture("a.png", &[1, 2, 3]).unwrap();
+    writer.put_texture("b.png", &[1, 2, 3]).unwrap();
+    writer.put_texture("c.png", &[4, 5]).unwrap();
+    writer.commit().unwrap();
+    assert_eq!(blob_count(&store), 2);
+
+    // The blob stays while another name refers to it.
+    assert!(store.remove(AssetKind::Texture, "a.png").unwrap());
+    assert_eq!(store.read().unwrap().get_texture("b.png").unwrap().as_deref(), Some(&[1u8, 2, 3][..]));
+    assert_eq!(blob_count(&store), 2);
+
+    // Overwriting or removing the last name releases it.
+    let writer = store.write().unwrap();
+    writer.put_texture("b.png", &[4, 5]).unwrap();
+    writer.commit().unwrap();
+    assert_eq!(blob_count(&store), 1);
+    assert!(store.remove(AssetKind::Texture, "b.png").unwrap());
+    assert!(store.remove(AssetKind::Texture, "c.png").unwrap());
+    assert_eq!(blob_count(&store), 0);
+
+    drop(store);
+    let _ = std::fs::remove_file(path);
+}
+
+/// Migrations are part of the baker.
+#[cfg(feature = "bake")]
+mod migration {
+    use super::*;
+    use serde::Serialize;
+    use types::{ANIMATION_TABLE, MODEL_TABLE, SOURCE_MANIFEST_TABLE};
+
+    /// `SourceManifestEntry` as format version 1 stored it, before materials and bake settings.
+    #[derive(Serialize)]
+    struct ManifestEntryV1 {
+        content_hash: [u8; 32],
+        importer: String,
+        models: Vec<String>,
+        animated_models: Vec<String>,
+        animations: Vec<String>,
+        textures: Vec<String>,
+    }
+
+    /// `Animation` as format version 1 stored it, keyed by the bare clip name and with keys that
+    /// had no interpolation mode.
+    #[derive(Serialize)]
+    struct AnimationV1 {
+        name: String,
+        duration_in_ticks: f64,
+        ticks_per_second: f64,
+        channels: Vec<AnimationChannelV1>,
+    }
+
+    /// Keys were `(time, value)` pairs.
+    #[derive(Serialize)]
+    struct AnimationChannelV1 {
+        bone_name: String,
+        position_keys: Vec<(f64, [f32; 3])>,
+        rotation_keys: Vec<(f64, [f32; 4])>,
+        scale_keys: Vec<(f64, [f32; 3])>,
+    }
+
+    /// A database as version 1 of the baker left it: one source with a model, a clip and a texture.
+    fn version_1_store(name: &str) -> (AssetStore, std::path::PathBuf) {
+        let (store, path) = temp_store(name);
+        let entry = ManifestEntryV1 {
+            content_hash: [7; 32],
+            importer: "gltf".to_string(),
+            models: vec!["Fox".to_string()],
+            animated_models: Vec::new(),
+            animations: vec!["Run".to_string()],
+            textures: vec!["Fox.png".to_string()],
+        };
+        let clip = AnimationV1 {
+            name: "Run".to_string(),
+            duration_in_ticks: 1.0,
+            ticks_per_second: 24.0,
+            channels: vec![AnimationChannelV1 {
+                bone_name: "root".to_string(),
+                position_keys: vec![(0.0, [0.0; 3])],
+                rotation_keys: vec![(0.0, [0.0, 0.0, 0.0, 1.0])],
+                scale_keys: Vec::new(),
+            }],
+        };
+
+        let writer = store.write().unwrap();
+        writer
+            .put(DatabaseMeta::KEY, &DatabaseMeta { format_version: 1, baker_version: "0.1.0".to_string() })
+            .unwrap();
+        writer.put_raw(SOURCE_MANIFEST_TABLE, "Fox.gltf", &bincode::serialize(&entry).unwrap()).unwrap();
+        writer.put_raw(ANIMATION_TABLE, "Run", &bincode::serialize(&clip).unwrap()).unwrap();
+        writer.put("Fox", &model("Fox")).unwrap();
+        writer.put_texture("Fox.png", &[1, 2, 3]).unwrap();
+        writer.commit().unwrap();
+        (store, path)
+    }
+
+    #[test]
+    fn version_1_database_migrates_to_the_current_format() {
+        let (store, path) = version_1_store("migrate");
+
+        let applied = database::migrate(&store).unwrap();
+        assert_eq!(applied.len(), (FORMAT_VERSION - 1) as usize);
+        assert_eq!(store.format_version().unwrap(), FORMAT_VERSION);
+
+        // Every step past the first requires a rebake, so nothing stored survives and the next
+        // bake imports every source again.
+        let reader = store.read().unwrap();
+        for table in [SOURCE_MANIFEST_TABLE, ANIMATION_TABLE, MODEL_TABLE] {
+            assert!(reader.entries(table).unwrap().is_empty());
+        }
+        assert_eq!(reader.texture_blobs().unwrap().count(), 0);
+        drop(reader);
+
+        // A second run has nothing left to do.
+        assert!(database::migrate(&store).unwrap().is_empty());
+        drop(store);
+        assert!(AssetStore::open_current(&path).is_ok());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn missing_migration_step_leaves_the_database_untouched() {
+        let (store, path) = version_1_store("missing_step");
+
+        let without_step_3: Vec<database::Migration> = database::MIGRATIONS
+            .iter()
+            .filter(|m| m.from != 3)
+            .map(|m| database::Migration { from: m.from, description: m.description, apply: m.apply })
+            .collect();
+        assert!(matches!(
+            database::migrate_with(&store, &without_step_3),
+            Err(DatabaseError::NoMigration { from: 3 })
+        ));
+
+        assert_eq!(store.format_version().unwrap(), 1);
+        let reader = store.read().unwrap();
+        assert!(reader.get_raw(SOURCE_MANIFEST_TABLE, "Fox.gltf").unwrap().is_some());
+        assert!(reader.get_raw(ANIMATION_TABLE, "Run").unwrap().is_some());
+        assert_eq!(reader.get::<Model>("Fox").unwrap().unwrap().name, "Fox");
+        assert_eq!(reader.get_texture("Fox.png").unwrap().as_deref(), Some(&[1u8, 2, 3][..]));
+
+        drop(reader);
+        drop(store);
+        let _ = std::fs::remove_file(path);
+    }
+}
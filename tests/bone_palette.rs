@@ -0,0 +1,121 @@
+//! Splits a skinned mesh weighting more joints than the bone palette holds and checks the parts
+//! still describe the same triangles and bones.
+
+use database::palette::split_for_palette;
+use glam::{Mat4, Vec4};
+use types::{AABB, AnimatedMesh, AnimatedModel, BONE_PALETTE_SIZE, Bone, Skeleton, SkinnedVertex, VertexAttributes};
+
+const JOINTS: u32 = 700;
+
+/// Vertex `i` sits at `x = i`, so a part's vertex can be traced back to the input, and is
+/// weighted to two joints spread across the skeleton. Its third slot holds junk with no weight.
+fn vertex(i: u32) -> SkinnedVertex {
+    SkinnedVertex {
+        position: Vec4::new(i as f32, (i % 2) as f32, 0.0, 1.0),
+        normal: Vec4::Z,
+        bone_indices: [(i * 37) % JOINTS, (i * 101 + 13) % JOINTS, JOINTS + 5, 0],
+        bone_weights: [0.75, 0.25, 0.0, 0.0],
+        ..Default::default()
+    }
+}
+
+fn mesh(name: &str, vertices: Vec<SkinnedVertex>) -> AnimatedMesh {
+    // A strip, so neighbouring triangles share vertices.
+    let indices = (0..vertices.len() as u32 - 2).flat_map(|i| [i, i + 1, i + 2]).collect();
+    AnimatedMesh {
+        name: name.to_string(),
+        vertices,
+        quantized_vertices: None,
+        indices,
+        texture_name: Some("skin.png".to_string()),
+        material: None,
+        meshlets: None,
+        lods: Vec::new(),
+        aabb: AABB::default(),
+        attributes: VertexAttributes::default(),
+        morph_targets: Vec::new(),
+        morph_weights: Vec::new(),
+        node_name: None,
+        bone_palette: Vec::new(),
+    }
+}
+
+fn model() -> AnimatedModel {
+    let bones = (0..JOINTS)
+        .map(|i| Bone {
+            name: format!("bone_{i}"),
+            parent_index: i.checked_sub(1).map(|p| p as usize),
+            transform: Mat4::IDENTITY,
+            inverse_bind_pose: Mat4::IDENTITY,
+        })
+        .collect();
+    let small = (0..8)
+        .map(|i| SkinnedVertex { bone_indices: [i % 4, 0, 0, 0], bone_weights: [1.0, 0.0, 0.0, 0.0], ..vertex(i) })
+        .collect();
+    AnimatedModel {
+        name: "crowd".to_string(),
+        meshes: vec![mesh("body", (0..1200).map(vertex).collect()), mesh("hat", small)],
+        skeleton: Skeleton { bones },
+        aabb: AABB::default(),
+    }
+}
+
+/// The input vertex a part's vertex was copied from.
+fn origin(vertex: &SkinnedVertex) -> u32 {
+    vertex.position.x as u32
+}
+
+#[test]
+fn parts_fit_the_palette_and_reproduce_the_mesh() {
+    let input = model();
+    let mut model = model();
+    split_for_palette(&mut model).unwrap();
+
+    let (parts, kept): (Vec<_>, Vec<_>) = model.meshes.iter().partition(|m| m.name.starts_with("body-part-"));
+    assert!(parts.len() > 1);
+    // Meshes within the palette are left as they were.
+    assert_eq!(kept.len(), 1);
+    assert_eq!(kept[0].name, "hat");
+    assert!(kept[0].bone_palette.is_empty());
+    assert_eq!(kept[0].indices, input.meshes[1].indices);
+
+    let body = &input.meshes[0];
+    let mut triangles = Vec::new();
+    for part in &parts {
+        assert!(part.bone_palette.len() <= BONE_PALETTE_SIZE, "{} uses {} joints", part.name, part.bone_palette.len());
+        assert_eq!(part.texture_name, body.texture_name);
+        assert!(part.meshlets.is_some());
+
+        for vertex in &part.vertices {
+            let original = &body.vertices[origin(vertex) as usize];
+            for slot in 0..4 {
+                assert_eq!(vertex.bone_weights[slot], original.bone_weights[slot]);
+                if vertex.bone_weights[slot] > 0.0 {
+                    let local = vertex.bone_indices[slot] as usize;
+                    assert_eq!(part.bone_palette[local], original.bone_indices[slot]);
+                } else {
+                    // Junk in unweighted slots must not index past the palette.
+                    assert_eq!(vertex.bone_indices[slot], 0);
+                }
+            }
+        }
+        let traced = part.indices.chunks_exact(3).map(|t| t.iter().map(|&i| origin(&part.vertices[i as usize])));
+        triangles.extend(traced.map(Vec::from_iter));
+    }
+
+    // Each input triangle lands in exactly one part, with its winding.
+    let mut expected: Vec<Vec<u32>> = body.indices.chunks_exact(3).map(<[u32]>::to_vec).collect();
+    triangles.sort();
+    expected.sort();
+    assert_eq!(triangles, expected);
+}
+
+#[test]
+fn models_within_the_palette_are_untouched() {
+    let mut model = model();
+    model.meshes.remove(0);
+    split_for_palette(&mut model).unwrap();
+    assert_eq!(model.meshes.len(), 1);
+    assert_eq!(model.meshes[0].name, "hat");
+    assert!(model.meshes[0].meshlets.is_none());
+}
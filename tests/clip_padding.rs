@@ -0,0 +1,90 @@
+//! Pads bone tracks to the length of their clip without changing any authored key.
+
+use database::gltf_loader::validate_and_fix_animation_data;
+use glam::{Mat4, Quat, Vec3};
+use types::{Animation, AnimationChannel, Bone, Interpolation, PositionKey, RotationKey, Skeleton};
+
+fn skeleton() -> Skeleton {
+    Skeleton {
+        bones: vec![Bone {
+            name: "hinge".to_string(),
+            parent_index: None,
+            transform: Mat4::IDENTITY,
+            inverse_bind_pose: Mat4::IDENTITY,
+        }],
+    }
+}
+
+fn clip(channel: AnimationChannel) -> Animation {
+    Animation {
+        name: "Open".to_string(),
+        skeleton: "door".to_string(),
+        duration_in_ticks: 10.0,
+        ticks_per_second: 24.0,
+        channels: vec![channel],
+        morph_channels: Vec::new(),
+    }
+}
+
+fn channel(position_keys: Vec<PositionKey>, rotation_keys: Vec<RotationKey>) -> AnimationChannel {
+    AnimationChannel {
+        bone_name: "hinge".to_string(),
+        position_keys,
+        rotation_keys,
+        quantized_rotation_keys: None,
+        scale_keys: Vec::new(),
+    }
+}
+
+fn position(time: f64, x: f32) -> PositionKey {
+    PositionKey { time, position: Vec3::X * x, ..Default::default() }
+}
+
+#[test]
+fn a_key_at_the_end_keeps_its_value() {
+    // A door swinging open ends open, not back at its first pose.
+    let open = Quat::from_rotation_y(1.5);
+    let rotations = vec![
+        RotationKey { time: 0.0, rotation: Quat::IDENTITY, ..Default::default() },
+        RotationKey { time: 10.0, rotation: open, ..Default::default() },
+    ];
+    let mut animation = clip(channel(vec![position(0.0, 0.0), position(10.0, 2.0)], rotations));
+    validate_and_fix_animation_data(&mut animation, &skeleton(), "door").unwrap();
+
+    let channel = &animation.channels[0];
+    assert_eq!(channel.position_keys.len(), 2);
+    assert_eq!(channel.position_keys[1].position, Vec3::X * 2.0);
+    assert_eq!(channel.rotation_keys.len(), 2);
+    assert_eq!(channel.rotation_keys[1].rotation, open);
+}
+
+#[test]
+fn short_tracks_hold_their_ends() {
+    let keys = vec![
+        PositionKey { out_tangent: Vec3::Y, interpolation: Interpolation::CubicSpline, ..position(2.0, 1.0) },
+        PositionKey {
+            in_tangent: Vec3::Y,
+            out_tangent: Vec3::Y,
+            interpolation: Interpolation::CubicSpline,
+            ..position(6.0, 3.0)
+        },
+    ];
+    let mut animation = clip(channel(keys, Vec::new()));
+    validate_and_fix_animation_data(&mut animation, &skeleton(), "door").unwrap();
+
+    let keys = &animation.channels[0].position_keys;
+    let times: Vec<f64> = keys.iter().map(|k| k.time).collect();
+    assert_eq!(times, [0.0, 2.0, 6.0, 10.0]);
+    // The first value is held from the start and the last one to the end.
+    assert_eq!(keys[0].position, Vec3::X);
+    assert_eq!(keys[3].position, Vec3::X * 3.0);
+    assert!(keys.iter().all(|k| k.interpolation == Interpolation::CubicSpline));
+    // The authored spline between them is untouched; the holds around it are flat.
+    assert_eq!((keys[1].in_tangent, keys[1].out_tangent), (Vec3::ZERO, Vec3::Y));
+    assert_eq!((keys[2].in_tangent, keys[2].out_tangent), (Vec3::Y, Vec3::ZERO));
+    assert_eq!(keys[0].out_tangent, Vec3::ZERO);
+    assert_eq!(keys[3].in_tangent, Vec3::ZERO);
+
+    // Tracks the clip does not animate rest at the identity.
+    assert_eq!(animation.channels[0].rotation_keys[0].rotation, Quat::IDENTITY);
+}
@@ -0,0 +1,140 @@
+//! Drops animation keys within tolerance and measures what that saves.
+
+use database::keyframes::{KeyframeSettings, compress_animation};
+use glam::{Quat, Vec3};
+use types::{Animation, AnimationChannel, Interpolation, PositionKey, RotationKey, ScaleKey};
+
+fn clip(channel: AnimationChannel) -> Animation {
+    Animation {
+        name: "Run".to_string(),
+        skeleton: "Fox".to_string(),
+        duration_in_ticks: 4.0,
+        ticks_per_second: 24.0,
+        channels: vec![channel],
+        morph_channels: Vec::new(),
+    }
+}
+
+fn channel(positions: &[Vec3], interpolation: Interpolation) -> AnimationChannel {
+    AnimationChannel {
+        bone_name: "root".to_string(),
+        position_keys: positions
+            .iter()
+            .enumerate()
+            .map(|(i, &position)| PositionKey { time: i as f64, position, interpolation, ..Default::default() })
+            .collect(),
+        rotation_keys: Vec::new(),
+        quantized_rotation_keys: None,
+        scale_keys: Vec::new(),
+    }
+}
+
+fn times(keys: &[PositionKey]) -> Vec<f64> {
+    keys.iter().map(|k| k.time).collect()
+}
+
+#[test]
+fn keys_are_dropped_only_within_tolerance() {
+    let settings = KeyframeSettings { position_tolerance: 0.01, ..Default::default() };
+
+    // Keys on a straight line are reproduced exactly by their neighbours.
+    let line: Vec<Vec3> = (0..5).map(|i| Vec3::X * i as f32).collect();
+    let mut animation = clip(channel(&line, Interpolation::Linear));
+    compress_animation(&mut animation, &settings);
+    assert_eq!(times(&animation.channels[0].position_keys), [0.0, 4.0]);
+
+    // A bump just inside the tolerance goes; one just outside it is kept as a key.
+    let mut bumped = line.clone();
+    bumped[2].y = 0.009;
+    let mut animation = clip(channel(&bumped, Interpolation::Linear));
+    compress_animation(&mut animation, &settings);
+    assert_eq!(times(&animation.channels[0].position_keys), [0.0, 4.0]);
+
+    bumped[2].y = 0.02;
+    let mut animation = clip(channel(&bumped, Interpolation::Linear));
+    compress_animation(&mut animation, &settings);
+    assert_eq!(times(&animation.channels[0].position_keys), [0.0, 2.0, 4.0]);
+    assert_eq!(animation.channels[0].position_keys[1].position, bumped[2]);
+}
+
+#[test]
+fn first_and_last_keys_are_always_kept() {
+    let mut animation = clip(channel(&[Vec3::ONE; 6], Interpolation::Linear));
+    compress_animation(&mut animation, &KeyframeSettings { position_tolerance: 100.0, ..Default::default() });
+    let keys = &animation.channels[0].position_keys;
+    assert_eq!(times(keys), [0.0, 5.0]);
+    assert!(keys.iter().all(|k| k.position == Vec3::ONE));
+
+    // Tracks too short to drop anything from are left alone.
+    let mut animation = clip(channel(&[Vec3::ZERO, Vec3::ZERO], Interpolation::Step));
+    compress_animation(&mut animation, &KeyframeSettings::default());
+    assert_eq!(animation.channels[0].position_keys.len(), 2);
+}
+
+#[test]
+fn step_keys_are_dropped_only_where_the_value_holds() {
+    let values = [Vec3::ZERO, Vec3::ZERO, Vec3::X, Vec3::X, Vec3::X];
+    let mut animation = clip(channel(&values, Interpolation::Step));
+    compress_animation(&mut animation, &KeyframeSettings::default());
+    assert_eq!(times(&animation.channels[0].position_keys), [0.0, 2.0, 4.0]);
+}
+
+#[test]
+fn cubic_tracks_are_left_as_imported() {
+    let line: Vec<Vec3> = (0..5).map(|i| Vec3::X * i as f32).collect();
+    let mut spline = channel(&line, Interpolation::CubicSpline);
+    spline.rotation_keys = (0..5)
+        .map(|i| RotationKey {
+            time: i as f64,
+            rotation: Quat::from_rotation_y(0.1 * i as f32),
+            interpolation: Interpolation::CubicSpline,
+            ..Default::default()
+        })
+        .collect();
+    let mut animation = clip(spline);
+
+    let stats = compress_animation(
+        &mut animation,
+        &KeyframeSettings { position_tolerance: 1.0, rotation_tolerance: 1.0, quantize_rotations: true, ..Default::default() },
+    );
+    let channel = &animation.channels[0];
+    assert_eq!(channel.position_keys.len(), 5);
+    // Spline rotations are not packed either, since their tangents would have to be too.
+    assert_eq!(channel.rotation_keys.len(), 5);
+    assert!(channel.quantized_rotation_keys.is_none());
+    assert_eq!((stats.keys_before, stats.keys_after), (10, 10));
+}
+
+#[test]
+fn stats_count_keys_bytes_and_error() {
+    let positions: Vec<Vec3> = (0..9).map(|i| Vec3::new(i as f32, 0.0, 0.0)).collect();
+    let mut walk = channel(&positions, Interpolation::Linear);
+    walk.rotation_keys = (0..9)
+        .map(|i| RotationKey { time: i as f64, rotation: Quat::from_rotation_y(0.2 * i as f32), ..Default::default() })
+        .collect();
+    walk.scale_keys = (0..9)
+        .map(|i| ScaleKey {
+            time: i as f64,
+            // Alternates by less than the tolerance, so the error is non-zero but bounded.
+            scale: Vec3::splat(1.0 + if i % 2 == 0 { 0.0 } else { 5e-5 }),
+            ..Default::default()
+        })
+        .collect();
+    let mut animation = clip(walk);
+
+    let settings = KeyframeSettings { quantize_rotations: true, ..Default::default() };
+    let stats = compress_animation(&mut animation, &settings);
+    let channel = &animation.channels[0];
+
+    assert_eq!(stats.keys_before, 27);
+    assert_eq!(stats.keys_after, channel.position_keys.len() + channel.rotation_key_count() + channel.scale_keys.len());
+    assert_eq!(stats.keys_after, 6);
+    assert!(channel.rotation_keys.is_empty());
+    assert_eq!(channel.quantized_rotation_keys.as_ref().map(Vec::len), Some(2));
+    assert!(stats.bytes_after < stats.bytes_before);
+
+    assert_eq!(stats.max_position_error, 0.0);
+    assert!(stats.max_scale_error > 0.0 && stats.max_scale_error <= settings.scale_tolerance);
+    // Packing adds its own small error on top of the reduction.
+    assert!(stats.max_rotation_error <= settings.rotation_tolerance + 1e-3);
+}
@@ -0,0 +1,243 @@
+//! Triggers each validation issue with a small fixture and checks how findings are reported.
+
+use database::validate::{Finding, Issue, Severity, ValidationReport, validate_animated_model, validate_model};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use types::{
+    AABB, AnimatedMesh, AnimatedModel, Bone, Mesh, Model, Skeleton, SkinnedVertex, Vertex, VertexAttributes,
+};
+
+const TRIANGLE: [Vec3; 3] = [Vec3::ZERO, Vec3::X, Vec3::Y];
+
+fn vertex(position: Vec3, uv: Vec2) -> Vertex {
+    Vertex { position: position.extend(1.0), normal: Vec4::Z, uv, ..Default::default() }
+}
+
+/// A textured triangle with distinct UVs, which passes every check.
+fn mesh() -> Mesh {
+    Mesh {
+        name: "body".to_string(),
+        vertices: TRIANGLE.iter().map(|&p| vertex(p, p.truncate())).collect(),
+        quantized_vertices: None,
+        indices: vec![0, 1, 2],
+        texture_name: Some("body.png".to_string()),
+        material: None,
+        meshlets: None,
+        lods: Vec::new(),
+        aabb: AABB::default(),
+        attributes: VertexAttributes::default(),
+    }
+}
+
+fn model(mesh: Mesh) -> Model {
+    Model { name: "crate".to_string(), meshes: vec![mesh], aabb: AABB::default() }
+}
+
+fn bone(name: &str, parent_index: Option<usize>) -> Bone {
+    Bone { name: name.to_string(), parent_index, transform: Mat4::IDENTITY, inverse_bind_pose: Mat4::IDENTITY }
+}
+
+/// A triangle fully weighted to the root of a two-bone skeleton, which passes every check.
+fn animated_model() -> AnimatedModel {
+    let vertices = TRIANGLE
+        .iter()
+        .map(|&p| SkinnedVertex {
+            position: p.extend(1.0),
+            normal: Vec4::Z,
+            uv: p.truncate(),
+            bone_weights: [1.0, 0.0, 0.0, 0.0],
+            ..Default::default()
+        })
+        .collect();
+    AnimatedModel {
+        name: "fox".to_string(),
+        meshes: vec![AnimatedMesh {
+            name: "body".to_string(),
+            vertices,
+            quantized_vertices: None,
+            indices: vec![0, 1, 2],
+            texture_name: None,
+            material: None,
+            meshlets: None,
+            lods: Vec::new(),
+            aabb: AABB::default(),
+            attributes: VertexAttributes::default(),
+            morph_targets: Vec::new(),
+            morph_weights: Vec::new(),
+            node_name: None,
+            bone_palette: Vec::new(),
+        }],
+        skeleton: Skeleton { bones: vec![bone("root", None), bone("spine", Some(0))] },
+        aabb: AABB::default(),
+    }
+}
+
+fn issues(findings: Vec<Finding>) -> Vec<Issue> {
+    findings.into_iter().map(|f| f.issue).collect()
+}
+
+#[test]
+fn clean_fixtures_have_no_findings() {
+    assert_eq!(validate_model(&model(mesh())), []);
+    assert_eq!(validate_animated_model(&animated_model()), []);
+}
+
+#[test]
+fn degenerate_triangles() {
+    let mut mesh = mesh();
+    // One with a repeated vertex and one with three collinear corners.
+    mesh.vertices.push(vertex(Vec3::X * 2.0, Vec2::ONE));
+    mesh.indices.extend([0, 1, 1, 0, 1, 3]);
+    let findings = validate_model(&model(mesh));
+    assert_eq!(findings[0].asset, "crate/body");
+    assert_eq!(issues(findings), [Issue::DegenerateTriangles { count: 2 }]);
+}
+
+#[test]
+fn indices_out_of_range() {
+    let mut mesh = mesh();
+    mesh.indices.extend([0, 1, 7]);
+    assert_eq!(issues(validate_model(&model(mesh))), [Issue::IndicesOutOfRange { count: 1 }]);
+}
+
+#[test]
+fn non_finite_positions() {
+    let mut mesh = mesh();
+    mesh.vertices[1].position.x = f32::NAN;
+    mesh.vertices[2].position.y = f32::INFINITY;
+    let found = issues(validate_model(&model(mesh)));
+    assert!(found.contains(&Issue::NonFinitePositions { count: 2 }), "{found:?}");
+}
+
+#[test]
+fn zero_length_normals() {
+    let mut mesh = mesh();
+    mesh.vertices[0].normal = Vec4::ZERO;
+    assert_eq!(issues(validate_model(&model(mesh))), [Issue::ZeroLengthNormals { count: 1 }]);
+}
+
+#[test]
+fn missing_uvs_only_matter_when_textured() {
+    let mut mesh = mesh();
+    mesh.vertices.iter_mut().for_each(|v| v.uv = Vec2::ZERO);
+    assert_eq!(issues(validate_model(&model(mesh.clone()))), [Issue::MissingUvs]);
+
+    mesh.texture_name = None;
+    assert_eq!(validate_model(&model(mesh)), []);
+}
+
+#[test]
+fn unnormalized_weights() {
+    let mut model = animated_model();
+    let vertices = &mut model.meshes[0].vertices;
+    vertices[0].bone_weights = [0.5, 0.2, 0.0, 0.0];
+    vertices[1].bone_weights = [0.9, 0.0, 0.0, 0.0];
+    // Within the tolerance.
+    vertices[2].bone_weights = [0.5, 0.5005, 0.0, 0.0];
+    assert_eq!(
+        issues(validate_animated_model(&model)),
+        [Issue::UnnormalizedWeights { count: 2, worst_sum: 0.7 }]
+    );
+}
+
+#[test]
+fn joints_out_of_range() {
+    let mut model = animated_model();
+    let vertices = &mut model.meshes[0].vertices;
+    vertices[0].bone_indices = [0, 5, 0, 0];
+    vertices[0].bone_weights = [0.5, 0.5, 0.0, 0.0];
+    vertices[1].bone_indices = [3, 9, 0, 0];
+    vertices[1].bone_weights = [1.0, 0.0, 0.0, 0.0];
+    // Joint 9 is unweighted and never read, so it is not counted.
+    assert_eq!(
+        issues(validate_animated_model(&model)),
+        [Issue::JointsOutOfRange { count: 2, max_joint: 5, bone_count: 2 }]
+    );
+}
+
+#[test]
+fn unparented_bones() {
+    let mut model = animated_model();
+    model.skeleton.bones.extend([bone("prop", None), bone("camera", None)]);
+    let findings = validate_animated_model(&model);
+    assert_eq!(findings[0].asset, "fox");
+    assert_eq!(
+        issues(findings),
+        [Issue::UnparentedBones { bones: vec!["prop".to_string(), "camera".to_string()] }]
+    );
+}
+
+#[test]
+fn severity_follows_the_issue() {
+    let errors = [
+        Issue::IndicesOutOfRange { count: 1 },
+        Issue::NonFinitePositions { count: 1 },
+        Issue::JointsOutOfRange { count: 1, max_joint: 2, bone_count: 1 },
+    ];
+    let warnings = [
+        Issue::DegenerateTriangles { count: 1 },
+        Issue::ZeroLengthNormals { count: 1 },
+        Issue::MissingUvs,
+        Issue::UnnormalizedWeights { count: 1, worst_sum: 0.5 },
+        Issue::UnparentedBones { bones: vec!["prop".to_string()] },
+    ];
+    for issue in errors {
+        assert_eq!(Finding::new("a".to_string(), issue).severity, Severity::Error);
+    }
+    for issue in warnings {
+        assert_eq!(Finding::new("a".to_string(), issue).severity, Severity::Warning);
+    }
+}
+
+#[test]
+fn strict_mode_fails_only_sources_with_errors() {
+    let warning = || Finding::new("crate/body".to_string(), Issue::ZeroLengthNormals { count: 1 });
+    let error = || Finding::new("crate/body".to_string(), Issue::IndicesOutOfRange { count: 3 });
+
+    let mut report = ValidationReport::default();
+    assert_eq!(report.add("clean.gltf", Vec::new(), true), None);
+    assert_eq!(report.add("warned.gltf", vec![warning()], true), None);
+    assert_eq!(report.add("lenient.gltf", vec![error()], false), None);
+    assert_eq!(
+        report.add("broken.gltf", vec![error(), error(), warning()], true).as_deref(),
+        Some("2 validation errors")
+    );
+
+    // Failed sources are still reported; clean ones are left out.
+    assert_eq!(report.sources.keys().collect::<Vec<_>>(), ["broken.gltf", "lenient.gltf", "warned.gltf"]);
+    assert_eq!(report.count(Severity::Error), 3);
+    assert_eq!(report.count(Severity::Warning), 2);
+}
+
+#[test]
+fn report_json_shape() {
+    let mut report = ValidationReport::default();
+    let findings = vec![
+        Finding::new("fox".to_string(), Issue::UnparentedBones { bones: vec!["prop".to_string()] }),
+        Finding::new("fox/body".to_string(), Issue::JointsOutOfRange { count: 4, max_joint: 70, bone_count: 64 }),
+        Finding::new("fox/body".to_string(), Issue::MissingUvs),
+    ];
+    report.add("characters/fox.gltf", findings, false);
+
+    let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "errors": 1,
+            "warnings": 2,
+            "sources": {
+                "characters/fox.gltf": [
+                    {"asset": "fox", "severity": "warning", "kind": "unparented_bones", "bones": ["prop"]},
+                    {
+                        "asset": "fox/body",
+                        "severity": "error",
+                        "kind": "joints_out_of_range",
+                        "count": 4,
+                        "max_joint": 70,
+                        "bone_count": 64
+                    },
+                    {"asset": "fox/body", "severity": "warning", "kind": "missing_uvs"}
+                ]
+            }
+        })
+    );
+}
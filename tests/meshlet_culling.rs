@@ -0,0 +1,67 @@
+//! Checks the CPU meshlet culling tests against a camera at the origin looking down -Z.
+
+use glam::{Mat4, Vec3};
+use types::{Frustum, MeshletBounds};
+
+fn frustum() -> Frustum {
+    let proj = Mat4::perspective_rh(90f32.to_radians(), 1.0, 0.1, 100.0);
+    let view = Mat4::look_at_rh(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y);
+    Frustum::from_view_proj(&(proj * view))
+}
+
+fn sphere(center: Vec3, radius: f32) -> MeshletBounds {
+    MeshletBounds {
+        center,
+        radius,
+        // A full cone never culls.
+        cone_cutoff: 1.0,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn spheres_are_tested_against_every_plane() {
+    let frustum = frustum();
+    let identity = Mat4::IDENTITY;
+
+    assert!(sphere(Vec3::new(0.0, 0.0, -10.0), 1.0).is_in_frustum(&identity, &frustum));
+    // Behind the camera, beyond the far plane, and off to the side.
+    assert!(!sphere(Vec3::new(0.0, 0.0, 10.0), 1.0).is_in_frustum(&identity, &frustum));
+    assert!(!sphere(Vec3::new(0.0, 0.0, -200.0), 1.0).is_in_frustum(&identity, &frustum));
+    assert!(!sphere(Vec3::new(30.0, 0.0, -10.0), 1.0).is_in_frustum(&identity, &frustum));
+    // Straddling the right plane still counts as visible.
+    assert!(sphere(Vec3::new(10.5, 0.0, -10.0), 1.0).is_in_frustum(&identity, &frustum));
+
+    // The transform moves and scales the sphere.
+    let moved = Mat4::from_translation(Vec3::new(0.0, 0.0, -20.0));
+    assert!(sphere(Vec3::new(0.0, 0.0, 10.0), 1.0).is_in_frustum(&moved, &frustum));
+    let scaled = Mat4::from_scale(Vec3::splat(10.0));
+    assert!(sphere(Vec3::new(1.05, 0.0, -1.0), 0.1).is_in_frustum(&scaled, &frustum));
+}
+
+#[test]
+fn normal_cones_cull_only_when_facing_away() {
+    // Triangles around z = -10 whose normals point down -Z, away from the origin.
+    let bounds = MeshletBounds {
+        center: Vec3::new(0.0, 0.0, -10.0),
+        radius: 1.0,
+        cone_apex: Vec3::new(0.0, 0.0, -10.0),
+        cone_axis: Vec3::NEG_Z,
+        cone_cutoff: 0.5,
+    };
+    let identity = Mat4::IDENTITY;
+
+    assert!(bounds.is_backfacing(&identity, Vec3::ZERO));
+    assert!(!bounds.is_backfacing(&identity, Vec3::new(0.0, 0.0, -20.0)));
+    // Seen from the side the viewer is outside the cone.
+    assert!(!bounds.is_backfacing(&identity, Vec3::new(20.0, 0.0, -10.0)));
+
+    // Turning the meshlet around makes it face the origin.
+    let turned = Mat4::from_translation(Vec3::new(0.0, 0.0, -10.0))
+        * Mat4::from_rotation_y(std::f32::consts::PI)
+        * Mat4::from_translation(Vec3::new(0.0, 0.0, 10.0));
+    assert!(!bounds.is_backfacing(&turned, Vec3::ZERO));
+
+    let degenerate = MeshletBounds { cone_cutoff: 1.0, ..bounds };
+    assert!(!degenerate.is_backfacing(&identity, Vec3::ZERO));
+}
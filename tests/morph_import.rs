@@ -0,0 +1,106 @@
+//! Imports a glTF file whose morph targets sit on a node without a skin and checks they are
+//! posed by an animated model of their nodes, with the clip driving their weights.
+
+use database::gltf_loader::load_gltf_model;
+use glam::Vec3;
+
+const TRIANGLE: [Vec3; 3] = [Vec3::ZERO, Vec3::X, Vec3::Y];
+
+/// Writes a glTF file with a plain triangle at the node "body" and, at its child "face" two
+/// units up, a triangle with two morph targets and a clip animating their weights.
+fn write_morphed_gltf() -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("morph_import_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut floats: Vec<f32> =
+        [TRIANGLE, [Vec3::Z; 3], [Vec3::X; 3]].iter().flatten().flat_map(|v| v.to_array()).collect();
+    // Key times, then the weights of both targets at each key.
+    floats.extend([0.0, 1.0, 0.0, 0.0, 1.0, 0.5]);
+    let bytes: Vec<u8> = floats.iter().flat_map(|f| f.to_le_bytes()).collect();
+    std::fs::write(dir.join("morphed.bin"), &bytes).unwrap();
+
+    let vec3 = |offset: usize, max: [f32; 3]| {
+        format!(
+            r#"{{"bufferView": 0, "byteOffset": {offset}, "componentType": 5126, "count": 3, "type": "VEC3",
+                "min": [0, 0, 0], "max": [{}, {}, {}]}}"#,
+            max[0], max[1], max[2]
+        )
+    };
+    let gltf = format!(
+        r#"{{
+            "asset": {{"version": "2.0"}},
+            "scene": 0,
+            "scenes": [{{"nodes": [0]}}],
+            "nodes": [
+                {{"mesh": 0, "name": "body", "children": [1]}},
+                {{"mesh": 1, "name": "face", "translation": [0, 2, 0], "weights": [0.25, 0]}}
+            ],
+            "meshes": [
+                {{"name": "body", "primitives": [{{"attributes": {{"POSITION": 0}}}}]}},
+                {{"name": "face", "primitives": [{{
+                    "attributes": {{"POSITION": 0}},
+                    "targets": [{{"POSITION": 1}}, {{"POSITION": 2}}]
+                }}]}}
+            ],
+            "animations": [{{
+                "name": "Smile",
+                "channels": [{{"sampler": 0, "target": {{"node": 1, "path": "weights"}}}}],
+                "samplers": [{{"input": 3, "output": 4}}]
+            }}],
+            "accessors": [{}, {}, {},
+                {{"bufferView": 0, "byteOffset": 108, "componentType": 5126, "count": 2, "type": "SCALAR",
+                  "min": [0], "max": [1]}},
+                {{"bufferView": 0, "byteOffset": 116, "componentType": 5126, "count": 4, "type": "SCALAR"}}
+            ],
+            "bufferViews": [{{"buffer": 0, "byteLength": {len}}}],
+            "buffers": [{{"uri": "morphed.bin", "byteLength": {len}}}]
+        }}"#,
+        vec3(0, [1.0, 1.0, 0.0]),
+        vec3(36, [0.0, 0.0, 1.0]),
+        vec3(72, [1.0, 0.0, 0.0]),
+        len = bytes.len()
+    );
+    let path = dir.join("morphed.gltf");
+    std::fs::write(&path, gltf).unwrap();
+    path
+}
+
+#[test]
+fn unskinned_morph_targets_are_posed_by_their_nodes() {
+    let path = write_morphed_gltf();
+    let imported = load_gltf_model(&path, "head", false).unwrap();
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+
+    // The plain mesh stays static; the face's node still exists but places nothing.
+    let model = imported.model.unwrap();
+    assert_eq!(model.meshes.len(), 1);
+    let prefab = imported.prefab.unwrap();
+    let names: Vec<&str> = prefab.nodes.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(names, ["body", "face"]);
+    assert!(prefab.nodes[1].meshes.is_empty());
+
+    assert_eq!(imported.animated_models.len(), 1);
+    let morphs = &imported.animated_models[0];
+    assert_eq!(morphs.name, "head/morphs");
+    let bones: Vec<(&str, Option<usize>)> =
+        morphs.skeleton.bones.iter().map(|b| (b.name.as_str(), b.parent_index)).collect();
+    assert_eq!(bones, [("head/morphs", None), ("body", Some(0)), ("face", Some(1))]);
+
+    let face = &morphs.meshes[0];
+    assert_eq!(face.node_name.as_deref(), Some("face"));
+    assert_eq!(face.morph_weights, [0.25, 0.0]);
+    assert_eq!(face.morph_targets.len(), 2);
+    assert_eq!(face.morph_targets[0].position_deltas, [Vec3::Z; 3]);
+    assert_eq!(face.morph_targets[1].position_deltas, [Vec3::X; 3]);
+    // Every vertex follows the face's bone alone.
+    assert!(face.vertices.iter().all(|v| v.bone_indices[0] == 2 && v.bone_weights == [1.0, 0.0, 0.0, 0.0]));
+    // The model's bounds are placed by the nodes, two units up.
+    assert_eq!(morphs.aabb.max.y, 3.0);
+
+    assert_eq!(imported.animations.len(), 1);
+    let smile = &imported.animations[0];
+    assert_eq!(smile.skeleton, "head/morphs");
+    assert!(smile.channels.is_empty());
+    assert_eq!(smile.morph_channels.len(), 1);
+    assert_eq!(smile.morph_channels[0].node_name, "face");
+    assert_eq!(smile.morph_channels[0].keys[1].weights, [1.0, 0.5]);
+}
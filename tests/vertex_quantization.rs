@@ -0,0 +1,161 @@
+//! Round-trips vertices and animation rotations through the quantized layouts with the CPU
+//! decoder.
+
+use glam::{EulerRot, Quat, Vec2, Vec3, Vec4};
+use types::{
+    AABB, AnimatedMesh, AnimationChannel, Interpolation, Mesh, PackedQuat, RotationKey, SkinnedVertex, Vertex,
+    VertexAttributes, VertexFormat,
+};
+
+fn aabb() -> AABB {
+    AABB {
+        min: Vec4::new(-2.0, 0.0, -1.0, 1.0),
+        max: Vec4::new(2.0, 4.0, -1.0, 1.0),
+    }
+}
+
+fn vertex(position: Vec3, normal: Vec3, uv: Vec2, tangent: Vec4) -> Vertex {
+    Vertex {
+        position: position.extend(1.0),
+        normal: normal.normalize().extend(0.0),
+        uv,
+        // A lightmap-style second set in 0..1 and a color derived from it.
+        uv1: uv.fract_gl(),
+        tangent: tangent.truncate().normalize().extend(tangent.w),
+        color: uv.fract_gl().extend(0.25).extend(1.0),
+    }
+}
+
+fn mesh(vertices: Vec<Vertex>) -> Mesh {
+    Mesh {
+        name: "mesh".to_string(),
+        vertices,
+        quantized_vertices: None,
+        indices: vec![0, 1, 2],
+        texture_name: None,
+        material: None,
+        meshlets: None,
+        lods: Vec::new(),
+        aabb: aabb(),
+        attributes: VertexAttributes { uv1: true, color: true },
+    }
+}
+
+#[test]
+fn static_vertices_survive_quantization() {
+    let original = vec![
+        vertex(Vec3::new(-2.0, 0.0, -1.0), Vec3::Z, Vec2::new(0.0, 0.0), Vec4::new(1.0, 0.0, 0.0, 1.0)),
+        vertex(Vec3::new(2.0, 4.0, -1.0), Vec3::new(0.3, -0.5, -0.8), Vec2::new(1.0, 2.5), Vec4::new(0.0, 0.8, -0.5, -1.0)),
+        vertex(Vec3::new(0.7, 1.3, -1.0), Vec3::new(-1.0, -1.0, -1.0), Vec2::new(-0.25, 0.75), Vec4::new(0.0, 0.0, 1.0, 1.0)),
+    ];
+    let mut mesh = mesh(original.clone());
+    mesh.quantize();
+
+    assert_eq!(mesh.vertex_format(), VertexFormat::Quantized);
+    assert!(mesh.vertices.is_empty());
+    assert_eq!(mesh.vertex_count(), 3);
+
+    for (decoded, original) in mesh.decoded_vertices().iter().zip(&original) {
+        // One unorm16 step of the 4-unit extent, and exact on the flat z axis.
+        assert!(decoded.position.abs_diff_eq(original.position, 4.0 / 65535.0));
+        assert!(decoded.normal.abs_diff_eq(original.normal, 1e-3));
+        assert!(decoded.uv.abs_diff_eq(original.uv, 1e-3));
+        assert!(decoded.uv1.abs_diff_eq(original.uv1, 1e-3));
+        // One unorm8 step.
+        assert!(decoded.color.abs_diff_eq(original.color, 1.0 / 255.0));
+        // The bitangent sign rides along in the position's spare lane.
+        assert!(decoded.tangent.abs_diff_eq(original.tangent, 1e-3));
+    }
+}
+
+#[test]
+fn skinned_vertices_keep_bones_and_weights() {
+    let skinned = |bone: u32| SkinnedVertex {
+        position: Vec4::new(1.0, 2.0, -1.0, 1.0),
+        normal: Vec4::new(0.0, 1.0, 0.0, 0.0),
+        uv: Vec2::new(0.5, 0.5),
+        uv1: Vec2::new(0.25, 0.75),
+        bone_indices: [bone, 3, 0, 0],
+        bone_weights: [0.6, 0.4, 0.0, 0.0],
+        tangent: Vec4::new(1.0, 0.0, 0.0, -1.0),
+        color: Vec4::ONE,
+    };
+    let mut mesh = AnimatedMesh {
+        name: "skinned".to_string(),
+        vertices: vec![skinned(255)],
+        quantized_vertices: None,
+        indices: Vec::new(),
+        texture_name: None,
+        material: None,
+        meshlets: None,
+        lods: Vec::new(),
+        aabb: aabb(),
+        attributes: VertexAttributes { uv1: true, color: false },
+        morph_targets: Vec::new(),
+        morph_weights: Vec::new(),
+        node_name: None,
+        bone_palette: Vec::new(),
+    };
+    assert!(mesh.quantize());
+
+    let decoded = mesh.decoded_vertices()[0];
+    assert_eq!(decoded.bone_indices, [255, 3, 0, 0]);
+    assert!(Vec4::from(decoded.bone_weights).abs_diff_eq(Vec4::new(0.6, 0.4, 0.0, 0.0), 1e-4));
+    assert!(decoded.normal.abs_diff_eq(Vec4::new(0.0, 1.0, 0.0, 0.0), 1e-4));
+    assert!(decoded.tangent.abs_diff_eq(Vec4::new(1.0, 0.0, 0.0, -1.0), 1e-4));
+    assert_eq!(decoded.uv1, Vec2::new(0.25, 0.75));
+    assert_eq!(decoded.color, Vec4::ONE);
+
+    // Bone indices past a byte keep the mesh in the full format.
+    mesh.vertices = vec![skinned(256)];
+    mesh.quantized_vertices = None;
+    assert!(!mesh.quantize());
+    assert_eq!(mesh.vertex_format(), VertexFormat::Full);
+    assert_eq!(mesh.vertices.len(), 1);
+}
+
+#[test]
+fn packed_rotations_stay_within_a_small_angle() {
+    let rotations = [
+        Quat::IDENTITY,
+        -Quat::IDENTITY,
+        Quat::from_rotation_y(std::f32::consts::PI),
+        Quat::from_euler(EulerRot::XYZ, 0.3, -1.2, 2.5),
+        Quat::from_euler(EulerRot::ZYX, -3.0, 0.01, 0.7),
+        Quat::from_xyzw(0.5, -0.5, 0.5, -0.5),
+    ];
+    for rotation in rotations {
+        let decoded = PackedQuat::encode(rotation).decode();
+        assert!(decoded.angle_between(rotation) < 1e-3, "{rotation:?} came back as {decoded:?}");
+    }
+
+    let key = |time: f64, rotation: Quat, interpolation: Interpolation| RotationKey {
+        time,
+        rotation,
+        interpolation,
+        ..Default::default()
+    };
+    let mut channel = AnimationChannel {
+        bone_name: "bone".to_string(),
+        position_keys: Vec::new(),
+        rotation_keys: vec![key(0.0, rotations[3], Interpolation::Linear), key(1.5, rotations[4], Interpolation::Step)],
+        quantized_rotation_keys: None,
+        scale_keys: Vec::new(),
+    };
+    assert!(channel.quantize_rotations());
+    assert!(channel.rotation_keys.is_empty());
+    assert_eq!(channel.rotation_key_count(), 2);
+    let decoded = channel.decoded_rotation_keys();
+    assert_eq!(decoded[1].time, 1.5);
+    assert_eq!(decoded[1].interpolation, Interpolation::Step);
+    assert!(decoded[1].rotation.angle_between(rotations[4]) < 1e-3);
+
+    // Spline keys carry tangents the packed form has no room for.
+    let mut spline = AnimationChannel {
+        rotation_keys: vec![key(0.0, Quat::IDENTITY, Interpolation::CubicSpline)],
+        quantized_rotation_keys: None,
+        ..channel
+    };
+    assert!(!spline.quantize_rotations());
+    assert_eq!(spline.rotation_keys.len(), 1);
+}
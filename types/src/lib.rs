@@ -1,12 +1,42 @@
+use std::borrow::Cow;
+
 use glam::{Mat4, Quat, Vec2, Vec3, Vec4};
 use redb::TableDefinition;
 use serde::{Deserialize, Serialize};
 
+mod quantized;
+mod sampling;
+
+pub use quantized::{PackedQuat, QuantizedRotationKey, QuantizedSkinnedVertex, QuantizedVertex, VertexFormat};
+pub use sampling::{hermite, sample_position, sample_quantized_rotation, sample_rotation, sample_scale, sample_weights};
+
 pub const MODEL_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("models");
+/// Texture names, each pointing at a `TextureRef` into `TEXTURE_BLOB_TABLE`.
 pub const TEXTURE_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("textures");
+/// Texture contents as uncompressed KTX2 files holding the full mip chain, keyed by the hex
+/// blake3 hash of those bytes.
+pub const TEXTURE_BLOB_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("texture_blobs");
 pub const ANIMATED_MODEL_TABLE: TableDefinition<&str, &[u8]> =
     TableDefinition::new("animated_models");
 pub const ANIMATION_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("animations");
+pub const SOURCE_MANIFEST_TABLE: TableDefinition<&str, &[u8]> =
+    TableDefinition::new("source_manifest");
+pub const MATERIAL_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("materials");
+/// One `Composite` per model source, keyed like the models it lists.
+pub const COMPOSITE_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("composites");
+/// One `Prefab` per static model that keeps its source's node hierarchy, under the model's key.
+pub const PREFAB_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("prefabs");
+/// One `Scene` per model source with authored cameras or lights, keyed like its models.
+pub const SCENE_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("scenes");
+pub const META_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("meta");
+
+/// Layout version of everything stored in the tables above. Bump it whenever a stored
+/// type changes shape and register a migration for the old version in the baker.
+pub const FORMAT_VERSION: u32 = 18;
+
+/// Most bones one skinned mesh may weight. The baker splits larger skins into meshes that each
+/// refer to their bones through a `AnimatedMesh::bone_palette` of at most this many.
+pub const BONE_PALETTE_SIZE: usize = 256;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
 #[repr(C)]
@@ -15,44 +45,309 @@ pub struct AABB {
     pub max: Vec4,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, bytemuck::Pod, bytemuck::Zeroable)]
+impl AABB {
+    /// The box enclosing this one once placed by `transform`.
+    pub fn transformed(&self, transform: &Mat4) -> AABB {
+        let (min, max) = (self.min.truncate(), self.max.truncate());
+        let mut out_min = Vec3::splat(f32::INFINITY);
+        let mut out_max = Vec3::splat(f32::NEG_INFINITY);
+        for corner in 0..8 {
+            let point = Vec3::new(
+                if corner & 1 == 0 { min.x } else { max.x },
+                if corner & 2 == 0 { min.y } else { max.y },
+                if corner & 4 == 0 { min.z } else { max.z },
+            );
+            let point = transform.transform_point3(point);
+            out_min = out_min.min(point);
+            out_max = out_max.max(point);
+        }
+        AABB {
+            min: out_min.extend(self.min.w),
+            max: out_max.extend(self.max.w),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(C)]
 pub struct Vertex {
     pub position: Vec4,
     pub normal: Vec4,
     pub uv: Vec2,
-    pub _padding: [f32; 2],
+    /// `TEXCOORD_1`, such as lightmap coordinates; zero unless `VertexAttributes::uv1`.
+    pub uv1: Vec2,
+    /// MikkTSpace tangent with the bitangent sign in `w`; all zero until tangents are known.
+    pub tangent: Vec4,
+    /// Linear RGBA multiplied into the base color; white unless `VertexAttributes::color`.
+    pub color: Vec4,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(C)]
 pub struct SkinnedVertex {
     pub position: Vec4,
     pub normal: Vec4,
     pub uv: Vec2,
-    pub _padding: [f32; 2],
+    /// `TEXCOORD_1`, such as lightmap coordinates; zero unless `VertexAttributes::uv1`.
+    pub uv1: Vec2,
     pub bone_indices: [u32; 4],
     pub bone_weights: [f32; 4],
+    /// MikkTSpace tangent with the bitangent sign in `w`; all zero until tangents are known.
+    pub tangent: Vec4,
+    /// Linear RGBA multiplied into the base color; white unless `VertexAttributes::color`.
+    pub color: Vec4,
+}
+
+/// The optional vertex attributes a mesh's source provided. Absent ones still occupy their
+/// place in the vertex, holding the neutral value documented on the field.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VertexAttributes {
+    pub uv1: bool,
+    pub color: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Mesh {
     pub name: String,
+    /// Empty once the mesh is quantized; use `decoded_vertices` to read either form.
     pub vertices: Vec<Vertex>,
+    /// The vertices encoded relative to `aabb`, when baked with `VertexFormat::Quantized`.
+    pub quantized_vertices: Option<Vec<QuantizedVertex>>,
     pub indices: Vec<u32>,
     pub texture_name: Option<String>,
+    /// `MATERIAL_TABLE` key; `None` means the default material.
+    pub material: Option<String>,
     pub meshlets: Option<Meshlets>,
+    /// Progressively coarser versions of `indices`, each with a larger `MeshLod::error`.
+    pub lods: Vec<MeshLod>,
     pub aabb: AABB,
+    pub attributes: VertexAttributes,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnimatedMesh {
     pub name: String,
+    /// Empty once the mesh is quantized; use `decoded_vertices` to read either form.
     pub vertices: Vec<SkinnedVertex>,
+    /// The vertices encoded relative to `aabb`, when baked with `VertexFormat::Quantized`.
+    pub quantized_vertices: Option<Vec<QuantizedSkinnedVertex>>,
     pub indices: Vec<u32>,
     pub texture_name: Option<String>,
+    /// `MATERIAL_TABLE` key; `None` means the default material.
+    pub material: Option<String>,
     pub meshlets: Option<Meshlets>,
+    /// Progressively coarser versions of `indices`, each with a larger `MeshLod::error`.
+    pub lods: Vec<MeshLod>,
     pub aabb: AABB,
+    pub attributes: VertexAttributes,
+    /// Blend shapes over `vertices`, in the order the source's weights refer to them.
+    pub morph_targets: Vec<MorphTarget>,
+    /// Weight of each of `morph_targets` while no clip animates them.
+    pub morph_weights: Vec<f32>,
+    /// Source node that placed the mesh, which `MorphChannel::node_name` refers to.
+    pub node_name: Option<String>,
+    /// The skeleton bone of each joint the vertices name, when the mesh was split to fit the
+    /// bone palette. Empty when the joints are skeleton bones.
+    pub bone_palette: Vec<u32>,
+}
+
+/// Offsets added to every vertex of an `AnimatedMesh`, scaled by the target's weight, before
+/// skinning. Deltas are indexed like the vertices.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MorphTarget {
+    pub name: String,
+    pub position_deltas: Vec<Vec3>,
+    /// Empty when the source moves only positions.
+    pub normal_deltas: Vec<Vec3>,
+}
+
+impl MorphTarget {
+    /// Follows a rebuild of the mesh's vertices in which vertex `i` was copied from old vertex
+    /// `origins[i]`.
+    pub fn remap(&mut self, origins: &[u32]) {
+        let remap = |deltas: &[Vec3]| origins.iter().map(|&i| deltas[i as usize]).collect();
+        self.position_deltas = remap(&self.position_deltas);
+        if !self.normal_deltas.is_empty() {
+            self.normal_deltas = remap(&self.normal_deltas);
+        }
+    }
+}
+
+impl Mesh {
+    pub fn vertex_format(&self) -> VertexFormat {
+        match self.quantized_vertices {
+            Some(_) => VertexFormat::Quantized,
+            None => VertexFormat::Full,
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.quantized_vertices.as_ref().map_or(self.vertices.len(), Vec::len)
+    }
+
+    /// Re-encodes `vertices` as `quantized_vertices`. `aabb` must bound them.
+    pub fn quantize(&mut self) {
+        let vertices = std::mem::take(&mut self.vertices);
+        self.quantized_vertices = Some(vertices.iter().map(|v| QuantizedVertex::encode(v, &self.aabb)).collect());
+    }
+
+    pub fn decoded_vertices(&self) -> Cow<'_, [Vertex]> {
+        match &self.quantized_vertices {
+            Some(quantized) => quantized.iter().map(|v| v.decode(&self.aabb)).collect(),
+            None => Cow::Borrowed(&self.vertices),
+        }
+    }
+}
+
+impl AnimatedMesh {
+    pub fn vertex_format(&self) -> VertexFormat {
+        match self.quantized_vertices {
+            Some(_) => VertexFormat::Quantized,
+            None => VertexFormat::Full,
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.quantized_vertices.as_ref().map_or(self.vertices.len(), Vec::len)
+    }
+
+    /// Re-encodes `vertices` as `quantized_vertices`, unless a bone index does not fit the
+    /// quantized layout, in which case the mesh is left as is and `false` returned.
+    pub fn quantize(&mut self) -> bool {
+        let quantized: Option<Vec<_>> = self
+            .vertices
+            .iter()
+            .map(|v| QuantizedSkinnedVertex::encode(v, &self.aabb))
+            .collect();
+        let Some(quantized) = quantized else {
+            return false;
+        };
+        self.vertices.clear();
+        self.quantized_vertices = Some(quantized);
+        true
+    }
+
+    pub fn decoded_vertices(&self) -> Cow<'_, [SkinnedVertex]> {
+        match &self.quantized_vertices {
+            Some(quantized) => quantized.iter().map(|v| v.decode(&self.aabb)).collect(),
+            None => Cow::Borrowed(&self.vertices),
+        }
+    }
+
+    /// Grows `aabb` and the bounding sphere of every meshlet, LODs included, by the largest
+    /// delta any morph target moves each vertex, so a fully weighted shape is neither culled
+    /// nor given a coarser LOD than it should. Meshlets whose vertices move lose their normal
+    /// cone, since morphed normals may face anywhere. Must run before `quantize`.
+    pub fn grow_bounds_for_morph_targets(&mut self) {
+        if self.morph_targets.is_empty() {
+            return;
+        }
+        let reach: Vec<f32> = (0..self.vertices.len())
+            .map(|i| {
+                self.morph_targets
+                    .iter()
+                    .filter_map(|target| target.position_deltas.get(i))
+                    .map(|delta| delta.length())
+                    .fold(0.0, f32::max)
+            })
+            .collect();
+
+        for (vertex, &r) in self.vertices.iter().zip(&reach) {
+            let r = Vec4::new(r, r, r, 0.0);
+            self.aabb.min = self.aabb.min.min(vertex.position - r);
+            self.aabb.max = self.aabb.max.max(vertex.position + r);
+        }
+
+        let lod_meshlets = self.lods.iter_mut().filter_map(|lod| lod.meshlets.as_mut());
+        for meshlets in self.meshlets.as_mut().into_iter().chain(lod_meshlets) {
+            for meshlet in &mut meshlets.meshlets {
+                let start = meshlet.vertex_offset as usize;
+                let vertices = &meshlets.vertices[start..start + meshlet.vertex_count as usize];
+                let r = vertices.iter().map(|&v| reach[v as usize]).fold(0.0, f32::max);
+                if r > 0.0 {
+                    meshlet.bounds.radius += r;
+                    meshlet.bounds.cone_cutoff = 1.0;
+                }
+            }
+        }
+    }
+}
+
+/// A simplified level of detail over the vertices of its mesh. `error` is how far it deviates
+/// from the full-detail mesh, in model units; the renderer projects it to screen space to
+/// pick a level per instance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeshLod {
+    pub indices: Vec<u32>,
+    pub meshlets: Option<Meshlets>,
+    pub error: f32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum AlphaMode {
+    #[default]
+    Opaque,
+    Mask,
+    Blend,
+}
+
+/// Metallic-roughness PBR material as authored in glTF. Textures are `TEXTURE_TABLE` keys, each
+/// sampled with the UV set its `*_tex_coord` names: 0 for `uv`, 1 for `uv1`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Material {
+    pub name: String,
+    pub base_color_factor: Vec4,
+    pub base_color_texture: Option<String>,
+    pub base_color_tex_coord: u32,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub metallic_roughness_texture: Option<String>,
+    pub metallic_roughness_tex_coord: u32,
+    pub normal_texture: Option<String>,
+    pub normal_tex_coord: u32,
+    pub normal_scale: f32,
+    pub occlusion_texture: Option<String>,
+    pub occlusion_tex_coord: u32,
+    pub occlusion_strength: f32,
+    pub emissive_factor: Vec3,
+    pub emissive_texture: Option<String>,
+    pub emissive_tex_coord: u32,
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32,
+    pub double_sided: bool,
+}
+
+impl Default for Material {
+    /// The glTF default material.
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            base_color_factor: Vec4::ONE,
+            base_color_texture: None,
+            base_color_tex_coord: 0,
+            metallic_factor: 1.0,
+            roughness_factor: 1.0,
+            metallic_roughness_texture: None,
+            metallic_roughness_tex_coord: 0,
+            normal_texture: None,
+            normal_tex_coord: 0,
+            normal_scale: 1.0,
+            occlusion_texture: None,
+            occlusion_tex_coord: 0,
+            occlusion_strength: 1.0,
+            emissive_factor: Vec3::ZERO,
+            emissive_texture: None,
+            emissive_tex_coord: 0,
+            alpha_mode: AlphaMode::Opaque,
+            alpha_cutoff: 0.5,
+            double_sided: false,
+        }
+    }
+}
+
+/// `MATERIAL_TABLE` key of a material, namespaced like animations.
+pub fn material_key(model: &str, material: &str) -> String {
+    format!("{model}/{material}")
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,6 +356,63 @@ pub struct Meshlet {
     pub triangle_offset: u32,
     pub vertex_count: u32,
     pub triangle_count: u32,
+    pub bounds: MeshletBounds,
+}
+
+/// Bounding sphere and normal cone of one meshlet in model space, as computed by meshopt.
+/// Every triangle faces away from a viewer inside the cone `dot(normalize(apex - eye), axis)
+/// >= cutoff`; a cutoff of 1 or more means the triangles face too many ways to cull.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct MeshletBounds {
+    pub center: Vec3,
+    pub radius: f32,
+    pub cone_apex: Vec3,
+    pub cone_axis: Vec3,
+    pub cone_cutoff: f32,
+}
+
+impl MeshletBounds {
+    /// Whether the bounding sphere, placed by `transform`, touches `frustum`.
+    pub fn is_in_frustum(&self, transform: &Mat4, frustum: &Frustum) -> bool {
+        let (scale, _, _) = transform.to_scale_rotation_translation();
+        frustum.intersects_sphere(
+            transform.transform_point3(self.center),
+            self.radius * scale.abs().max_element(),
+        )
+    }
+
+    /// Whether every triangle, placed by `transform`, faces away from `eye`.
+    pub fn is_backfacing(&self, transform: &Mat4, eye: Vec3) -> bool {
+        if self.cone_cutoff >= 1.0 {
+            return false;
+        }
+        let apex = transform.transform_point3(self.cone_apex);
+        let axis = transform.transform_vector3(self.cone_axis).normalize_or_zero();
+        (apex - eye).normalize_or_zero().dot(axis) >= self.cone_cutoff
+    }
+}
+
+/// The six planes of a view frustum, each as `(normal, distance)` with the normal pointing
+/// inwards.
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes of a right-handed projection with a 0..1 depth range, as wgpu uses.
+    pub fn from_view_proj(view_proj: &Mat4) -> Self {
+        let (x, y, z, w) = (view_proj.row(0), view_proj.row(1), view_proj.row(2), view_proj.row(3));
+        let planes = [w + x, w - x, w + y, w - y, z, w - z]
+            .map(|plane| plane / plane.truncate().length());
+        Self { planes }
+    }
+
+    pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.truncate().dot(center) + plane.w >= -radius)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -90,38 +442,138 @@ pub struct Skeleton {
     pub bones: Vec<Bone>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+impl Skeleton {
+    /// Whether a clip authored for `other` can drive this skeleton: the same bones, by name,
+    /// in the same order and with the same parents. Bind poses may differ.
+    pub fn is_compatible_with(&self, other: &Skeleton) -> bool {
+        self.bones.len() == other.bones.len()
+            && self
+                .bones
+                .iter()
+                .zip(&other.bones)
+                .all(|(a, b)| a.name == b.name && a.parent_index == b.parent_index)
+    }
+}
+
+/// How a channel moves from a key to the next one, as in glTF samplers. The mode of a key
+/// applies to the segment that starts at it.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Holds the key's value until the next key.
+    Step,
+    /// Lerps positions and scales and slerps rotations.
+    #[default]
+    Linear,
+    /// Hermite spline through the key values using their tangents.
+    CubicSpline,
+}
+
+/// Tangents are in value units per tick and only read for `Interpolation::CubicSpline`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
 pub struct PositionKey {
     pub time: f64,
     pub position: Vec3,
+    pub interpolation: Interpolation,
+    pub in_tangent: Vec3,
+    pub out_tangent: Vec3,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+/// Rotation tangents are quaternion derivatives, so they are kept as plain 4-vectors.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
 pub struct RotationKey {
     pub time: f64,
     pub rotation: Quat,
+    pub interpolation: Interpolation,
+    pub in_tangent: Vec4,
+    pub out_tangent: Vec4,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
 pub struct ScaleKey {
     pub time: f64,
     pub scale: Vec3,
+    pub interpolation: Interpolation,
+    pub in_tangent: Vec3,
+    pub out_tangent: Vec3,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AnimationChannel {
     pub bone_name: String,
     pub position_keys: Vec<PositionKey>,
+    /// Empty when the rotations are stored in `quantized_rotation_keys` instead.
     pub rotation_keys: Vec<RotationKey>,
+    pub quantized_rotation_keys: Option<Vec<QuantizedRotationKey>>,
     pub scale_keys: Vec<ScaleKey>,
 }
 
+impl AnimationChannel {
+    pub fn rotation_key_count(&self) -> usize {
+        self.quantized_rotation_keys.as_ref().map_or(self.rotation_keys.len(), Vec::len)
+    }
+
+    /// Re-encodes `rotation_keys` as `quantized_rotation_keys`, unless one of them is a cubic
+    /// spline key, in which case the channel is left as is and `false` returned.
+    pub fn quantize_rotations(&mut self) -> bool {
+        if self.rotation_keys.iter().any(|k| k.interpolation == Interpolation::CubicSpline) {
+            return false;
+        }
+        let keys = std::mem::take(&mut self.rotation_keys);
+        self.quantized_rotation_keys = Some(
+            keys.iter()
+                .map(|k| QuantizedRotationKey {
+                    time: k.time as f32,
+                    rotation: PackedQuat::encode(k.rotation),
+                    interpolation: k.interpolation,
+                })
+                .collect(),
+        );
+        true
+    }
+
+    pub fn decoded_rotation_keys(&self) -> Cow<'_, [RotationKey]> {
+        match &self.quantized_rotation_keys {
+            Some(quantized) => quantized
+                .iter()
+                .map(|k| RotationKey {
+                    time: k.time as f64,
+                    rotation: k.rotation.decode(),
+                    interpolation: k.interpolation,
+                    ..Default::default()
+                })
+                .collect(),
+            None => Cow::Borrowed(&self.rotation_keys),
+        }
+    }
+}
+
+/// Morph target weights of the meshes one node placed, all of them per key.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MorphChannel {
+    pub node_name: String,
+    pub keys: Vec<MorphKey>,
+}
+
+/// Tangents are in weight units per tick and only read for `Interpolation::CubicSpline`; they
+/// are empty otherwise.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MorphKey {
+    pub time: f64,
+    pub weights: Vec<f32>,
+    pub interpolation: Interpolation,
+    pub in_tangents: Vec<f32>,
+    pub out_tangents: Vec<f32>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Animation {
     pub name: String,
+    /// Key of the animated model whose skeleton this clip was authored for.
+    pub skeleton: String,
     pub duration_in_ticks: f64,
     pub ticks_per_second: f64,
     pub channels: Vec<AnimationChannel>,
+    pub morph_channels: Vec<MorphChannel>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -131,3 +583,172 @@ pub struct AnimatedModel {
     pub skeleton: Skeleton,
     pub aabb: AABB,
 }
+
+/// Everything one model source produced. A level exported as a single file can hold static
+/// props and several skinned characters; they share the source's coordinate space and are
+/// placed together through this record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Composite {
+    pub name: String,
+    /// `MODEL_TABLE` key of the unskinned meshes, baked with their node transforms.
+    pub model: Option<String>,
+    /// `ANIMATED_MODEL_TABLE` keys, one per skin.
+    pub animated_models: Vec<String>,
+    pub aabb: AABB,
+}
+
+/// The node hierarchy of a static model. Meshes are baked once in their own space and placed
+/// by the nodes that reference them, so a mesh reused by many nodes is stored once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Prefab {
+    pub name: String,
+    /// `MODEL_TABLE` key of the model whose meshes the nodes reference.
+    pub model: String,
+    /// Parents come before their children, as in `Skeleton`.
+    pub nodes: Vec<PrefabNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefabNode {
+    pub name: String,
+    pub parent_index: Option<usize>,
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+    /// Indices into the model's `meshes` drawn at this node.
+    pub meshes: Vec<usize>,
+}
+
+impl Prefab {
+    /// Each node's transform relative to the prefab root.
+    pub fn world_transforms(&self) -> Vec<Mat4> {
+        let mut transforms: Vec<Mat4> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let local = Mat4::from_scale_rotation_translation(node.scale, node.rotation, node.translation);
+            let transform = match node.parent_index {
+                Some(parent) => transforms[parent] * local,
+                None => local,
+            };
+            transforms.push(transform);
+        }
+        transforms
+    }
+}
+
+/// The cameras and punctual lights of a model source. Their placements are in the source's
+/// space, the same one its prefab and composite use.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scene {
+    pub name: String,
+    pub cameras: Vec<SceneCamera>,
+    pub lights: Vec<SceneLight>,
+}
+
+/// A camera looks down its local -Z axis with +Y up. Node scale does not apply to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneCamera {
+    pub name: String,
+    /// The node placing the camera.
+    pub node_name: String,
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub projection: Projection,
+}
+
+/// Angles are in radians. A missing aspect ratio follows the viewport and a missing far plane
+/// is at infinity.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Projection {
+    Perspective {
+        yfov: f32,
+        aspect_ratio: Option<f32>,
+        znear: f32,
+        zfar: Option<f32>,
+    },
+    Orthographic {
+        xmag: f32,
+        ymag: f32,
+        znear: f32,
+        zfar: f32,
+    },
+}
+
+/// A `KHR_lights_punctual` light. Directional and spot lights shine down the local -Z axis.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneLight {
+    pub name: String,
+    /// The node placing the light.
+    pub node_name: String,
+    pub translation: Vec3,
+    pub rotation: Quat,
+    /// Linear RGB.
+    pub color: Vec3,
+    /// Lux for directional lights, candela for point and spot lights.
+    pub intensity: f32,
+    /// Distance at which the light reaches zero; unlimited when missing.
+    pub range: Option<f32>,
+    pub kind: LightKind,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum LightKind {
+    Directional,
+    Point,
+    /// Cone angles in radians from the light's axis.
+    Spot { inner_cone_angle: f32, outer_cone_angle: f32 },
+}
+
+/// `ANIMATED_MODEL_TABLE` key of one skin of a source holding several; a source with a single
+/// skin keeps the plain model name.
+pub fn skin_key(model: &str, skin: &str) -> String {
+    format!("{model}/{skin}")
+}
+
+/// `ANIMATION_TABLE` key of a clip: clips are namespaced by the model they came with, since
+/// clip names such as "Armature|running|baselayer" repeat across files.
+pub fn animation_key(model: &str, clip: &str) -> String {
+    format!("{model}/{clip}")
+}
+
+/// A `TEXTURE_TABLE` entry. Identical images imported by several models share one blob.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TextureRef {
+    pub blob: String,
+}
+
+/// Bookkeeping for one file under the assets folder: the hash of its contents at
+/// the time it was baked and every table key it produced.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SourceManifestEntry {
+    /// Hash of the source with its `dependencies`.
+    pub content_hash: [u8; 32],
+    /// Hash of the source alone, including any files it names in a way the baker reads itself.
+    /// While it is unchanged, `dependencies` still lists what the source reads.
+    pub source_hash: [u8; 32],
+    /// Files the source reads that only its importer can name, such as the textures of an FBX,
+    /// relative to the source's folder.
+    pub dependencies: Vec<String>,
+    pub importer: String,
+    /// The baker settings it was processed with; changing them re-bakes the source.
+    pub settings: String,
+    pub models: Vec<String>,
+    pub animated_models: Vec<String>,
+    pub animations: Vec<String>,
+    pub textures: Vec<String>,
+    pub materials: Vec<String>,
+    pub composites: Vec<String>,
+    pub prefabs: Vec<String>,
+    pub scenes: Vec<String>,
+}
+
+/// The single entry of `META_TABLE`, stored under `DatabaseMeta::KEY`. Databases baked
+/// before it existed have no entry and count as format version 0.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DatabaseMeta {
+    pub format_version: u32,
+    pub baker_version: String,
+}
+
+impl DatabaseMeta {
+    pub const KEY: &'static str = "meta";
+}
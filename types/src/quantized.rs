@@ -0,0 +1,225 @@
+//! Compact vertex layouts chosen per source at bake time, and the CPU side of their encoding.
+//! The shaders decode the same layouts; keep the two in step. Compressed clips store their
+//! rotations in the quaternion layout at the end.
+
+use glam::{Quat, Vec2, Vec3, Vec4};
+use half::f16;
+use serde::{Deserialize, Serialize};
+
+use crate::{AABB, Interpolation, SkinnedVertex, Vertex};
+
+/// How a mesh stores its vertices. The discriminant is what the shaders switch on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u32)]
+pub enum VertexFormat {
+    /// `Vertex` or `SkinnedVertex`, all attributes as f32.
+    #[default]
+    Full = 0,
+    /// `QuantizedVertex` or `QuantizedSkinnedVertex`.
+    Quantized = 1,
+}
+
+/// 28 bytes: the position as unorm16 within the mesh AABB, the normal and tangent
+/// octahedral-encoded as snorm16, both UV sets as half floats and the color as unorm8.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct QuantizedVertex {
+    /// x, y, z and the tangent's bitangent sign: 0 for +1, 65535 for -1.
+    pub position: [u16; 4],
+    pub normal: [i16; 2],
+    pub uv: [u16; 2],
+    pub tangent: [i16; 2],
+    pub uv1: [u16; 2],
+    pub color: [u8; 4],
+}
+
+/// 40 bytes: a `QuantizedVertex` followed by u8 bone indices and unorm16 weights.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct QuantizedSkinnedVertex {
+    pub position: [u16; 4],
+    pub normal: [i16; 2],
+    pub uv: [u16; 2],
+    pub tangent: [i16; 2],
+    pub uv1: [u16; 2],
+    pub color: [u8; 4],
+    pub bone_indices: [u8; 4],
+    pub bone_weights: [u16; 4],
+}
+
+impl QuantizedVertex {
+    pub fn encode(vertex: &Vertex, aabb: &AABB) -> Self {
+        Self {
+            position: encode_position(vertex.position, vertex.tangent.w, aabb),
+            normal: encode_normal(vertex.normal.truncate()),
+            uv: encode_uv(vertex.uv),
+            tangent: encode_normal(vertex.tangent.truncate()),
+            uv1: encode_uv(vertex.uv1),
+            color: encode_color(vertex.color),
+        }
+    }
+
+    pub fn decode(&self, aabb: &AABB) -> Vertex {
+        Vertex {
+            position: decode_position(self.position, aabb),
+            normal: decode_normal(self.normal).extend(0.0),
+            uv: decode_uv(self.uv),
+            uv1: decode_uv(self.uv1),
+            tangent: decode_tangent(self.tangent, self.position),
+            color: decode_color(self.color),
+        }
+    }
+}
+
+impl QuantizedSkinnedVertex {
+    /// `None` when a bone index does not fit in a byte.
+    pub fn encode(vertex: &SkinnedVertex, aabb: &AABB) -> Option<Self> {
+        let mut bone_indices = [0; 4];
+        for (quantized, &index) in bone_indices.iter_mut().zip(&vertex.bone_indices) {
+            *quantized = u8::try_from(index).ok()?;
+        }
+        Some(Self {
+            position: encode_position(vertex.position, vertex.tangent.w, aabb),
+            normal: encode_normal(vertex.normal.truncate()),
+            uv: encode_uv(vertex.uv),
+            tangent: encode_normal(vertex.tangent.truncate()),
+            uv1: encode_uv(vertex.uv1),
+            color: encode_color(vertex.color),
+            bone_indices,
+            bone_weights: vertex.bone_weights.map(encode_unorm16),
+        })
+    }
+
+    pub fn decode(&self, aabb: &AABB) -> SkinnedVertex {
+        SkinnedVertex {
+            position: decode_position(self.position, aabb),
+            normal: decode_normal(self.normal).extend(0.0),
+            uv: decode_uv(self.uv),
+            uv1: decode_uv(self.uv1),
+            bone_indices: self.bone_indices.map(u32::from),
+            bone_weights: self.bone_weights.map(decode_unorm16),
+            tangent: decode_tangent(self.tangent, self.position),
+            color: decode_color(self.color),
+        }
+    }
+}
+
+fn encode_unorm16(x: f32) -> u16 {
+    (x.clamp(0.0, 1.0) * 65535.0).round() as u16
+}
+
+fn decode_unorm16(x: u16) -> f32 {
+    x as f32 / 65535.0
+}
+
+fn encode_snorm16(x: f32) -> i16 {
+    (x.clamp(-1.0, 1.0) * 32767.0).round() as i16
+}
+
+fn decode_snorm16(x: i16) -> f32 {
+    (x as f32 / 32767.0).max(-1.0)
+}
+
+fn encode_position(position: Vec4, bitangent_sign: f32, aabb: &AABB) -> [u16; 4] {
+    let extent = (aabb.max - aabb.min).truncate();
+    let offset = (position - aabb.min).truncate();
+    // A flat axis has no extent to spread over; every vertex sits at the minimum.
+    let relative = Vec3::select(extent.cmpgt(Vec3::ZERO), offset / extent, Vec3::ZERO);
+    let [x, y, z] = relative.to_array().map(encode_unorm16);
+    [x, y, z, if bitangent_sign < 0.0 { u16::MAX } else { 0 }]
+}
+
+fn decode_position([x, y, z, _]: [u16; 4], aabb: &AABB) -> Vec4 {
+    let relative = Vec3::new(decode_unorm16(x), decode_unorm16(y), decode_unorm16(z));
+    let extent = (aabb.max - aabb.min).truncate();
+    (aabb.min.truncate() + relative * extent).extend(1.0)
+}
+
+/// Projects the unit normal onto the octahedron and folds the lower half over the upper.
+fn encode_normal(normal: Vec3) -> [i16; 2] {
+    let n = normal / (normal.x.abs() + normal.y.abs() + normal.z.abs()).max(f32::EPSILON);
+    let folded = if n.z < 0.0 {
+        (Vec2::ONE - Vec2::new(n.y, n.x).abs()) * Vec2::new(n.x, n.y).signum()
+    } else {
+        Vec2::new(n.x, n.y)
+    };
+    [encode_snorm16(folded.x), encode_snorm16(folded.y)]
+}
+
+fn decode_normal([x, y]: [i16; 2]) -> Vec3 {
+    let (x, y) = (decode_snorm16(x), decode_snorm16(y));
+    let mut n = Vec3::new(x, y, 1.0 - x.abs() - y.abs());
+    let t = (-n.z).max(0.0);
+    n.x += if n.x >= 0.0 { -t } else { t };
+    n.y += if n.y >= 0.0 { -t } else { t };
+    n.normalize_or_zero()
+}
+
+fn decode_tangent(tangent: [i16; 2], [_, _, _, sign]: [u16; 4]) -> Vec4 {
+    decode_normal(tangent).extend(if sign == u16::MAX { -1.0 } else { 1.0 })
+}
+
+fn encode_uv(uv: Vec2) -> [u16; 2] {
+    [f16::from_f32(uv.x).to_bits(), f16::from_f32(uv.y).to_bits()]
+}
+
+fn decode_uv([u, v]: [u16; 2]) -> Vec2 {
+    Vec2::new(f16::from_bits(u).to_f32(), f16::from_bits(v).to_f32())
+}
+
+fn encode_color(color: Vec4) -> [u8; 4] {
+    color.to_array().map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+fn decode_color(color: [u8; 4]) -> Vec4 {
+    Vec4::from(color.map(|c| c as f32 / 255.0))
+}
+
+/// 6 bytes: a unit quaternion encoded as its smallest three components, which lie within
+/// ±1/√2, as 15-bit unorms over that range. The index of the dropped largest component takes
+/// the top bits of the first two words; it is made positive, since `q` and `-q` rotate alike.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackedQuat(pub [u16; 3]);
+
+const SMALLEST_THREE_RANGE: f32 = std::f32::consts::FRAC_1_SQRT_2;
+const UNORM15_MAX: f32 = 32767.0;
+
+impl PackedQuat {
+    pub fn encode(rotation: Quat) -> Self {
+        let mut q = rotation.normalize().to_array();
+        let largest = (0..4).max_by(|&a, &b| q[a].abs().total_cmp(&q[b].abs())).unwrap_or(3);
+        if q[largest] < 0.0 {
+            q = q.map(|c| -c);
+        }
+        let mut words = [0u16; 3];
+        for (word, c) in words.iter_mut().zip((0..4).filter(|&i| i != largest).map(|i| q[i])) {
+            let unit = (c / SMALLEST_THREE_RANGE * 0.5 + 0.5).clamp(0.0, 1.0);
+            *word = (unit * UNORM15_MAX).round() as u16;
+        }
+        words[0] |= ((largest & 1) as u16) << 15;
+        words[1] |= ((largest >> 1) as u16) << 15;
+        Self(words)
+    }
+
+    pub fn decode(self) -> Quat {
+        let [w0, w1, w2] = self.0;
+        let largest = ((w0 >> 15) | ((w1 >> 15) << 1)) as usize;
+        let small = [w0, w1, w2].map(|w| ((w & 0x7fff) as f32 / UNORM15_MAX * 2.0 - 1.0) * SMALLEST_THREE_RANGE);
+        let rest = 1.0 - small.iter().map(|c| c * c).sum::<f32>();
+        let mut q = [0.0; 4];
+        let mut small = small.into_iter();
+        for (i, c) in q.iter_mut().enumerate() {
+            *c = if i == largest { rest.max(0.0).sqrt() } else { small.next().unwrap_or(0.0) };
+        }
+        Quat::from_array(q).normalize()
+    }
+}
+
+/// A `RotationKey` of a compressed clip. Only step and linear keys are quantized, so there
+/// are no tangents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuantizedRotationKey {
+    pub time: f32,
+    pub rotation: PackedQuat,
+    pub interpolation: Interpolation,
+}
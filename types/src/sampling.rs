@@ -0,0 +1,124 @@
+//! Evaluation of keyframe tracks per the glTF sampler rules: step, linear and cubic Hermite
+//! segments, with the nearest key held outside a track. Times are in ticks.
+
+use glam::{Quat, Vec3, Vec4};
+
+use crate::{Interpolation, MorphKey, PositionKey, QuantizedRotationKey, RotationKey, ScaleKey};
+
+/// Where `time_in_ticks` falls among keys sorted by time: on a single key to hold, or inside
+/// the segment starting at `prev` with the fraction `t` covered and its length `dt` in ticks.
+enum KeySpan {
+    Hold(usize),
+    Segment { prev: usize, t: f32, dt: f32 },
+}
+
+fn find_span<K>(time_in_ticks: f64, keys: &[K], key_time: impl Fn(&K) -> f64) -> Option<KeySpan> {
+    let (first, last) = (keys.first()?, keys.last()?);
+    // Before the first key and after the last the nearest value is held.
+    if time_in_ticks <= key_time(first) {
+        return Some(KeySpan::Hold(0));
+    }
+    if time_in_ticks >= key_time(last) {
+        return Some(KeySpan::Hold(keys.len() - 1));
+    }
+    let next = keys.partition_point(|k| key_time(k) <= time_in_ticks);
+    let prev = next - 1;
+    let dt = key_time(&keys[next]) - key_time(&keys[prev]);
+    Some(KeySpan::Segment {
+        prev,
+        t: ((time_in_ticks - key_time(&keys[prev])) / dt) as f32,
+        dt: dt as f32,
+    })
+}
+
+/// The glTF cubic Hermite spline between `p0` and `p1`, with tangents already scaled by the
+/// segment length.
+pub fn hermite<T>(p0: T, m0: T, p1: T, m1: T, t: f32) -> T
+where
+    T: std::ops::Mul<f32, Output = T> + std::ops::Add<Output = T>,
+{
+    let t2 = t * t;
+    let t3 = t2 * t;
+    p0 * (2.0 * t3 - 3.0 * t2 + 1.0)
+        + m0 * (t3 - 2.0 * t2 + t)
+        + p1 * (-2.0 * t3 + 3.0 * t2)
+        + m1 * (t3 - t2)
+}
+
+pub fn sample_position(time_in_ticks: f64, keys: &[PositionKey]) -> Option<Vec3> {
+    let (prev, t, dt) = match find_span(time_in_ticks, keys, |k| k.time)? {
+        KeySpan::Hold(i) => return Some(keys[i].position),
+        KeySpan::Segment { prev, t, dt } => (prev, t, dt),
+    };
+    let (a, b) = (&keys[prev], &keys[prev + 1]);
+    Some(match a.interpolation {
+        Interpolation::Step => a.position,
+        Interpolation::Linear => a.position.lerp(b.position, t),
+        Interpolation::CubicSpline => hermite(a.position, a.out_tangent * dt, b.position, b.in_tangent * dt, t),
+    })
+}
+
+pub fn sample_rotation(time_in_ticks: f64, keys: &[RotationKey]) -> Option<Quat> {
+    let (prev, t, dt) = match find_span(time_in_ticks, keys, |k| k.time)? {
+        KeySpan::Hold(i) => return Some(keys[i].rotation),
+        KeySpan::Segment { prev, t, dt } => (prev, t, dt),
+    };
+    let (a, b) = (&keys[prev], &keys[prev + 1]);
+    Some(match a.interpolation {
+        Interpolation::Step => a.rotation,
+        // Slerp takes the shorter arc, so keys on opposite hemispheres do not spin the long way.
+        Interpolation::Linear => a.rotation.slerp(b.rotation, t),
+        // The spline runs through quaternion space and is brought back onto the unit sphere.
+        Interpolation::CubicSpline => {
+            let q = hermite(Vec4::from(a.rotation), a.out_tangent * dt, Vec4::from(b.rotation), b.in_tangent * dt, t);
+            Quat::from_vec4(q).normalize()
+        }
+    })
+}
+
+/// Compressed clips only quantize step and linear tracks, so there is no spline case.
+pub fn sample_quantized_rotation(time_in_ticks: f64, keys: &[QuantizedRotationKey]) -> Option<Quat> {
+    let (prev, t) = match find_span(time_in_ticks, keys, |k| k.time as f64)? {
+        KeySpan::Hold(i) => return Some(keys[i].rotation.decode()),
+        KeySpan::Segment { prev, t, .. } => (prev, t),
+    };
+    let (a, b) = (&keys[prev], &keys[prev + 1]);
+    Some(match a.interpolation {
+        Interpolation::Step => a.rotation.decode(),
+        _ => a.rotation.decode().slerp(b.rotation.decode(), t),
+    })
+}
+
+pub fn sample_scale(time_in_ticks: f64, keys: &[ScaleKey]) -> Option<Vec3> {
+    let (prev, t, dt) = match find_span(time_in_ticks, keys, |k| k.time)? {
+        KeySpan::Hold(i) => return Some(keys[i].scale),
+        KeySpan::Segment { prev, t, dt } => (prev, t, dt),
+    };
+    let (a, b) = (&keys[prev], &keys[prev + 1]);
+    Some(match a.interpolation {
+        Interpolation::Step => a.scale,
+        Interpolation::Linear => a.scale.lerp(b.scale, t),
+        Interpolation::CubicSpline => hermite(a.scale, a.out_tangent * dt, b.scale, b.in_tangent * dt, t),
+    })
+}
+
+pub fn sample_weights(time_in_ticks: f64, keys: &[MorphKey]) -> Option<Vec<f32>> {
+    let (prev, t, dt) = match find_span(time_in_ticks, keys, |k| k.time)? {
+        KeySpan::Hold(i) => return Some(keys[i].weights.clone()),
+        KeySpan::Segment { prev, t, dt } => (prev, t, dt),
+    };
+    let (a, b) = (&keys[prev], &keys[prev + 1]);
+    let pairs = a.weights.iter().zip(&b.weights);
+    Some(match a.interpolation {
+        Interpolation::Step => a.weights.clone(),
+        Interpolation::Linear => pairs.map(|(w0, w1)| w0 + (w1 - w0) * t).collect(),
+        Interpolation::CubicSpline => pairs
+            .enumerate()
+            .map(|(i, (&w0, &w1))| {
+                let m0 = a.out_tangents.get(i).copied().unwrap_or(0.0) * dt;
+                let m1 = b.in_tangents.get(i).copied().unwrap_or(0.0) * dt;
+                hermite(w0, m0, w1, m1, t)
+            })
+            .collect(),
+    })
+}